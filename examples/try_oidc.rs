@@ -0,0 +1,41 @@
+#![allow(deprecated)]
+
+use std::io::{stdin, stdout, Write};
+
+use fedora::{OIDCSessionKind, Session};
+use url::Url;
+
+fn prompt_code() -> String {
+    let mut code = String::new();
+
+    print!("Authorization code: ");
+    stdout().flush().unwrap();
+    stdin().read_line(&mut code).unwrap();
+
+    code.trim().to_string()
+}
+
+#[tokio::main]
+async fn main() -> Result<(), String> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
+
+    let bodhi = Url::parse("https://bodhi.stg.fedoraproject.org/").unwrap();
+    let login = Session::oidc_auth(OIDCSessionKind::Staging, "bodhi-client", &bodhi)
+        .build()
+        .await
+        .map_err(|error| error.to_string())?;
+
+    println!("Visit this URL to log in: {}", login.authorization_url());
+
+    // read authorization code displayed by the OIDC provider from stdin
+    let code = prompt_code();
+    let session = login.login(&code).await;
+
+    match session {
+        Ok(_session) => {
+            println!("Successfully logged in.");
+            Ok(())
+        },
+        Err(error) => Err(error.to_string()),
+    }
+}
@@ -1,84 +1,52 @@
 //! This module contains an implementation for building anonymous [`Session`]s.
 
-use std::time::Duration;
-
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
 use reqwest::redirect::Policy;
-use reqwest::Client;
 
+use crate::config::{client_config_methods, ClientConfig};
 use crate::session::Session;
-use crate::{DEFAULT_TIMEOUT, FEDORA_USER_AGENT};
+use crate::tls::TlsError;
 
 /// This type encapsulates the (optional) arguments that are required for building an anonymous
 /// session.
 #[derive(Debug, Default)]
 pub struct AnonymousSessionBuilder<'a> {
-    /// optional override of the default timeout duration
-    timeout: Option<Duration>,
-    /// optional override of the default User-Agent header
-    user_agent: Option<&'a str>,
+    /// optional overrides of the default client settings
+    config: ClientConfig<'a>,
 }
 
 impl<'a> AnonymousSessionBuilder<'a> {
     /// This method constructs a new [`AnonymousSessionBuilder`] instance.
     pub fn new() -> Self {
         AnonymousSessionBuilder {
-            timeout: None,
-            user_agent: None,
+            config: ClientConfig::default(),
         }
     }
 
-    /// Override the default request timeout duration.
-    #[must_use]
-    pub fn timeout(mut self, timeout: Duration) -> Self {
-        self.timeout = Some(timeout);
-        self
-    }
-
-    /// Override the default User-Agent header.
-    #[must_use]
-    pub fn user_agent(mut self, user_agent: &'a str) -> Self {
-        self.user_agent = Some(user_agent);
-        self
-    }
+    client_config_methods!('a);
 
     /// This method consumes the [`AnonymousSessionBuilder`] and returns a [`Session`] with
     /// custom timeout and User-Agent header settings.
     ///
-    /// Note: This method will panic if the network stack cannot be initialized.
+    /// Note: This method will panic if the network stack cannot be initialized, or if the TLS
+    /// backend rejects the configured client certificate or CA certificates. Use
+    /// [`AnonymousSessionBuilder::try_build`] to handle the latter case.
     pub fn build(self) -> Session {
-        let timeout = match self.timeout {
-            Some(timeout) => timeout,
-            None => DEFAULT_TIMEOUT,
-        };
-
-        let user_agent = match self.user_agent {
-            Some(user_agent) => user_agent,
-            None => FEDORA_USER_AGENT,
-        };
-
-        // set default headers for our requests
-        // - User Agent
-        // - Accept: application/json
-        let mut headers = HeaderMap::new();
+        self.try_build().expect("Failed to initialize the network stack.")
+    }
 
-        headers.insert(
-            USER_AGENT,
-            HeaderValue::from_str(user_agent).expect("Failed to parse hardcoded HTTP headers."),
-        );
-        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    /// This method consumes the [`AnonymousSessionBuilder`] and returns a [`Session`] with
+    /// custom timeout and User-Agent header settings, or an error if the TLS backend rejects the
+    /// configured client certificate or CA certificates.
+    ///
+    /// Note: This method will panic if the network stack cannot be initialized.
+    pub fn try_build(self) -> Result<Session, TlsError> {
+        let template = self.config.resolve();
 
         // construct reqwest session with:
         // - custom default headers
         // - no-redirects policy
-        let client = Client::builder()
-            .default_headers(headers)
-            .cookie_store(true)
-            .timeout(timeout)
-            .redirect(Policy::none())
-            .build()
-            .expect("Failed to initialize the network stack.");
+        let client = template.build(template.client().cookie_store(true).redirect(Policy::none()))?;
 
-        Session { client }
+        Ok(template.session(client))
     }
 }
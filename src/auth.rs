@@ -0,0 +1,156 @@
+//! This module contains the definition of the [`Authenticator`] trait, which is implemented by all
+//! supported authentication methods, and which can be implemented by other crates to plug custom
+//! authentication methods into a [`Session`].
+
+use std::error::Error;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use reqwest::redirect::Policy;
+use reqwest::{Client, Request, Response, StatusCode};
+
+use crate::config::{client_config_methods, ClientConfig};
+use crate::openid::CachingJar;
+use crate::session::{Session, SessionError};
+
+/// This type alias is used for the futures that are returned by [`Authenticator`] methods (the
+/// trait can not use `async fn` while supporting older versions of Rust).
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// This type alias is used for errors that are returned by [`Authenticator`] implementations.
+pub type AuthError = Box<dyn Error + Send + Sync>;
+
+/// This trait is implemented by all authentication methods that are supported for [`Session`]s.
+///
+/// All methods have default implementations that do nothing, so implementations only need to
+/// override the methods that are relevant for them:
+///
+/// - [`Authenticator::login`] is called once when building a session (for example, to submit a
+///   login form and store session cookies in the cookie jar of the session),
+/// - [`Authenticator::authorize`] is called for every request that is sent with
+///   [`Session::execute`] (for example, to add an `Authorization` header),
+/// - [`Authenticator::recover`] is called when a request was rejected (by default, with "401
+///   Unauthorized", see [`Authenticator::is_rejected`]), for example, to refresh expired
+///   credentials, and the request is retried once if it returns `true`,
+/// - [`Authenticator::logout`] is called by [`Session::logout`] (for example, to revoke tokens).
+///
+/// ```
+/// use fedora::{AuthError, Authenticator, BoxFuture};
+/// use reqwest::header::{HeaderValue, AUTHORIZATION};
+/// use reqwest::Request;
+///
+/// #[derive(Debug)]
+/// struct ProxyToken(String);
+///
+/// impl Authenticator for ProxyToken {
+///     fn authorize<'a>(
+///         &'a self,
+///         request: &'a mut Request,
+///     ) -> BoxFuture<'a, Result<(), AuthError>> {
+///         Box::pin(async move {
+///             let value = HeaderValue::from_str(&format!("Proxy {}", self.0))?;
+///             request.headers_mut().insert(AUTHORIZATION, value);
+///             Ok(())
+///         })
+///     }
+/// }
+/// ```
+pub trait Authenticator: Debug + Send + Sync {
+    /// This method performs the initial login with the given client, which does not follow
+    /// redirects, and which shares its cookie jar with the client of the resulting session.
+    fn login<'a>(&'a self, client: &'a Client) -> BoxFuture<'a, Result<(), AuthError>> {
+        let _ = client;
+        Box::pin(async { Ok(()) })
+    }
+
+    /// This method adds credentials to an outgoing request.
+    fn authorize<'a>(&'a self, request: &'a mut Request) -> BoxFuture<'a, Result<(), AuthError>> {
+        let _ = request;
+        Box::pin(async { Ok(()) })
+    }
+
+    /// This method determines whether the server rejected the credentials of a request, in which
+    /// case [`Authenticator::recover`] is called. The default implementation checks for
+    /// "401 Unauthorized" responses.
+    fn is_rejected(&self, response: &Response) -> bool {
+        response.status() == StatusCode::UNAUTHORIZED
+    }
+
+    /// This method is called when the server rejected a request (see
+    /// [`Authenticator::is_rejected`]). It can update the credentials of the given copy of the
+    /// request, and return `true` if the request should be retried.
+    fn recover<'a>(
+        &'a self,
+        request: &'a mut Request,
+        response: &'a Response,
+    ) -> BoxFuture<'a, Result<bool, AuthError>> {
+        let _ = (request, response);
+        Box::pin(async { Ok(false) })
+    }
+
+    /// This method is called by [`Session::logout`], and can invalidate the credentials on the
+    /// server (for example, by revoking tokens).
+    fn logout<'a>(&'a self, client: &'a Client) -> BoxFuture<'a, Result<(), AuthError>> {
+        let _ = client;
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// This type implements [`Authenticator`] for anonymous sessions, and does nothing.
+#[derive(Debug, Default)]
+pub struct Anonymous;
+
+impl Authenticator for Anonymous {}
+
+/// This type encapsulates the (optional) arguments that are required for building a session that
+/// is authenticated with an arbitrary [`Authenticator`].
+#[derive(Debug)]
+pub struct SessionBuilder<'a> {
+    authenticator: Arc<dyn Authenticator>,
+    config: ClientConfig<'a>,
+}
+
+impl<'a> SessionBuilder<'a> {
+    /// This method constructs a new [`SessionBuilder`] instance for the given authentication
+    /// method.
+    pub fn new<A: Authenticator + 'static>(authenticator: A) -> Self {
+        SessionBuilder {
+            authenticator: Arc::new(authenticator),
+            config: ClientConfig::default(),
+        }
+    }
+
+    client_config_methods!('a);
+
+    /// This method consumes the [`SessionBuilder`], logs in with the [`Authenticator`], and returns
+    /// a [`Session`] that uses the [`Authenticator`] for all requests.
+    ///
+    /// If the TLS backend rejects the configured client certificate or CA certificates, an error
+    /// is returned. Otherwise, this method will panic if the network stack cannot be initialized.
+    pub async fn build(self) -> Result<Session, SessionError> {
+        let template = self.config.resolve();
+
+        let jar = Arc::new(CachingJar::empty());
+
+        // construct reqwest session for authentication with:
+        // - custom default headers
+        // - no-redirects policy
+        let login_client = template.build(template.client().cookie_provider(jar.clone()).redirect(Policy::none()))?;
+
+        self.authenticator
+            .login(&login_client)
+            .await
+            .map_err(|error| SessionError::Authentication { error })?;
+
+        // construct new client with default redirect handling, but keep all cookies
+        let client = template.build(template.client().cookie_provider(jar.clone()))?;
+
+        Ok(Session {
+            auth: Some(self.authenticator),
+            cookies: Some(jar),
+            ..template.session(client)
+        })
+    }
+}
@@ -0,0 +1,289 @@
+//! This module contains a typed client for the [Bodhi](https://bodhi.fedoraproject.org) REST API,
+//! which is built on top of a [`Session`].
+//!
+//! Queries for updates, builds, buildroot overrides, releases, comments, and users can be sent with
+//! anonymous sessions. Actions that modify data (creating and editing updates, posting comments,
+//! and changing the requested status of updates) need a session that is authenticated with the
+//! Fedora identity provider (for example, with [`Session::oidc_auth`] or [`Session::openid_auth`]).
+//!
+//! ```ignore
+//! use fedora::bodhi::{BodhiClient, UpdateQuery, UpdateStatus};
+//! use fedora::Session;
+//!
+//! let bodhi = BodhiClient::new(Session::anonymous().build());
+//!
+//! let query = UpdateQuery::new().package("rust-fedora").status(UpdateStatus::Testing);
+//! let updates = bodhi.all(&query).await?;
+//! ```
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+use crate::client::{api_error, fixed_url, ApiClient, ApiError, ApiPath, PathError};
+use crate::openid::excerpt;
+use crate::session::Session;
+
+mod actions;
+pub use actions::{Karma, NewUpdate};
+
+mod query;
+pub use query::{BuildQuery, CommentQuery, OverrideQuery, Query, ReleaseQuery, UpdateQuery, UserQuery};
+
+mod types;
+pub use types::{
+    Bug,
+    Build,
+    Comment,
+    Group,
+    Override,
+    Release,
+    ReleaseState,
+    Update,
+    UpdateRequest,
+    UpdateSeverity,
+    UpdateStatus,
+    UpdateType,
+    User,
+};
+
+/// base URL of the production instance of Bodhi
+const FEDORA_BODHI_URL: &str = "https://bodhi.fedoraproject.org/";
+/// base URL of the staging instance of Bodhi
+const FEDORA_BODHI_STG_URL: &str = "https://bodhi.stg.fedoraproject.org/";
+
+api_error! {
+    service: "Bodhi",
+    /// This collection of errors is returned for failures when sending requests to Bodhi.
+    pub enum BodhiError {
+        Status {},
+        /// This error represents an issue with constructing the URL of an API endpoint.
+        #[error("Failed to construct URL: {error}")]
+        UrlParsing {
+            /// The inner error contains the error that occurred when parsing the URL.
+            #[from]
+            error: url::ParseError,
+        },
+        /// This error is returned when a name can not be used in the path of an API endpoint
+        /// (because it is empty, or because it is "." or "..").
+        #[error("Invalid name in API path: {name:?}")]
+        InvalidName {
+            /// name that was rejected
+            name: String,
+        },
+        /// This error is returned when a query parameter could not be serialized.
+        #[error("Failed to serialize query parameter: {error}")]
+        Serialization {
+            /// The inner error contains the serialization error message from
+            /// [`serde_plain`](https://docs.rs/serde_plain).
+            error: serde_plain::Error,
+        },
+    }
+}
+
+impl ApiError for BodhiError {
+    fn from_response(status: StatusCode, body: &str) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorMessage {
+            name: Option<String>,
+            description: String,
+        }
+
+        #[derive(Deserialize)]
+        struct ErrorResponse {
+            errors: Vec<ErrorMessage>,
+        }
+
+        let message = match serde_json::from_str::<ErrorResponse>(body) {
+            Ok(response) if !response.errors.is_empty() => response
+                .errors
+                .into_iter()
+                .map(|error| match error.name {
+                    Some(name) if !name.is_empty() => format!("{}: {}", name, error.description),
+                    _ => error.description,
+                })
+                .collect::<Vec<String>>()
+                .join("; "),
+            _ => excerpt(body),
+        };
+
+        BodhiError::Status { status, message }
+    }
+}
+
+impl From<PathError> for BodhiError {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::Base(error) => BodhiError::UrlParsing { error },
+            PathError::Segment(name) => BodhiError::InvalidName { name },
+        }
+    }
+}
+
+/// This type represents one page of results of a query.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Page<T> {
+    /// results on this page
+    pub items: Vec<T>,
+    /// number of this page (starting at 1)
+    pub page: u32,
+    /// total number of pages
+    pub pages: u32,
+    /// number of results per page
+    pub rows_per_page: u32,
+    /// total number of results
+    pub total: u64,
+}
+
+impl<T: DeserializeOwned> Page<T> {
+    /// This method extracts a page of results from a response, where the results are stored under
+    /// the given key.
+    fn from_value(mut value: serde_json::Value, key: &str) -> Result<Self, BodhiError> {
+        #[derive(Deserialize)]
+        struct PageInfo {
+            page: u32,
+            pages: u32,
+            rows_per_page: u32,
+            total: u64,
+        }
+
+        let items = match value.get_mut(key) {
+            Some(items) => serde_json::from_value(items.take())?,
+            None => Vec::new(),
+        };
+        let info: PageInfo = serde_json::from_value(value)?;
+
+        Ok(Page {
+            items,
+            page: info.page,
+            pages: info.pages,
+            rows_per_page: info.rows_per_page,
+            total: info.total,
+        })
+    }
+}
+
+/// This type is a typed client for the Bodhi REST API.
+///
+/// All requests are sent with [`Session::send`], so they are authenticated by the [`Session`] (if
+/// it is authenticated), and they are retried and rate-limited according to its settings.
+#[derive(Debug)]
+pub struct BodhiClient {
+    api: ApiClient<BodhiError>,
+}
+
+impl BodhiClient {
+    /// This method constructs a new [`BodhiClient`] for the production instance of Bodhi.
+    pub fn new(session: Session) -> Self {
+        BodhiClient::with_url(session, fixed_url(FEDORA_BODHI_URL))
+    }
+
+    /// This method constructs a new [`BodhiClient`] for the staging instance of Bodhi.
+    pub fn staging(session: Session) -> Self {
+        BodhiClient::with_url(session, fixed_url(FEDORA_BODHI_STG_URL))
+    }
+
+    /// This method constructs a new [`BodhiClient`] for the instance of Bodhi at the given URL.
+    pub fn with_url(session: Session, url: Url) -> Self {
+        BodhiClient {
+            api: ApiClient::rest(session, url, &[]),
+        }
+    }
+
+    /// This method returns a reference to the wrapped [`Session`].
+    pub fn session(&self) -> &Session {
+        self.api.session()
+    }
+
+    /// This method returns the base URL of the Bodhi instance.
+    pub fn url(&self) -> &Url {
+        self.api.url()
+    }
+
+    /// This method returns the given page (starting at 1) of results of a query.
+    pub async fn page<Q: Query>(&self, query: &Q, page: u32) -> Result<Page<Q::Item>, BodhiError> {
+        let mut params = query.params()?;
+        params.push(("page", page.max(1).to_string()));
+
+        let value: serde_json::Value = self.api.get(&ApiPath::dir(&[Q::PATH]), &params).await?;
+        Page::from_value(value, Q::KEY)
+    }
+
+    /// This method returns all results of a query, by requesting all pages one after another.
+    pub async fn all<Q: Query>(&self, query: &Q) -> Result<Vec<Q::Item>, BodhiError> {
+        let mut items = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let mut result = self.page(query, page).await?;
+            log::debug!("Received page {} of {} for Bodhi query.", result.page, result.pages);
+
+            let done = result.items.is_empty() || result.page >= result.pages;
+            items.append(&mut result.items);
+
+            if done {
+                return Ok(items);
+            }
+            page += 1;
+        }
+    }
+
+    /// This method returns the update with the given alias (or `None` if it does not exist).
+    pub async fn update(&self, alias: &str) -> Result<Option<Update>, BodhiError> {
+        #[derive(Deserialize)]
+        struct UpdateResponse {
+            update: Update,
+        }
+
+        let response: Option<UpdateResponse> = self.api.get_optional(&ApiPath::file(&["updates", alias]), &[]).await?;
+        Ok(response.map(|response| response.update))
+    }
+
+    /// This method returns the build with the given NVR (or `None` if it is not known to Bodhi).
+    pub async fn build(&self, nvr: &str) -> Result<Option<Build>, BodhiError> {
+        self.api.get_optional(&ApiPath::file(&["builds", nvr]), &[]).await
+    }
+
+    /// This method returns the buildroot override for the build with the given NVR (or `None` if it
+    /// does not exist).
+    pub async fn build_override(&self, nvr: &str) -> Result<Option<Override>, BodhiError> {
+        #[derive(Deserialize)]
+        struct OverrideResponse {
+            r#override: Override,
+        }
+
+        let response: Option<OverrideResponse> =
+            self.api.get_optional(&ApiPath::file(&["overrides", nvr]), &[]).await?;
+        Ok(response.map(|response| response.r#override))
+    }
+
+    /// This method returns the release with the given name (or `None` if it does not exist).
+    pub async fn release(&self, name: &str) -> Result<Option<Release>, BodhiError> {
+        self.api.get_optional(&ApiPath::file(&["releases", name]), &[]).await
+    }
+
+    /// This method returns the comment with the given ID (or `None` if it does not exist).
+    pub async fn comment_by_id(&self, id: u64) -> Result<Option<Comment>, BodhiError> {
+        #[derive(Deserialize)]
+        struct CommentResponse {
+            comment: Comment,
+        }
+
+        let id = id.to_string();
+        let response: Option<CommentResponse> = self.api.get_optional(&ApiPath::file(&["comments", &id]), &[]).await?;
+        Ok(response.map(|response| response.comment))
+    }
+
+    /// This method returns the user with the given name (or `None` if it does not exist).
+    pub async fn user(&self, name: &str) -> Result<Option<User>, BodhiError> {
+        #[derive(Deserialize)]
+        struct UserResponse {
+            user: User,
+        }
+
+        let response: Option<UserResponse> = self.api.get_optional(&ApiPath::file(&["users", name]), &[]).await?;
+        Ok(response.map(|response| response.user))
+    }
+}
@@ -0,0 +1,245 @@
+//! This module contains the authenticated actions of the Bodhi REST API (creating and editing
+//! updates, posting comments, and changing the requested status of updates).
+//!
+//! All actions are protected with a CSRF token, which is requested from Bodhi before every action.
+//! They need a session that is authenticated with the Fedora identity provider.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::types::{Comment, Update, UpdateRequest, UpdateSeverity, UpdateType};
+use super::{BodhiClient, BodhiError};
+use crate::client::{ApiPath, Body};
+
+/// This enum represents the feedback that is given with a comment on an update.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Karma {
+    /// the update works (+1)
+    Positive,
+    /// no feedback (0)
+    #[default]
+    Neutral,
+    /// the update is broken (-1)
+    Negative,
+}
+
+impl From<Karma> for i32 {
+    fn from(karma: Karma) -> Self {
+        match karma {
+            Karma::Positive => 1,
+            Karma::Neutral => 0,
+            Karma::Negative => -1,
+        }
+    }
+}
+
+/// This type contains the data for creating a new update (or for replacing the contents of an
+/// existing update).
+///
+/// ```
+/// use fedora::bodhi::{NewUpdate, UpdateType};
+///
+/// let update = NewUpdate::new(&["rust-fedora-2.2.0-1.fc40"], "Update to version 2.2.0.")
+///     .update_type(UpdateType::Enhancement)
+///     .bug(1234567)
+///     .stable_karma(2);
+/// ```
+#[derive(Clone, Debug, Serialize)]
+#[must_use]
+pub struct NewUpdate<'a> {
+    builds: Vec<&'a str>,
+    notes: &'a str,
+    bugs: Vec<u64>,
+    #[serde(rename = "type")]
+    update_type: UpdateType,
+    severity: UpdateSeverity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    close_bugs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    autokarma: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stable_karma: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unstable_karma: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    autotime: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stable_days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    requirements: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request: Option<UpdateRequest>,
+}
+
+impl<'a> NewUpdate<'a> {
+    /// This method constructs a new [`NewUpdate`] for the given builds (specified by their NVR)
+    /// with the given user-visible description.
+    pub fn new(builds: &[&'a str], notes: &'a str) -> Self {
+        NewUpdate {
+            builds: builds.to_vec(),
+            notes,
+            bugs: Vec::new(),
+            update_type: UpdateType::Unspecified,
+            severity: UpdateSeverity::Unspecified,
+            display_name: None,
+            close_bugs: None,
+            autokarma: None,
+            stable_karma: None,
+            unstable_karma: None,
+            autotime: None,
+            stable_days: None,
+            requirements: None,
+            request: None,
+        }
+    }
+
+    /// Associate the update with the given bug.
+    pub fn bug(mut self, bug_id: u64) -> Self {
+        self.bugs.push(bug_id);
+        self
+    }
+
+    /// Override the type of the update (default: unspecified).
+    pub fn update_type(mut self, update_type: UpdateType) -> Self {
+        self.update_type = update_type;
+        self
+    }
+
+    /// Override the severity of the update (default: unspecified).
+    pub fn severity(mut self, severity: UpdateSeverity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Use the given title instead of the list of builds.
+    pub fn display_name(mut self, display_name: &'a str) -> Self {
+        self.display_name = Some(display_name);
+        self
+    }
+
+    /// Close associated bugs when the update is pushed to stable.
+    pub fn close_bugs(mut self, close_bugs: bool) -> Self {
+        self.close_bugs = Some(close_bugs);
+        self
+    }
+
+    /// Push the update to stable automatically when it reaches the stable karma threshold.
+    pub fn autokarma(mut self, autokarma: bool) -> Self {
+        self.autokarma = Some(autokarma);
+        self
+    }
+
+    /// Override the karma threshold for pushing the update to stable.
+    pub fn stable_karma(mut self, stable_karma: i32) -> Self {
+        self.stable_karma = Some(stable_karma);
+        self
+    }
+
+    /// Override the karma threshold for unpushing the update.
+    pub fn unstable_karma(mut self, unstable_karma: i32) -> Self {
+        self.unstable_karma = Some(unstable_karma);
+        self
+    }
+
+    /// Push the update to stable automatically when it has been in testing for long enough.
+    pub fn autotime(mut self, autotime: bool) -> Self {
+        self.autotime = Some(autotime);
+        self
+    }
+
+    /// Override the number of days that the update needs to be in testing.
+    pub fn stable_days(mut self, stable_days: u32) -> Self {
+        self.stable_days = Some(stable_days);
+        self
+    }
+
+    /// Require the given (space-separated) tests to pass before the update is pushed to stable.
+    pub fn requirements(mut self, requirements: &'a str) -> Self {
+        self.requirements = Some(requirements);
+        self
+    }
+
+    /// Request the given status change for the update (default: testing).
+    pub fn request(mut self, request: UpdateRequest) -> Self {
+        self.request = Some(request);
+        self
+    }
+}
+
+impl BodhiClient {
+    /// This method requests a new CSRF token from Bodhi, which is needed for all requests that
+    /// modify data.
+    async fn csrf_token(&self) -> Result<String, BodhiError> {
+        #[derive(Deserialize)]
+        struct CsrfResponse {
+            csrf_token: String,
+        }
+
+        let response: CsrfResponse = self.api.get(&ApiPath::file(&["csrf"]), &[]).await?;
+        Ok(response.csrf_token)
+    }
+
+    /// This method creates a new update.
+    pub async fn create_update(&self, update: &NewUpdate<'_>) -> Result<Update, BodhiError> {
+        let mut body = serde_json::to_value(update)?;
+        body["csrf_token"] = json!(self.csrf_token().await?);
+
+        self.api.post(&ApiPath::dir(&["updates"]), Body::Json(&body)).await
+    }
+
+    /// This method replaces the contents of the update with the given alias.
+    pub async fn edit_update(&self, alias: &str, update: &NewUpdate<'_>) -> Result<Update, BodhiError> {
+        let mut body = serde_json::to_value(update)?;
+        body["edited"] = json!(alias);
+        body["csrf_token"] = json!(self.csrf_token().await?);
+
+        self.api.post(&ApiPath::dir(&["updates"]), Body::Json(&body)).await
+    }
+
+    /// This method posts a comment with the given feedback on the update with the given alias.
+    ///
+    /// ```ignore
+    /// use fedora::bodhi::Karma;
+    ///
+    /// bodhi.comment("FEDORA-2024-1234567890", "Works for me.", Karma::Positive).await?;
+    /// ```
+    pub async fn comment(&self, alias: &str, text: &str, karma: Karma) -> Result<Comment, BodhiError> {
+        #[derive(Deserialize)]
+        struct CommentResponse {
+            comment: Comment,
+        }
+
+        let body = json!({
+            "update": alias,
+            "text": text,
+            "karma": i32::from(karma),
+            "csrf_token": self.csrf_token().await?,
+        });
+
+        let response: CommentResponse = self.api.post(&ApiPath::dir(&["comments"]), Body::Json(&body)).await?;
+        Ok(response.comment)
+    }
+
+    /// This method requests the given status change (for example, [`UpdateRequest::Stable`]) for
+    /// the update with the given alias.
+    pub async fn request(&self, alias: &str, request: UpdateRequest) -> Result<Update, BodhiError> {
+        #[derive(Deserialize)]
+        struct RequestResponse {
+            update: Update,
+        }
+
+        let body = json!({
+            "update": alias,
+            "request": request,
+            "csrf_token": self.csrf_token().await?,
+        });
+
+        let response: RequestResponse = self
+            .api
+            .post(&ApiPath::file(&["updates", alias, "request"]), Body::Json(&body))
+            .await?;
+        Ok(response.update)
+    }
+}
@@ -0,0 +1,674 @@
+//! This module contains the query builders for the Bodhi REST API.
+//!
+//! All builders can be passed to [`BodhiClient::page`](super::BodhiClient::page) (to request one
+//! page of results) or [`BodhiClient::all`](super::BodhiClient::all) (to request all results).
+//! Setters that filter by a list of values (for example, [`UpdateQuery::package`]) can be called
+//! multiple times, in which case results that match any of the values are returned.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::types::{Build, Comment, Override, Release, ReleaseState, Update, User};
+use super::types::{UpdateRequest, UpdateSeverity, UpdateStatus, UpdateType};
+use super::BodhiError;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// This trait is implemented by all query builders for the Bodhi REST API. It can not be
+/// implemented outside of this crate.
+pub trait Query: private::Sealed {
+    /// type of the results of the query
+    type Item: DeserializeOwned;
+
+    /// API path of the query (a collection, which is requested with a trailing slash)
+    #[doc(hidden)]
+    const PATH: &'static str;
+
+    /// key under which results are stored in responses
+    #[doc(hidden)]
+    const KEY: &'static str;
+
+    /// This method returns the query parameters of the query.
+    #[doc(hidden)]
+    fn params(&self) -> Result<Vec<(&'static str, String)>, BodhiError>;
+}
+
+/// This helper function returns the string representation of an enum value as it is used by Bodhi.
+fn param<T: Serialize>(value: &T) -> Result<String, BodhiError> {
+    serde_plain::to_string(value).map_err(|error| BodhiError::Serialization { error })
+}
+
+/// This helper function adds a query parameter for every value in a list.
+fn push_all(params: &mut Vec<(&'static str, String)>, key: &'static str, values: &[&str]) {
+    params.extend(values.iter().map(|value| (key, value.to_string())));
+}
+
+/// This type represents a query for updates.
+///
+/// ```
+/// use fedora::bodhi::{UpdateQuery, UpdateStatus};
+///
+/// let query = UpdateQuery::new()
+///     .package("rust-fedora")
+///     .release("F40")
+///     .status(UpdateStatus::Testing);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct UpdateQuery<'a> {
+    aliases: Vec<&'a str>,
+    builds: Vec<&'a str>,
+    bugs: Vec<u64>,
+    packages: Vec<&'a str>,
+    releases: Vec<&'a str>,
+    users: Vec<&'a str>,
+    status: Option<UpdateStatus>,
+    request: Option<UpdateRequest>,
+    update_type: Option<UpdateType>,
+    severity: Option<UpdateSeverity>,
+    search: Option<&'a str>,
+    submitted_since: Option<&'a str>,
+    modified_since: Option<&'a str>,
+    rows_per_page: Option<u32>,
+}
+
+impl<'a> UpdateQuery<'a> {
+    /// This method constructs a new [`UpdateQuery`] without any filters.
+    pub fn new() -> Self {
+        UpdateQuery::default()
+    }
+
+    /// Only return the update with the given alias.
+    pub fn alias(mut self, alias: &'a str) -> Self {
+        self.aliases.push(alias);
+        self
+    }
+
+    /// Only return updates that contain the build with the given NVR.
+    pub fn build(mut self, nvr: &'a str) -> Self {
+        self.builds.push(nvr);
+        self
+    }
+
+    /// Only return updates that are associated with the given bug.
+    pub fn bug(mut self, bug_id: u64) -> Self {
+        self.bugs.push(bug_id);
+        self
+    }
+
+    /// Only return updates for the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Only return updates for the given release (for example, "F40").
+    pub fn release(mut self, release: &'a str) -> Self {
+        self.releases.push(release);
+        self
+    }
+
+    /// Only return updates that were submitted by the given user.
+    pub fn user(mut self, user: &'a str) -> Self {
+        self.users.push(user);
+        self
+    }
+
+    /// Only return updates with the given status.
+    pub fn status(mut self, status: UpdateStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Only return updates with the given pending request.
+    pub fn request(mut self, request: UpdateRequest) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    /// Only return updates of the given type.
+    pub fn update_type(mut self, update_type: UpdateType) -> Self {
+        self.update_type = Some(update_type);
+        self
+    }
+
+    /// Only return updates with the given severity.
+    pub fn severity(mut self, severity: UpdateSeverity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// Only return updates whose title, alias, or notes contain the given string.
+    pub fn search(mut self, search: &'a str) -> Self {
+        self.search = Some(search);
+        self
+    }
+
+    /// Only return updates that were submitted after the given time (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub fn submitted_since(mut self, since: &'a str) -> Self {
+        self.submitted_since = Some(since);
+        self
+    }
+
+    /// Only return updates that were modified after the given time (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub fn modified_since(mut self, since: &'a str) -> Self {
+        self.modified_since = Some(since);
+        self
+    }
+
+    /// Override the number of results per page.
+    pub fn rows_per_page(mut self, rows_per_page: u32) -> Self {
+        self.rows_per_page = Some(rows_per_page);
+        self
+    }
+}
+
+impl private::Sealed for UpdateQuery<'_> {}
+
+impl Query for UpdateQuery<'_> {
+    type Item = Update;
+
+    const PATH: &'static str = "updates";
+    const KEY: &'static str = "updates";
+
+    fn params(&self) -> Result<Vec<(&'static str, String)>, BodhiError> {
+        let mut params = Vec::new();
+
+        push_all(&mut params, "alias", &self.aliases);
+        push_all(&mut params, "builds", &self.builds);
+        params.extend(self.bugs.iter().map(|bug| ("bugs", bug.to_string())));
+        push_all(&mut params, "packages", &self.packages);
+        push_all(&mut params, "releases", &self.releases);
+        push_all(&mut params, "user", &self.users);
+
+        if let Some(status) = &self.status {
+            params.push(("status", param(status)?));
+        }
+        if let Some(request) = &self.request {
+            params.push(("request", param(request)?));
+        }
+        if let Some(update_type) = &self.update_type {
+            params.push(("type", param(update_type)?));
+        }
+        if let Some(severity) = &self.severity {
+            params.push(("severity", param(severity)?));
+        }
+        if let Some(search) = self.search {
+            params.push(("search", search.to_string()));
+        }
+        if let Some(since) = self.submitted_since {
+            params.push(("submitted_since", since.to_string()));
+        }
+        if let Some(since) = self.modified_since {
+            params.push(("modified_since", since.to_string()));
+        }
+        if let Some(rows_per_page) = self.rows_per_page {
+            params.push(("rows_per_page", rows_per_page.to_string()));
+        }
+
+        Ok(params)
+    }
+}
+
+/// This type represents a query for builds.
+///
+/// ```
+/// use fedora::bodhi::BuildQuery;
+///
+/// let query = BuildQuery::new().package("rust-fedora").release("F40");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct BuildQuery<'a> {
+    nvr: Option<&'a str>,
+    packages: Vec<&'a str>,
+    releases: Vec<&'a str>,
+    updates: Vec<&'a str>,
+    rows_per_page: Option<u32>,
+}
+
+impl<'a> BuildQuery<'a> {
+    /// This method constructs a new [`BuildQuery`] without any filters.
+    pub fn new() -> Self {
+        BuildQuery::default()
+    }
+
+    /// Only return the build with the given NVR.
+    pub fn nvr(mut self, nvr: &'a str) -> Self {
+        self.nvr = Some(nvr);
+        self
+    }
+
+    /// Only return builds of the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Only return builds for the given release (for example, "F40").
+    pub fn release(mut self, release: &'a str) -> Self {
+        self.releases.push(release);
+        self
+    }
+
+    /// Only return builds that are part of the update with the given alias.
+    pub fn update(mut self, alias: &'a str) -> Self {
+        self.updates.push(alias);
+        self
+    }
+
+    /// Override the number of results per page.
+    pub fn rows_per_page(mut self, rows_per_page: u32) -> Self {
+        self.rows_per_page = Some(rows_per_page);
+        self
+    }
+}
+
+impl private::Sealed for BuildQuery<'_> {}
+
+impl Query for BuildQuery<'_> {
+    type Item = Build;
+
+    const PATH: &'static str = "builds";
+    const KEY: &'static str = "builds";
+
+    fn params(&self) -> Result<Vec<(&'static str, String)>, BodhiError> {
+        let mut params = Vec::new();
+
+        if let Some(nvr) = self.nvr {
+            params.push(("nvr", nvr.to_string()));
+        }
+        push_all(&mut params, "packages", &self.packages);
+        push_all(&mut params, "releases", &self.releases);
+        push_all(&mut params, "updates", &self.updates);
+        if let Some(rows_per_page) = self.rows_per_page {
+            params.push(("rows_per_page", rows_per_page.to_string()));
+        }
+
+        Ok(params)
+    }
+}
+
+/// This type represents a query for buildroot overrides.
+///
+/// ```
+/// use fedora::bodhi::OverrideQuery;
+///
+/// let query = OverrideQuery::new().user("janedoe").expired(false);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct OverrideQuery<'a> {
+    builds: Vec<&'a str>,
+    packages: Vec<&'a str>,
+    releases: Vec<&'a str>,
+    users: Vec<&'a str>,
+    expired: Option<bool>,
+    like: Option<&'a str>,
+    rows_per_page: Option<u32>,
+}
+
+impl<'a> OverrideQuery<'a> {
+    /// This method constructs a new [`OverrideQuery`] without any filters.
+    pub fn new() -> Self {
+        OverrideQuery::default()
+    }
+
+    /// Only return the override for the build with the given NVR.
+    pub fn build(mut self, nvr: &'a str) -> Self {
+        self.builds.push(nvr);
+        self
+    }
+
+    /// Only return overrides for builds of the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Only return overrides for the given release (for example, "F40").
+    pub fn release(mut self, release: &'a str) -> Self {
+        self.releases.push(release);
+        self
+    }
+
+    /// Only return overrides that were created by the given user.
+    pub fn user(mut self, user: &'a str) -> Self {
+        self.users.push(user);
+        self
+    }
+
+    /// Only return overrides that have (or have not) expired.
+    pub fn expired(mut self, expired: bool) -> Self {
+        self.expired = Some(expired);
+        self
+    }
+
+    /// Only return overrides for builds whose NVR contains the given string.
+    pub fn like(mut self, like: &'a str) -> Self {
+        self.like = Some(like);
+        self
+    }
+
+    /// Override the number of results per page.
+    pub fn rows_per_page(mut self, rows_per_page: u32) -> Self {
+        self.rows_per_page = Some(rows_per_page);
+        self
+    }
+}
+
+impl private::Sealed for OverrideQuery<'_> {}
+
+impl Query for OverrideQuery<'_> {
+    type Item = Override;
+
+    const PATH: &'static str = "overrides";
+    const KEY: &'static str = "overrides";
+
+    fn params(&self) -> Result<Vec<(&'static str, String)>, BodhiError> {
+        let mut params = Vec::new();
+
+        push_all(&mut params, "builds", &self.builds);
+        push_all(&mut params, "packages", &self.packages);
+        push_all(&mut params, "releases", &self.releases);
+        push_all(&mut params, "user", &self.users);
+        if let Some(expired) = self.expired {
+            params.push(("expired", expired.to_string()));
+        }
+        if let Some(like) = self.like {
+            params.push(("like", like.to_string()));
+        }
+        if let Some(rows_per_page) = self.rows_per_page {
+            params.push(("rows_per_page", rows_per_page.to_string()));
+        }
+
+        Ok(params)
+    }
+}
+
+/// This type represents a query for releases.
+///
+/// ```
+/// use fedora::bodhi::{ReleaseQuery, ReleaseState};
+///
+/// let query = ReleaseQuery::new().state(ReleaseState::Current);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct ReleaseQuery<'a> {
+    names: Vec<&'a str>,
+    packages: Vec<&'a str>,
+    updates: Vec<&'a str>,
+    state: Option<ReleaseState>,
+    exclude_archived: Option<bool>,
+    rows_per_page: Option<u32>,
+}
+
+impl<'a> ReleaseQuery<'a> {
+    /// This method constructs a new [`ReleaseQuery`] without any filters.
+    pub fn new() -> Self {
+        ReleaseQuery::default()
+    }
+
+    /// Only return the release with the given name (for example, "F40").
+    pub fn name(mut self, name: &'a str) -> Self {
+        self.names.push(name);
+        self
+    }
+
+    /// Only return releases that contain updates for the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Only return the release of the update with the given alias.
+    pub fn update(mut self, alias: &'a str) -> Self {
+        self.updates.push(alias);
+        self
+    }
+
+    /// Only return releases in the given state.
+    pub fn state(mut self, state: ReleaseState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Do not return releases that are no longer supported.
+    pub fn exclude_archived(mut self, exclude_archived: bool) -> Self {
+        self.exclude_archived = Some(exclude_archived);
+        self
+    }
+
+    /// Override the number of results per page.
+    pub fn rows_per_page(mut self, rows_per_page: u32) -> Self {
+        self.rows_per_page = Some(rows_per_page);
+        self
+    }
+}
+
+impl private::Sealed for ReleaseQuery<'_> {}
+
+impl Query for ReleaseQuery<'_> {
+    type Item = Release;
+
+    const PATH: &'static str = "releases";
+    const KEY: &'static str = "releases";
+
+    fn params(&self) -> Result<Vec<(&'static str, String)>, BodhiError> {
+        let mut params = Vec::new();
+
+        push_all(&mut params, "name", &self.names);
+        push_all(&mut params, "packages", &self.packages);
+        push_all(&mut params, "updates", &self.updates);
+        if let Some(state) = &self.state {
+            params.push(("state", param(state)?));
+        }
+        if let Some(exclude_archived) = self.exclude_archived {
+            params.push(("exclude_archived", exclude_archived.to_string()));
+        }
+        if let Some(rows_per_page) = self.rows_per_page {
+            params.push(("rows_per_page", rows_per_page.to_string()));
+        }
+
+        Ok(params)
+    }
+}
+
+/// This type represents a query for comments.
+///
+/// ```
+/// use fedora::bodhi::CommentQuery;
+///
+/// let query = CommentQuery::new()
+///     .update("FEDORA-2024-1234567890")
+///     .ignore_user("bodhi");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct CommentQuery<'a> {
+    packages: Vec<&'a str>,
+    updates: Vec<&'a str>,
+    update_owners: Vec<&'a str>,
+    users: Vec<&'a str>,
+    ignore_users: Vec<&'a str>,
+    like: Option<&'a str>,
+    since: Option<&'a str>,
+    rows_per_page: Option<u32>,
+}
+
+impl<'a> CommentQuery<'a> {
+    /// This method constructs a new [`CommentQuery`] without any filters.
+    pub fn new() -> Self {
+        CommentQuery::default()
+    }
+
+    /// Only return comments on updates for the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Only return comments on the update with the given alias.
+    pub fn update(mut self, alias: &'a str) -> Self {
+        self.updates.push(alias);
+        self
+    }
+
+    /// Only return comments on updates that were submitted by the given user.
+    pub fn update_owner(mut self, user: &'a str) -> Self {
+        self.update_owners.push(user);
+        self
+    }
+
+    /// Only return comments by the given user.
+    pub fn user(mut self, user: &'a str) -> Self {
+        self.users.push(user);
+        self
+    }
+
+    /// Do not return comments by the given user (for example, automated comments by "bodhi").
+    pub fn ignore_user(mut self, user: &'a str) -> Self {
+        self.ignore_users.push(user);
+        self
+    }
+
+    /// Only return comments whose text contains the given string.
+    pub fn like(mut self, like: &'a str) -> Self {
+        self.like = Some(like);
+        self
+    }
+
+    /// Only return comments that were posted after the given time (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub fn since(mut self, since: &'a str) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Override the number of results per page.
+    pub fn rows_per_page(mut self, rows_per_page: u32) -> Self {
+        self.rows_per_page = Some(rows_per_page);
+        self
+    }
+}
+
+impl private::Sealed for CommentQuery<'_> {}
+
+impl Query for CommentQuery<'_> {
+    type Item = Comment;
+
+    const PATH: &'static str = "comments";
+    const KEY: &'static str = "comments";
+
+    fn params(&self) -> Result<Vec<(&'static str, String)>, BodhiError> {
+        let mut params = Vec::new();
+
+        push_all(&mut params, "packages", &self.packages);
+        push_all(&mut params, "updates", &self.updates);
+        push_all(&mut params, "update_owner", &self.update_owners);
+        push_all(&mut params, "user", &self.users);
+        push_all(&mut params, "ignore_user", &self.ignore_users);
+        if let Some(like) = self.like {
+            params.push(("like", like.to_string()));
+        }
+        if let Some(since) = self.since {
+            params.push(("since", since.to_string()));
+        }
+        if let Some(rows_per_page) = self.rows_per_page {
+            params.push(("rows_per_page", rows_per_page.to_string()));
+        }
+
+        Ok(params)
+    }
+}
+
+/// This type represents a query for users.
+///
+/// ```
+/// use fedora::bodhi::UserQuery;
+///
+/// let query = UserQuery::new().group("provenpackager");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct UserQuery<'a> {
+    groups: Vec<&'a str>,
+    packages: Vec<&'a str>,
+    updates: Vec<&'a str>,
+    like: Option<&'a str>,
+    search: Option<&'a str>,
+    rows_per_page: Option<u32>,
+}
+
+impl<'a> UserQuery<'a> {
+    /// This method constructs a new [`UserQuery`] without any filters.
+    pub fn new() -> Self {
+        UserQuery::default()
+    }
+
+    /// Only return members of the given group.
+    pub fn group(mut self, group: &'a str) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    /// Only return users who submitted updates for the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Only return the submitter of the update with the given alias.
+    pub fn update(mut self, alias: &'a str) -> Self {
+        self.updates.push(alias);
+        self
+    }
+
+    /// Only return users whose name contains the given string.
+    pub fn like(mut self, like: &'a str) -> Self {
+        self.like = Some(like);
+        self
+    }
+
+    /// Only return users whose name or e-mail address contains the given string.
+    pub fn search(mut self, search: &'a str) -> Self {
+        self.search = Some(search);
+        self
+    }
+
+    /// Override the number of results per page.
+    pub fn rows_per_page(mut self, rows_per_page: u32) -> Self {
+        self.rows_per_page = Some(rows_per_page);
+        self
+    }
+}
+
+impl private::Sealed for UserQuery<'_> {}
+
+impl Query for UserQuery<'_> {
+    type Item = User;
+
+    const PATH: &'static str = "users";
+    const KEY: &'static str = "users";
+
+    fn params(&self) -> Result<Vec<(&'static str, String)>, BodhiError> {
+        let mut params = Vec::new();
+
+        push_all(&mut params, "groups", &self.groups);
+        push_all(&mut params, "packages", &self.packages);
+        push_all(&mut params, "updates", &self.updates);
+        if let Some(like) = self.like {
+            params.push(("like", like.to_string()));
+        }
+        if let Some(search) = self.search {
+            params.push(("search", search.to_string()));
+        }
+        if let Some(rows_per_page) = self.rows_per_page {
+            params.push(("rows_per_page", rows_per_page.to_string()));
+        }
+
+        Ok(params)
+    }
+}
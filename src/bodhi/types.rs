@@ -0,0 +1,314 @@
+//! This module contains the data types that are returned by the Bodhi REST API.
+//!
+//! All types keep attributes that are not captured by known fields in a catch-all map, so no data
+//! is lost if Bodhi adds new attributes. Timestamps are kept in the format that Bodhi returns them
+//! in (`YYYY-MM-DD HH:MM:SS`, in UTC).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum represents the status of an update.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum UpdateStatus {
+    /// update has not been pushed to any repository yet
+    Pending,
+    /// update is in the testing repository
+    Testing,
+    /// update is in the stable repository
+    Stable,
+    /// update was unpushed by its submitter
+    Unpushed,
+    /// update is obsolete (superseded by a newer update)
+    Obsolete,
+    /// update was revoked
+    Revoked,
+    /// update is a side tag update that is still being worked on
+    #[serde(rename = "side_tag_active")]
+    SideTagActive,
+    /// update is a side tag update that has expired
+    #[serde(rename = "side_tag_expired")]
+    SideTagExpired,
+    /// status that is not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
+/// This enum represents the requested status change of an update.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum UpdateRequest {
+    /// push the update to the testing repository
+    Testing,
+    /// push the update to the stable repository
+    Stable,
+    /// remove the update from the repositories
+    Unpush,
+    /// obsolete the update
+    Obsolete,
+    /// revoke the previous request
+    Revoke,
+    /// request that is not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
+/// This enum represents the type of an update.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum UpdateType {
+    /// bug fix update
+    Bugfix,
+    /// security update
+    Security,
+    /// enhancement update
+    Enhancement,
+    /// new package
+    Newpackage,
+    /// type that is not known to this crate
+    #[serde(other)]
+    Unspecified,
+}
+
+/// This enum represents the severity of an update.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum UpdateSeverity {
+    /// urgent
+    Urgent,
+    /// high
+    High,
+    /// medium
+    Medium,
+    /// low
+    Low,
+    /// severity that is not specified or not known to this crate
+    #[serde(other)]
+    Unspecified,
+}
+
+/// This enum represents the state of a release.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum ReleaseState {
+    /// release is not active yet
+    Disabled,
+    /// release is in development (for example, rawhide or branched releases)
+    Pending,
+    /// release is frozen
+    Frozen,
+    /// release is supported
+    Current,
+    /// release is no longer supported
+    Archived,
+    /// state that is not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
+/// This type represents a bug that is associated with an update.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Bug {
+    /// bug ID (in the Red Hat Bugzilla)
+    pub bug_id: u64,
+    /// title of the bug
+    pub title: Option<String>,
+    /// whether the bug is a security issue
+    #[serde(default)]
+    pub security: bool,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a build that is known to Bodhi.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Build {
+    /// name-version-release of the build
+    pub nvr: String,
+    /// epoch of the build, if any
+    pub epoch: Option<u32>,
+    /// ID of the release the build belongs to
+    pub release_id: Option<u32>,
+    /// whether the build has been signed
+    #[serde(default)]
+    pub signed: bool,
+    /// type of the build (for example, "rpm", "module", or "container")
+    #[serde(rename = "type")]
+    pub build_type: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a comment on an update.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Comment {
+    /// ID of the comment
+    pub id: u64,
+    /// text of the comment
+    pub text: String,
+    /// karma that was given with the comment (-1, 0, or 1)
+    #[serde(default)]
+    pub karma: i32,
+    /// time when the comment was posted
+    pub timestamp: Option<String>,
+    /// ID of the update that was commented on
+    pub update_id: Option<u64>,
+    /// author of the comment
+    pub user: Option<User>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a group of users.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Group {
+    /// name of the group
+    pub name: String,
+}
+
+/// This type represents a buildroot override.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Override {
+    /// name-version-release of the overridden build
+    pub nvr: String,
+    /// explanation why the override was created
+    #[serde(default)]
+    pub notes: String,
+    /// time when the override was submitted
+    pub submission_date: Option<String>,
+    /// time when the override will expire
+    pub expiration_date: Option<String>,
+    /// time when the override expired, if it has already expired
+    pub expired_date: Option<String>,
+    /// the overridden build
+    pub build: Option<Build>,
+    /// user who created the override
+    pub submitter: Option<User>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a release (for example, "F40").
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Release {
+    /// short name of the release (for example, "F40")
+    pub name: String,
+    /// long name of the release (for example, "Fedora 40")
+    pub long_name: Option<String>,
+    /// version of the release (for example, "40")
+    pub version: Option<String>,
+    /// dist-git branch of the release (for example, "f40")
+    pub branch: Option<String>,
+    /// prefix of update aliases for this release (for example, "FEDORA")
+    pub id_prefix: Option<String>,
+    /// koji tag of stable builds
+    pub stable_tag: Option<String>,
+    /// koji tag of builds in testing
+    pub testing_tag: Option<String>,
+    /// koji tag of update candidates
+    pub candidate_tag: Option<String>,
+    /// state of the release
+    pub state: Option<ReleaseState>,
+    /// whether updates for this release are composed by Bodhi
+    #[serde(default)]
+    pub composed_by_bodhi: bool,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents an update.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Update {
+    /// alias of the update (for example, "FEDORA-2024-1234567890")
+    pub alias: String,
+    /// title of the update
+    #[serde(default)]
+    pub title: String,
+    /// user-visible description of the update
+    #[serde(default)]
+    pub notes: String,
+    /// current status of the update
+    pub status: UpdateStatus,
+    /// requested status change of the update, if any
+    pub request: Option<UpdateRequest>,
+    /// type of the update
+    #[serde(rename = "type")]
+    pub update_type: UpdateType,
+    /// severity of the update
+    pub severity: Option<UpdateSeverity>,
+    /// current karma of the update
+    pub karma: Option<i32>,
+    /// karma threshold for automatically pushing the update to stable
+    pub stable_karma: Option<i32>,
+    /// karma threshold for automatically unpushing the update
+    pub unstable_karma: Option<i32>,
+    /// builds that are part of the update
+    #[serde(default)]
+    pub builds: Vec<Build>,
+    /// bugs that are fixed by the update
+    #[serde(default)]
+    pub bugs: Vec<Bug>,
+    /// comments on the update
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    /// release the update belongs to
+    pub release: Option<Release>,
+    /// user who submitted the update
+    pub user: Option<User>,
+    /// time when the update was submitted
+    pub date_submitted: Option<String>,
+    /// time when the update was last modified
+    pub date_modified: Option<String>,
+    /// time when the update was pushed to stable
+    pub date_stable: Option<String>,
+    /// URL of the web page of the update
+    pub url: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct User {
+    /// ID of the user
+    pub id: Option<u64>,
+    /// name of the user
+    pub name: String,
+    /// e-mail address of the user, if public
+    pub email: Option<String>,
+    /// URL of the avatar image of the user
+    pub avatar: Option<String>,
+    /// groups the user is a member of
+    #[serde(default)]
+    pub groups: Vec<Group>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
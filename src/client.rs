@@ -0,0 +1,350 @@
+//! This module contains the parts that are shared by the typed API clients (for example,
+//! [`BodhiClient`](crate::bodhi::BodhiClient)): the wrapped [`Session`], constructing the URLs of
+//! API endpoints, sending requests, checking responses, and the variants and classification
+//! methods that all error types of API clients have in common.
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::session::{Session, SessionError};
+
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+use reqwest::{Method, Response};
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+use serde::de::DeserializeOwned;
+
+/// This trait is implemented by the error types of all API clients, which are generated with the
+/// `api_error` macro.
+pub(crate) trait ApiError: From<SessionError> + From<reqwest::Error> + From<serde_json::Error> {
+    /// This method constructs an error from an unsuccessful response.
+    fn from_response(status: StatusCode, body: &str) -> Self;
+
+    /// This method returns `true` for service-specific errors that are likely temporary (in
+    /// addition to network errors and HTTP status codes that indicate temporary failures).
+    fn is_service_retryable(&self) -> bool {
+        false
+    }
+
+    /// This method returns `true` for service-specific errors that indicate that authentication
+    /// failed (in addition to "401 Unauthorized" and "403 Forbidden" responses).
+    fn is_service_auth_failure(&self) -> bool {
+        false
+    }
+}
+
+/// This helper function parses the hardcoded URL of a well-known service instance.
+pub(crate) fn fixed_url(url: &str) -> Url {
+    Url::parse(url).expect("Failed to parse a hardcoded URL.")
+}
+
+/// This type contains the [`Session`] and the URL of an API client. The type parameter is the
+/// error type of the client, which is returned by all methods that send requests.
+pub(crate) struct ApiClient<E> {
+    session: Session,
+    url: Url,
+    /// path segments between the base URL and all API endpoints (for example, `["api", "0"]`)
+    #[cfg_attr(
+        not(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure")),
+        allow(dead_code)
+    )]
+    prefix: &'static [&'static str],
+    error: PhantomData<fn() -> E>,
+}
+
+impl<E> Debug for ApiClient<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiClient")
+            .field("session", &self.session)
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E> ApiClient<E> {
+    /// This method constructs a new [`ApiClient`] for the API at the given URL, which is used
+    /// as-is.
+    pub(crate) fn new(session: Session, url: Url) -> Self {
+        ApiClient {
+            session,
+            url,
+            prefix: &[],
+            error: PhantomData,
+        }
+    }
+
+    /// This method constructs a new [`ApiClient`] for a REST API with endpoints below the given
+    /// path prefix. The base URL is normalized to end with a slash.
+    #[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+    pub(crate) fn rest(session: Session, mut url: Url, prefix: &'static [&'static str]) -> Self {
+        // relative API paths are resolved against the base URL, so it needs to end with a slash
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+
+        ApiClient {
+            prefix,
+            ..ApiClient::new(session, url)
+        }
+    }
+
+    /// This method returns a reference to the wrapped [`Session`].
+    pub(crate) fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// This method returns the base URL of the service.
+    pub(crate) fn url(&self) -> &Url {
+        &self.url
+    }
+}
+
+/// This error is returned when the URL of an API endpoint could not be constructed from its path
+/// segments.
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+#[derive(Debug)]
+pub(crate) enum PathError {
+    /// The base URL can not have a path (for example, a `data:` URL).
+    Base(url::ParseError),
+    /// The segment is empty, or it is "." or "..", so it would be removed or interpreted when the
+    /// path is normalized.
+    Segment(String),
+}
+
+/// This helper function appends the given path segments to the path of a base URL, with an
+/// optional trailing slash.
+///
+/// Every segment is percent-encoded, so names that contain "/", "?", or "#" can not change which
+/// path is requested.
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+pub(crate) fn api_url(base: &Url, segments: &[&str], trailing_slash: bool) -> Result<Url, PathError> {
+    if let Some(segment) = segments.iter().find(|segment| matches!(**segment, "" | "." | "..")) {
+        return Err(PathError::Segment((*segment).to_owned()));
+    }
+
+    let mut url = base.clone();
+    {
+        let mut path = url
+            .path_segments_mut()
+            .map_err(|_| PathError::Base(url::ParseError::RelativeUrlWithCannotBeABaseBase))?;
+        path.pop_if_empty().extend(segments);
+        if trailing_slash {
+            path.push("");
+        }
+    }
+
+    Ok(url)
+}
+
+/// This type represents the path of an API endpoint (relative to the path prefix of the API), as
+/// a list of segments that are percent-encoded when the URL is constructed.
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+pub(crate) struct ApiPath<'a> {
+    pub(crate) segments: &'a [&'a str],
+    trailing_slash: bool,
+}
+
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+impl<'a> ApiPath<'a> {
+    /// This method constructs the path for a collection or object, which ends with a slash.
+    #[cfg(any(feature = "bodhi", feature = "fas"))]
+    pub(crate) fn dir(segments: &'a [&'a str]) -> Self {
+        ApiPath {
+            segments,
+            trailing_slash: true,
+        }
+    }
+
+    /// This method constructs the path for an endpoint that does not end with a slash.
+    pub(crate) fn file(segments: &'a [&'a str]) -> Self {
+        ApiPath {
+            segments,
+            trailing_slash: false,
+        }
+    }
+}
+
+/// This enum represents the (optional) body of a request to a REST API.
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+pub(crate) enum Body<'a> {
+    /// no body
+    Empty,
+    /// JSON document
+    #[cfg_attr(not(any(feature = "bodhi", feature = "copr")), allow(dead_code))]
+    Json(&'a serde_json::Value),
+    /// URL-encoded form data
+    #[cfg_attr(not(feature = "pagure"), allow(dead_code))]
+    Form(&'a [(&'a str, String)]),
+}
+
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+impl<E: ApiError + From<PathError>> ApiClient<E> {
+    /// This method constructs the URL of the API endpoint with the given path. Every segment is
+    /// percent-encoded, so names can not change which endpoint is requested.
+    pub(crate) fn endpoint(&self, path: &ApiPath<'_>) -> Result<Url, E> {
+        let segments: Vec<&str> = self.prefix.iter().chain(path.segments).copied().collect();
+        Ok(api_url(&self.url, &segments, path.trailing_slash)?)
+    }
+
+    /// This method sends a request to the API endpoint with the given path, with the given query
+    /// parameters and body.
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        path: &ApiPath<'_>,
+        params: &[(&str, String)],
+        body: Body<'_>,
+    ) -> Result<Response, E> {
+        let request = self
+            .session
+            .session()
+            .request(method, self.endpoint(path)?)
+            .header(ACCEPT, "application/json")
+            .query(params);
+
+        let request = match body {
+            Body::Empty => request,
+            Body::Json(body) => request.header(CONTENT_TYPE, "application/json").body(body.to_string()),
+            Body::Form(form) => request.form(form),
+        };
+
+        Ok(self.session.send(request).await?)
+    }
+
+    /// This method sends a `GET` request to the API endpoint with the given path and deserializes
+    /// the response.
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &ApiPath<'_>, params: &[(&str, String)]) -> Result<T, E> {
+        let response = self.send(Method::GET, path, params, Body::Empty).await?;
+        parse(response).await
+    }
+
+    /// This method sends a `GET` request to the API endpoint with the given path and deserializes
+    /// the response, or returns `None` if the service returned "404 Not Found".
+    pub(crate) async fn get_optional<T: DeserializeOwned>(
+        &self,
+        path: &ApiPath<'_>,
+        params: &[(&str, String)],
+    ) -> Result<Option<T>, E> {
+        let response = self.send(Method::GET, path, params, Body::Empty).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        parse(response).await.map(Some)
+    }
+
+    /// This method sends a `POST` request with the given body to the API endpoint with the given
+    /// path and deserializes the response.
+    #[cfg(any(feature = "bodhi", feature = "copr", feature = "pagure"))]
+    pub(crate) async fn post<T: DeserializeOwned>(&self, path: &ApiPath<'_>, body: Body<'_>) -> Result<T, E> {
+        let response = self.send(Method::POST, path, &[], body).await?;
+        parse(response).await
+    }
+}
+
+/// This helper function checks the status of a response and deserializes its body.
+#[cfg(any(feature = "bodhi", feature = "copr", feature = "fas", feature = "pagure"))]
+pub(crate) async fn parse<T: DeserializeOwned, E: ApiError>(response: Response) -> Result<T, E> {
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        return Err(E::from_response(status, &body));
+    }
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// This macro generates the error type of an API client, with the variants that all API clients
+/// have in common (`Session`, `Request`, `Deserialization`, and `Status`), followed by the given
+/// client-specific variants, and the `is_retryable` and `is_auth_failure` methods.
+///
+/// The name of the service is used in the error message of the `Status` variant, which must be the
+/// first variant. It can contain additional fields, which are inserted between the `status` and
+/// `message` fields. The type must implement the `ApiError` trait.
+macro_rules! api_error {
+    (
+        service: $service:literal,
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            Status { $($status_fields:tt)* },
+            $($variants:tt)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, thiserror::Error)]
+        pub enum $name {
+            /// This error is returned when sending a request with the [`Session`](crate::Session)
+            /// failed.
+            #[error("Failed to send request: {error}")]
+            Session {
+                /// The inner error contains the error that was returned by the
+                /// [`Session`](crate::Session).
+                #[from]
+                error: crate::SessionError,
+            },
+            /// This error represents a network-related issue that occurred when reading a response.
+            #[error("Failed to read response: {error}")]
+            Request {
+                /// The inner error contains the error passed from [`reqwest`](https://docs.rs/reqwest).
+                #[from]
+                error: reqwest::Error,
+            },
+            /// This error is returned when the response could not be deserialized.
+            #[error("Failed to deserialize response: {error}")]
+            Deserialization {
+                /// The inner error contains the deserialization error message from
+                /// [`serde_json`](https://docs.rs/serde_json).
+                #[from]
+                error: serde_json::Error,
+            },
+            /// This error is returned when the service returned an unsuccessful HTTP status code.
+            #[error("{} returned HTTP status {}: {}", $service, .status, .message)]
+            Status {
+                /// HTTP status code of the response
+                status: reqwest::StatusCode,
+                $($status_fields)*
+                /// error message from the response (or the beginning of the response body, if it
+                /// did not contain an error message)
+                message: String,
+            },
+            $($variants)*
+        }
+
+        impl $name {
+            /// This method returns `true` if the error is likely temporary (for example, a timeout
+            /// or a "503 Service Unavailable" response), and the request can be retried later.
+            pub fn is_retryable(&self) -> bool {
+                match self {
+                    $name::Session { error } => error.is_retryable(),
+                    $name::Request { error } => crate::session::is_transient_error(error),
+                    $name::Status { status, .. } => crate::session::is_transient_status(*status),
+                    _ => crate::client::ApiError::is_service_retryable(self),
+                }
+            }
+
+            /// This method returns `true` if the error indicates that the request needs
+            /// authentication, that the credentials of the session are invalid or expired, or
+            /// that they do not grant the permissions that are needed for the request.
+            pub fn is_auth_failure(&self) -> bool {
+                match self {
+                    $name::Session { error } => error.is_auth_failure(),
+                    $name::Status { status, .. }
+                        if *status == reqwest::StatusCode::UNAUTHORIZED || *status == reqwest::StatusCode::FORBIDDEN =>
+                    {
+                        true
+                    },
+                    _ => crate::client::ApiError::is_service_auth_failure(self),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use api_error;
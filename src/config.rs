@@ -0,0 +1,167 @@
+//! This module contains the client settings that are shared by all session builders (timeout,
+//! User-Agent header, TLS settings, retry policy, and rate limiter).
+
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+use reqwest::{Client, ClientBuilder};
+
+use crate::ratelimit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::session::Session;
+use crate::tls::{TlsConfig, TlsError};
+use crate::{DEFAULT_TIMEOUT, FEDORA_USER_AGENT};
+
+/// This type collects the (optional) client settings of a session builder.
+///
+/// The public setters for these settings are generated for each builder with the
+/// `client_config_methods` macro, which expects the settings in a field named `config`.
+#[derive(Debug, Default)]
+pub(crate) struct ClientConfig<'a> {
+    /// optional override of the default timeout duration
+    pub(crate) timeout: Option<Duration>,
+    /// optional override of the default User-Agent header
+    pub(crate) user_agent: Option<&'a str>,
+    /// optional TLS client certificate and custom CA certificates
+    pub(crate) tls: TlsConfig,
+    /// optional policy for retrying failed requests
+    pub(crate) retry: Option<RetryPolicy>,
+    /// optional per-host rate limiter
+    pub(crate) rate_limiter: Option<RateLimiter>,
+}
+
+impl<'a> ClientConfig<'a> {
+    /// This method fills in the default values for all settings that were not overridden, and
+    /// returns the [`ClientTemplate`] that is used for constructing clients and sessions.
+    pub(crate) fn resolve(self) -> ClientTemplate {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => DEFAULT_TIMEOUT,
+        };
+
+        let user_agent = match self.user_agent {
+            Some(user_agent) => user_agent,
+            None => FEDORA_USER_AGENT,
+        };
+
+        // set default headers for our requests
+        // - User Agent
+        // - Accept: application/json
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_str(user_agent).expect("Failed to parse hardcoded HTTP headers."),
+        );
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+
+        ClientTemplate {
+            headers,
+            timeout,
+            tls: self.tls,
+            retry: self.retry,
+            rate_limiter: self.rate_limiter,
+        }
+    }
+}
+
+/// This type contains the resolved client settings of a session builder. It is kept by login
+/// handlers that construct the clients of a session only after logging in.
+#[derive(Clone, Debug)]
+pub(crate) struct ClientTemplate {
+    pub(crate) headers: HeaderMap,
+    pub(crate) timeout: Duration,
+    pub(crate) tls: TlsConfig,
+    pub(crate) retry: Option<RetryPolicy>,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+}
+
+impl ClientTemplate {
+    /// This method returns a [`ClientBuilder`] with the default headers, the timeout, and the TLS
+    /// settings already applied. Cookie and redirect handling is left to the caller.
+    pub(crate) fn client(&self) -> ClientBuilder {
+        self.tls
+            .apply(Client::builder())
+            .default_headers(self.headers.clone())
+            .timeout(self.timeout)
+    }
+
+    /// This method builds a client from a [`ClientBuilder`] that was returned by
+    /// [`ClientTemplate::client`].
+    ///
+    /// If a client certificate or custom CA certificates were configured, errors are returned
+    /// (the TLS backend might reject them). Otherwise, building the client can only fail if the
+    /// network stack cannot be initialized at all, which causes a panic.
+    pub(crate) fn build(&self, builder: ClientBuilder) -> Result<Client, TlsError> {
+        match builder.build() {
+            Ok(client) => Ok(client),
+            Err(error) if self.tls.is_custom() => Err(TlsError::Backend { error }),
+            Err(error) => panic!("Failed to initialize the network stack: {}", error),
+        }
+    }
+
+    /// This method wraps the given client in a [`Session`] with the retry policy and the rate
+    /// limiter of this template, but without an authentication method.
+    pub(crate) fn session(self, client: Client) -> Session {
+        Session {
+            retry: self.retry,
+            rate_limiter: self.rate_limiter,
+            ..Session::from_client(client)
+        }
+    }
+}
+
+/// This macro generates the public setters for the client settings of a session builder, which
+/// must store them in a field named `config` of type [`ClientConfig`]. The argument is the lifetime
+/// parameter of the builder, which is used for the User-Agent header.
+macro_rules! client_config_methods {
+    ($lifetime:lifetime) => {
+        /// Override the default request timeout duration.
+        #[must_use]
+        pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+            self.config.timeout = Some(timeout);
+            self
+        }
+
+        /// Override the default User-Agent header.
+        #[must_use]
+        pub fn user_agent(mut self, user_agent: &$lifetime str) -> Self {
+            self.config.user_agent = Some(user_agent);
+            self
+        }
+
+        /// Authenticate with the given TLS client certificate.
+        #[must_use]
+        pub fn client_identity(mut self, identity: crate::tls::ClientIdentity) -> Self {
+            self.config.tls.identity = Some(identity);
+            self
+        }
+
+        /// Trust the certificates in the given bundle in addition to the built-in root
+        /// certificates.
+        #[must_use]
+        pub fn ca_bundle(mut self, ca_bundle: crate::tls::CaBundle) -> Self {
+            self.config.tls.ca_bundle = Some(ca_bundle);
+            self
+        }
+
+        /// Retry requests that failed due to temporary errors according to the given policy.
+        #[must_use]
+        pub fn retry(mut self, policy: crate::retry::RetryPolicy) -> Self {
+            self.config.retry = Some(policy);
+            self
+        }
+
+        /// Limit the rate of requests to each host with the given [`RateLimiter`] (which can be
+        /// shared with other sessions).
+        ///
+        /// [`RateLimiter`]: crate::RateLimiter
+        #[must_use]
+        pub fn rate_limiter(mut self, rate_limiter: crate::ratelimit::RateLimiter) -> Self {
+            self.config.rate_limiter = Some(rate_limiter);
+            self
+        }
+    };
+}
+
+pub(crate) use client_config_methods;
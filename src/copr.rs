@@ -0,0 +1,299 @@
+//! This module contains a typed client for the REST API of
+//! [Copr](https://copr.fedorainfracloud.org), which is built on top of a [`Session`].
+//!
+//! Projects, builds, and the list of available chroots can be queried with anonymous sessions.
+//! Creating projects and submitting builds needs a session that is authenticated with a Copr API
+//! token, which can be downloaded from <https://copr.fedorainfracloud.org/api/> and is stored in
+//! `~/.config/copr` by default. [`CoprClient::from_config`] reads this file and sets up an
+//! authenticated session:
+//!
+//! ```ignore
+//! use std::time::Duration;
+//!
+//! use fedora::copr::{BuildOptions, CoprClient, CoprConfig, ScmSource};
+//!
+//! let config = CoprConfig::from_file(None)?;
+//! let copr = CoprClient::from_config(&config)?;
+//!
+//! let source = ScmSource::new("https://src.fedoraproject.org/rpms/rust-fedora.git");
+//! let build = copr.build_from_scm(&config.username, "rust-fedora", &source, &BuildOptions::new()).await?;
+//!
+//! let build = copr.wait_for_build(build.id, Duration::from_secs(30), Duration::from_secs(3 * 3600)).await?;
+//! println!("Build {} finished: {:?}", build.id, build.state);
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use url::Url;
+
+use crate::client::{api_error, fixed_url, ApiClient, ApiError, ApiPath, PathError};
+use crate::openid::excerpt;
+use crate::secret::Secret;
+use crate::session::Session;
+use crate::token::{config_value, read_copr_config, ApiToken, ApiTokenError};
+
+mod actions;
+pub use actions::{BuildOptions, NewProject, PypiSource, ScmSource, ScmType, SpecGenerator, SrpmBuildMethod};
+
+mod types;
+pub use types::{Build, BuildState, Project, SourcePackage};
+
+/// base URL of the Fedora instance of Copr
+const FEDORA_COPR_URL: &str = "https://copr.fedorainfracloud.org/";
+
+/// minimum interval between two checks of the state of a build in [`CoprClient::wait_for_build`]
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+api_error! {
+    service: "Copr",
+    /// This collection of errors is returned for failures when sending requests to Copr.
+    pub enum CoprError {
+        Status {},
+        /// This error represents an issue with constructing the URL of an API endpoint.
+        #[error("Failed to construct URL: {error}")]
+        UrlParsing {
+            /// The inner error contains the error that occurred when parsing the URL.
+            #[from]
+            error: url::ParseError,
+        },
+        /// This error is returned when a name can not be used in the path of an API endpoint
+        /// (because it is empty, or because it is "." or "..").
+        #[error("Invalid name in API path: {name:?}")]
+        InvalidName {
+            /// name that was rejected
+            name: String,
+        },
+        /// This error is returned when the Copr configuration file could not be read, or when
+        /// setting up an authenticated session with the API token from the configuration file
+        /// failed.
+        #[error("Invalid Copr configuration: {error}")]
+        Config {
+            /// The inner error contains the error that occurred when reading the configuration file
+            /// or when building the session.
+            #[from]
+            error: ApiTokenError,
+        },
+        /// This error is returned when a file that should be uploaded could not be read.
+        #[error("Failed to read file {}: {error}", path.display())]
+        FileSystemError {
+            /// path of the file
+            path: PathBuf,
+            /// The inner error contains the I/O error that occurred when reading the file.
+            error: std::io::Error,
+        },
+        /// This error is returned when a build did not finish before the deadline that was passed
+        /// to [`CoprClient::wait_for_build`].
+        #[error("Copr build {id} did not finish in time (last state: {state:?})")]
+        Timeout {
+            /// ID of the build
+            id: u64,
+            /// state of the build when it was last checked
+            state: BuildState,
+        },
+    }
+}
+
+impl ApiError for CoprError {
+    fn from_response(status: StatusCode, body: &str) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorResponse {
+            error: String,
+        }
+
+        let message = match serde_json::from_str::<ErrorResponse>(body) {
+            Ok(response) => response.error,
+            Err(_) => excerpt(body),
+        };
+
+        CoprError::Status { status, message }
+    }
+}
+
+impl From<PathError> for CoprError {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::Base(error) => CoprError::UrlParsing { error },
+            PathError::Segment(name) => CoprError::InvalidName { name },
+        }
+    }
+}
+
+/// This type contains the settings from the Copr configuration file (as written by `copr-cli`).
+///
+/// The file is in INI format, with all values in the `[copr-cli]` section:
+///
+/// ```ini
+/// [copr-cli]
+/// login = ABCDEFGHIJKLMNOPQRST
+/// username = janedoe
+/// token = abcdefghijklmnopqrstuvwxyzabcd
+/// copr_url = https://copr.fedorainfracloud.org
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CoprConfig {
+    /// API login (this is not the FAS username)
+    pub login: String,
+    /// name of the user that the API token belongs to
+    pub username: String,
+    /// API token
+    pub token: Secret,
+    /// base URL of the Copr instance (default: <https://copr.fedorainfracloud.org>)
+    pub copr_url: Url,
+}
+
+impl CoprConfig {
+    /// This method reads the Copr configuration file from the given path, or from the default
+    /// location (`~/.config/copr`).
+    pub fn from_file(path: Option<&Path>) -> Result<Self, CoprError> {
+        let (path, contents) = read_copr_config(path)?;
+        CoprConfig::parse(path, &contents)
+    }
+
+    /// This method parses the contents of a Copr configuration file that was read from the given
+    /// path.
+    fn parse(path: PathBuf, contents: &str) -> Result<Self, CoprError> {
+        let value = |key: &'static str| {
+            config_value(contents, "copr-cli", key).ok_or_else(|| ApiTokenError::MissingValue {
+                path: path.clone(),
+                key,
+            })
+        };
+
+        let login = value("login")?.to_string();
+        let username = value("username")?.to_string();
+        let token = Secret::from(value("token")?);
+
+        let copr_url = match config_value(contents, "copr-cli", "copr_url") {
+            Some(url) => Url::parse(url)?,
+            None => Url::parse(FEDORA_COPR_URL).expect("Failed to parse a hardcoded URL."),
+        };
+
+        Ok(CoprConfig {
+            login,
+            username,
+            token,
+            copr_url,
+        })
+    }
+
+    /// This method returns the API token from the configuration file, which can be used to build
+    /// an authenticated session with [`Session::with_token`].
+    pub fn token(&self) -> ApiToken {
+        ApiToken::copr(&self.login, self.token.clone())
+    }
+}
+
+/// This type is a typed client for the Copr REST API (version 3).
+///
+/// All requests are sent with [`Session::send`], so they are authenticated by the [`Session`] (if
+/// it is authenticated), and they are retried and rate-limited according to its settings.
+///
+/// Projects are identified by their owner (a user name, or a group name prefixed with "@") and
+/// their name.
+#[derive(Debug)]
+pub struct CoprClient {
+    api: ApiClient<CoprError>,
+}
+
+impl CoprClient {
+    /// This method constructs a new [`CoprClient`] for the Fedora instance of Copr.
+    pub fn new(session: Session) -> Self {
+        CoprClient::with_url(session, fixed_url(FEDORA_COPR_URL))
+    }
+
+    /// This method constructs a new [`CoprClient`] for the instance of Copr at the given URL.
+    pub fn with_url(session: Session, url: Url) -> Self {
+        CoprClient {
+            api: ApiClient::rest(session, url, &["api_3"]),
+        }
+    }
+
+    /// This method constructs a new [`CoprClient`] for the Copr instance from the configuration
+    /// file, with a session that is authenticated with the API token from the configuration file.
+    ///
+    /// Use [`CoprClient::with_url`] with a session that was built from [`CoprConfig::token`] to
+    /// customize the session (for example, to enable retries).
+    pub fn from_config(config: &CoprConfig) -> Result<Self, CoprError> {
+        let session = Session::with_token(config.token(), &config.copr_url).build()?;
+        Ok(CoprClient::with_url(session, config.copr_url.clone()))
+    }
+
+    /// This method returns a reference to the wrapped [`Session`].
+    pub fn session(&self) -> &Session {
+        self.api.session()
+    }
+
+    /// This method returns the base URL of the Copr instance.
+    pub fn url(&self) -> &Url {
+        self.api.url()
+    }
+
+    /// This method returns the name of the user that the API token of the session belongs to.
+    ///
+    /// It can be used to check whether the API token is valid and has not expired yet.
+    pub async fn auth_check(&self) -> Result<String, CoprError> {
+        #[derive(Deserialize)]
+        struct AuthCheckResponse {
+            #[serde(alias = "user")]
+            name: String,
+        }
+
+        let response: AuthCheckResponse = self.api.get(&ApiPath::file(&["auth-check"]), &[]).await?;
+        Ok(response.name)
+    }
+
+    /// This method returns the project with the given owner and name (or `None` if it does not
+    /// exist).
+    pub async fn project(&self, owner: &str, name: &str) -> Result<Option<Project>, CoprError> {
+        let params = [("ownername", owner.to_string()), ("projectname", name.to_string())];
+        self.api.get_optional(&ApiPath::file(&["project"]), &params).await
+    }
+
+    /// This method returns the build with the given ID (or `None` if it does not exist).
+    pub async fn build(&self, id: u64) -> Result<Option<Build>, CoprError> {
+        let id = id.to_string();
+        self.api.get_optional(&ApiPath::file(&["build", &id]), &[]).await
+    }
+
+    /// This method polls the state of the build with the given ID in the given interval until it
+    /// has finished, and returns the finished build.
+    ///
+    /// Intervals shorter than one second are extended to one second, so Copr is not flooded with
+    /// requests. If the build has not finished after the given timeout, [`CoprError::Timeout`] is
+    /// returned (the build itself keeps running).
+    pub async fn wait_for_build(&self, id: u64, interval: Duration, timeout: Duration) -> Result<Build, CoprError> {
+        let interval = interval.max(MIN_POLL_INTERVAL);
+        let deadline = tokio::time::Instant::now() + timeout;
+        let build_id = id.to_string();
+
+        loop {
+            let build: Build = self.api.get(&ApiPath::file(&["build", &build_id]), &[]).await?;
+
+            if build.state.is_finished() {
+                return Ok(build);
+            }
+
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(CoprError::Timeout { id, state: build.state });
+            }
+
+            log::debug!("Copr build {} is not finished yet ({:?}).", id, build.state);
+            tokio::time::sleep(interval.min(deadline - now)).await;
+        }
+    }
+
+    /// This method returns the names of all chroots that are currently available for builds (for
+    /// example, "fedora-rawhide-x86_64"), in alphabetical order.
+    pub async fn chroots(&self) -> Result<Vec<String>, CoprError> {
+        // chroot names are mapped to (possibly empty) comments
+        let chroots: BTreeMap<String, serde_json::Value> =
+            self.api.get(&ApiPath::file(&["mock-chroots", "list"]), &[]).await?;
+        Ok(chroots.into_keys().collect())
+    }
+}
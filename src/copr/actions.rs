@@ -0,0 +1,467 @@
+//! This module contains the authenticated actions of the Copr REST API (creating projects and
+//! submitting builds).
+//!
+//! All actions need a session that is authenticated with a Copr API token (see
+//! [`CoprClient::from_config`]).
+
+use std::path::Path;
+
+use reqwest::multipart::{Form, Part};
+use reqwest::Body;
+use serde::Serialize;
+
+use super::types::{Build, Project};
+use super::{CoprClient, CoprError};
+use crate::client::{parse, ApiPath, Body as ApiBody};
+
+/// This enum represents the version control systems that sources can be checked out from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScmType {
+    /// git repository
+    #[default]
+    Git,
+    /// subversion repository
+    Svn,
+}
+
+/// This enum represents the methods for building a source package from a checkout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SrpmBuildMethod {
+    /// build with `rpkg` (the spec file and sources are in the repository, or in the lookaside
+    /// cache)
+    #[default]
+    Rpkg,
+    /// build from the latest tag with `tito`
+    Tito,
+    /// build from the latest commit with `tito --test`
+    TitoTest,
+    /// build with the `srpm` target of the `.copr/Makefile` in the repository
+    MakeSrpm,
+}
+
+/// This enum represents the tools for generating a spec file for a package from PyPI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecGenerator {
+    /// generate the spec file with `pyp2spec`
+    Pyp2spec,
+    /// generate the spec file with `pyp2rpm`
+    Pyp2rpm,
+}
+
+/// This type contains the data for creating a new project.
+///
+/// ```
+/// use fedora::copr::NewProject;
+///
+/// let project = NewProject::new(
+///     "rust-fedora",
+///     &["fedora-rawhide-x86_64", "fedora-40-x86_64"],
+/// )
+/// .description("Development snapshots of the fedora crate.")
+/// .enable_net(true);
+/// ```
+#[derive(Clone, Debug, Serialize)]
+#[must_use]
+pub struct NewProject<'a> {
+    #[serde(rename = "projectname")]
+    name: &'a str,
+    chroots: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instructions: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contact: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    additional_repos: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enable_net: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unlisted_on_hp: Option<bool>,
+}
+
+impl<'a> NewProject<'a> {
+    /// This method constructs a new [`NewProject`] with the given name, which is built for the
+    /// given chroots (see [`CoprClient::chroots`] for the list of available chroots).
+    pub fn new(name: &'a str, chroots: &[&'a str]) -> Self {
+        NewProject {
+            name,
+            chroots: chroots.to_vec(),
+            description: None,
+            instructions: None,
+            homepage: None,
+            contact: None,
+            additional_repos: Vec::new(),
+            enable_net: None,
+            unlisted_on_hp: None,
+        }
+    }
+
+    /// Set the description of the project.
+    pub fn description(mut self, description: &'a str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Set the installation instructions of the project.
+    pub fn instructions(mut self, instructions: &'a str) -> Self {
+        self.instructions = Some(instructions);
+        self
+    }
+
+    /// Set the homepage of the project.
+    pub fn homepage(mut self, homepage: &'a str) -> Self {
+        self.homepage = Some(homepage);
+        self
+    }
+
+    /// Set the contact information of the project.
+    pub fn contact(mut self, contact: &'a str) -> Self {
+        self.contact = Some(contact);
+        self
+    }
+
+    /// Enable the given package repository for builds in the project.
+    pub fn additional_repo(mut self, repo: &'a str) -> Self {
+        self.additional_repos.push(repo);
+        self
+    }
+
+    /// Override whether builds have network access by default (default: no network access).
+    pub fn enable_net(mut self, enable_net: bool) -> Self {
+        self.enable_net = Some(enable_net);
+        self
+    }
+
+    /// Hide the project from the Copr homepage.
+    pub fn unlisted(mut self, unlisted: bool) -> Self {
+        self.unlisted_on_hp = Some(unlisted);
+        self
+    }
+}
+
+/// This type contains the options that apply to all kinds of builds.
+///
+/// ```
+/// use fedora::copr::BuildOptions;
+///
+/// let options = BuildOptions::new()
+///     .chroot("fedora-rawhide-x86_64")
+///     .timeout(3600)
+///     .background(true);
+/// ```
+#[derive(Clone, Debug, Default, Serialize)]
+#[must_use]
+pub struct BuildOptions<'a> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    chroots: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exclude_chroots: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enable_net: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after_build_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    with_build_id: Option<u64>,
+}
+
+impl<'a> BuildOptions<'a> {
+    /// This method constructs a new [`BuildOptions`], which builds for all chroots of the project
+    /// with the settings of the project.
+    pub fn new() -> Self {
+        BuildOptions::default()
+    }
+
+    /// Only build for the given chroot (instead of for all chroots of the project).
+    pub fn chroot(mut self, chroot: &'a str) -> Self {
+        self.chroots.push(chroot);
+        self
+    }
+
+    /// Do not build for the given chroot.
+    pub fn exclude_chroot(mut self, chroot: &'a str) -> Self {
+        self.exclude_chroots.push(chroot);
+        self
+    }
+
+    /// Override the build timeout (in seconds).
+    pub fn timeout(mut self, timeout: u32) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Override whether the build has network access (default: setting of the project).
+    pub fn enable_net(mut self, enable_net: bool) -> Self {
+        self.enable_net = Some(enable_net);
+        self
+    }
+
+    /// Submit the build with low priority.
+    pub fn background(mut self, background: bool) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Start the build only after the build with the given ID has finished.
+    pub fn after_build(mut self, build_id: u64) -> Self {
+        self.after_build_id = Some(build_id);
+        self
+    }
+
+    /// Add the build to the same batch as the build with the given ID.
+    pub fn with_build(mut self, build_id: u64) -> Self {
+        self.with_build_id = Some(build_id);
+        self
+    }
+}
+
+/// This type contains the data for building from a source code repository.
+///
+/// ```
+/// use fedora::copr::{ScmSource, SrpmBuildMethod};
+///
+/// let source = ScmSource::new("https://src.fedoraproject.org/rpms/rust-fedora.git")
+///     .committish("rawhide")
+///     .build_method(SrpmBuildMethod::Rpkg);
+/// ```
+#[derive(Clone, Debug, Serialize)]
+#[must_use]
+pub struct ScmSource<'a> {
+    clone_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committish: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subdirectory: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spec: Option<&'a str>,
+    scm_type: ScmType,
+    #[serde(rename = "source_build_method")]
+    build_method: SrpmBuildMethod,
+}
+
+impl<'a> ScmSource<'a> {
+    /// This method constructs a new [`ScmSource`] for the git repository at the given URL, which
+    /// is built from the default branch with `rpkg`.
+    pub fn new(clone_url: &'a str) -> Self {
+        ScmSource {
+            clone_url,
+            committish: None,
+            subdirectory: None,
+            spec: None,
+            scm_type: ScmType::Git,
+            build_method: SrpmBuildMethod::Rpkg,
+        }
+    }
+
+    /// Override the branch, tag, or commit that is checked out (default: default branch).
+    pub fn committish(mut self, committish: &'a str) -> Self {
+        self.committish = Some(committish);
+        self
+    }
+
+    /// Build from the given subdirectory of the repository.
+    pub fn subdirectory(mut self, subdirectory: &'a str) -> Self {
+        self.subdirectory = Some(subdirectory);
+        self
+    }
+
+    /// Override the path of the spec file (relative to the subdirectory).
+    pub fn spec(mut self, spec: &'a str) -> Self {
+        self.spec = Some(spec);
+        self
+    }
+
+    /// Override the type of the repository (default: git).
+    pub fn scm_type(mut self, scm_type: ScmType) -> Self {
+        self.scm_type = scm_type;
+        self
+    }
+
+    /// Override the method for building the source package (default: rpkg).
+    pub fn build_method(mut self, build_method: SrpmBuildMethod) -> Self {
+        self.build_method = build_method;
+        self
+    }
+}
+
+/// This type contains the data for building a package from PyPI.
+///
+/// ```
+/// use fedora::copr::{PypiSource, SpecGenerator};
+///
+/// let source = PypiSource::new("requests")
+///     .version("2.31.0")
+///     .spec_generator(SpecGenerator::Pyp2spec);
+/// ```
+#[derive(Clone, Debug, Serialize)]
+#[must_use]
+pub struct PypiSource<'a> {
+    #[serde(rename = "pypi_package_name")]
+    name: &'a str,
+    #[serde(rename = "pypi_package_version", skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spec_generator: Option<SpecGenerator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spec_template: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    python_versions: Vec<&'a str>,
+}
+
+impl<'a> PypiSource<'a> {
+    /// This method constructs a new [`PypiSource`] for the latest version of the given package.
+    pub fn new(name: &'a str) -> Self {
+        PypiSource {
+            name,
+            version: None,
+            spec_generator: None,
+            spec_template: None,
+            python_versions: Vec::new(),
+        }
+    }
+
+    /// Build the given version of the package (instead of the latest version).
+    pub fn version(mut self, version: &'a str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Override the tool for generating the spec file (default: setting of Copr).
+    pub fn spec_generator(mut self, spec_generator: SpecGenerator) -> Self {
+        self.spec_generator = Some(spec_generator);
+        self
+    }
+
+    /// Use the given spec file template (only supported by `pyp2rpm`, for example, "fedora").
+    pub fn spec_template(mut self, spec_template: &'a str) -> Self {
+        self.spec_template = Some(spec_template);
+        self
+    }
+
+    /// Build the package for the given major Python version (only supported by `pyp2rpm`).
+    pub fn python_version(mut self, python_version: &'a str) -> Self {
+        self.python_versions.push(python_version);
+        self
+    }
+}
+
+/// This type represents the body of requests that submit builds.
+#[derive(Serialize)]
+struct BuildRequest<'a, S: Serialize> {
+    ownername: &'a str,
+    projectname: &'a str,
+    #[serde(flatten)]
+    source: S,
+    #[serde(flatten)]
+    options: &'a BuildOptions<'a>,
+}
+
+impl CoprClient {
+    /// This method creates a new project that is owned by the given user or group (with group
+    /// names prefixed with "@").
+    pub async fn create_project(&self, owner: &str, project: &NewProject<'_>) -> Result<Project, CoprError> {
+        let body = serde_json::to_value(project)?;
+        self.api
+            .post(&ApiPath::file(&["project", "add", owner]), ApiBody::Json(&body))
+            .await
+    }
+
+    /// This method uploads the source package at the given path and submits a build for it.
+    ///
+    /// The file is streamed from disk instead of being read into memory first, so uploads can not
+    /// be retried automatically.
+    pub async fn build_from_file(
+        &self,
+        owner: &str,
+        project: &str,
+        path: &Path,
+        options: &BuildOptions<'_>,
+    ) -> Result<Build, CoprError> {
+        let file_error = |error| CoprError::FileSystemError {
+            path: path.to_path_buf(),
+            error,
+        };
+
+        let file = tokio::fs::File::open(path).await.map_err(file_error)?;
+        let length = file.metadata().await.map_err(file_error)?.len();
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("package.src.rpm"));
+
+        let data = BuildRequest {
+            ownername: owner,
+            projectname: project,
+            source: (),
+            options,
+        };
+
+        let form = Form::new().text("json", serde_json::to_string(&data)?).part(
+            "pkgs",
+            // the length is known up front, so the upload is not sent with chunked encoding
+            Part::stream_with_length(Body::from(file), length)
+                .file_name(file_name)
+                .mime_str("application/x-rpm")?,
+        );
+
+        let url = self.api.endpoint(&ApiPath::file(&["build", "create", "upload"]))?;
+        let request = self.session().session().post(url).multipart(form);
+
+        log::debug!("Uploading {} to Copr project {}/{}.", path.display(), owner, project);
+
+        let response = self.session().send(request).await?;
+        parse(response).await
+    }
+
+    /// This method submits a build from a source code repository.
+    pub async fn build_from_scm(
+        &self,
+        owner: &str,
+        project: &str,
+        source: &ScmSource<'_>,
+        options: &BuildOptions<'_>,
+    ) -> Result<Build, CoprError> {
+        let data = BuildRequest {
+            ownername: owner,
+            projectname: project,
+            source,
+            options,
+        };
+
+        let body = serde_json::to_value(data)?;
+        self.api
+            .post(&ApiPath::file(&["build", "create", "scm"]), ApiBody::Json(&body))
+            .await
+    }
+
+    /// This method submits a build of a package from PyPI.
+    pub async fn build_from_pypi(
+        &self,
+        owner: &str,
+        project: &str,
+        source: &PypiSource<'_>,
+        options: &BuildOptions<'_>,
+    ) -> Result<Build, CoprError> {
+        let data = BuildRequest {
+            ownername: owner,
+            projectname: project,
+            source,
+            options,
+        };
+
+        let body = serde_json::to_value(data)?;
+        self.api
+            .post(&ApiPath::file(&["build", "create", "pypi"]), ApiBody::Json(&body))
+            .await
+    }
+}
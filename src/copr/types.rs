@@ -0,0 +1,152 @@
+//! This module contains the data types that are returned by the Copr REST API.
+//!
+//! All types keep attributes that are not captured by known fields in a catch-all map, so no data
+//! is lost if Copr adds new attributes. Timestamps are kept as returned by Copr (seconds since the
+//! UNIX epoch).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum represents the state of a build (or of a build in a single chroot).
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum BuildState {
+    /// sources are being imported
+    Importing,
+    /// build is waiting for a builder
+    Pending,
+    /// builder is being prepared
+    Starting,
+    /// build is running
+    Running,
+    /// build succeeded
+    Succeeded,
+    /// build was copied from another project when it was forked
+    Forked,
+    /// build was skipped (because the package was already built)
+    Skipped,
+    /// build failed
+    Failed,
+    /// build was canceled
+    Canceled,
+    /// build is waiting for another build to finish
+    Waiting,
+    /// state that is not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
+impl BuildState {
+    /// This method returns `true` if the state is final, i.e. the build will not change its state
+    /// anymore.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            BuildState::Succeeded
+                | BuildState::Forked
+                | BuildState::Skipped
+                | BuildState::Failed
+                | BuildState::Canceled
+        )
+    }
+
+    /// This method returns `true` if the state is final and the build produced packages.
+    pub fn is_successful(&self) -> bool {
+        matches!(self, BuildState::Succeeded | BuildState::Forked | BuildState::Skipped)
+    }
+}
+
+/// This type represents a Copr project.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Project {
+    /// ID of the project
+    pub id: u64,
+    /// name of the project
+    pub name: String,
+    /// owner of the project (a user name, or a group name prefixed with "@")
+    pub ownername: String,
+    /// full name of the project (for example, "janedoe/rust-fedora")
+    pub full_name: String,
+    /// description of the project
+    pub description: Option<String>,
+    /// installation instructions of the project
+    pub instructions: Option<String>,
+    /// homepage of the project
+    pub homepage: Option<String>,
+    /// contact information of the project
+    pub contact: Option<String>,
+    /// URLs of the package repositories, keyed by chroot (for example, "fedora-rawhide-x86_64")
+    #[serde(default)]
+    pub chroot_repos: HashMap<String, String>,
+    /// additional package repositories that are enabled for builds
+    #[serde(default)]
+    pub additional_repos: Vec<String>,
+    /// whether builds have network access
+    #[serde(default)]
+    pub enable_net: bool,
+    /// whether the project is hidden from the Copr homepage
+    #[serde(default)]
+    pub unlisted_on_hp: bool,
+    /// whether builds and the project can not be deleted
+    #[serde(default)]
+    pub persistent: bool,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents the source package of a build.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct SourcePackage {
+    /// name of the package (not known until the sources were imported)
+    pub name: Option<String>,
+    /// version of the package, including release (not known until the sources were imported)
+    pub version: Option<String>,
+    /// URL of the source package
+    pub url: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a Copr build.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Build {
+    /// ID of the build
+    pub id: u64,
+    /// state of the build
+    pub state: BuildState,
+    /// owner of the project of the build
+    pub ownername: String,
+    /// name of the project of the build
+    pub projectname: String,
+    /// chroots the package is built for
+    #[serde(default)]
+    pub chroots: Vec<String>,
+    /// source package of the build
+    pub source_package: Option<SourcePackage>,
+    /// user who submitted the build
+    pub submitter: Option<String>,
+    /// time when the build was submitted
+    pub submitted_on: Option<i64>,
+    /// time when the build was started
+    pub started_on: Option<i64>,
+    /// time when the build finished
+    pub ended_on: Option<i64>,
+    /// URL of the build results
+    pub repo_url: Option<String>,
+    /// whether the build was submitted with low priority
+    #[serde(default)]
+    pub is_background: bool,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
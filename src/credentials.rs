@@ -0,0 +1,275 @@
+//! This module contains types for providing login credentials to sessions.
+//!
+//! Credentials are requested from a [`CredentialProvider`] only when they are needed for logging
+//! in. Implementations for prompting on the terminal ([`TerminalPrompt`]), reading environment
+//! variables ([`EnvironmentVariables`]), reading `.netrc` files ([`NetrcFile`]), running external
+//! credential helpers ([`CommandHelper`]), and looking up credentials in the Secret Service
+//! (`SecretServiceCredentials`, if the `secret-service` feature is enabled) are provided.
+
+use std::io::{stdin, stdout, Write};
+use std::path::PathBuf;
+
+use zeroize::Zeroize;
+
+use crate::auth::AuthError;
+use crate::secret::Secret;
+
+mod command;
+pub use command::CommandHelper;
+
+mod netrc;
+pub use netrc::NetrcFile;
+
+#[cfg(feature = "secret-service")]
+mod secret_service;
+#[cfg(feature = "secret-service")]
+pub use self::secret_service::SecretServiceCredentials;
+
+/// This collection of errors is returned when credentials could not be obtained from one of the
+/// built-in implementations of [`CredentialProvider`].
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    /// Reading from or writing to the terminal failed.
+    #[error("Failed to read credentials from the terminal: {error}")]
+    Terminal {
+        /// The inner error contains the I/O error that occurred.
+        error: std::io::Error,
+    },
+    /// A required environment variable is not set or is not valid unicode.
+    #[error("Environment variable {name} is not set.")]
+    MissingVariable {
+        /// name of the missing environment variable
+        name: String,
+    },
+    /// The home directory of the current user could not be determined.
+    #[error("Failed to determine the location of the .netrc file.")]
+    NoHomeDir,
+    /// The `.netrc` file could not be read.
+    #[error("Failed to read {}: {error}", path.display())]
+    FileSystemError {
+        /// path of the `.netrc` file
+        path: PathBuf,
+        /// The inner error contains the I/O error that occurred when reading the file.
+        error: std::io::Error,
+    },
+    /// No credentials were found for the requested host.
+    #[error("No credentials found for {host}.")]
+    NotFound {
+        /// name of the host that credentials were requested for
+        host: String,
+    },
+    /// The credential helper could not be run, failed, or returned invalid output.
+    #[error("Credential helper failed: {error}")]
+    Helper {
+        /// The inner string contains a description of the failure.
+        error: String,
+    },
+    /// Looking up credentials in the Secret Service failed.
+    #[error("Failed to look up credentials in the Secret Service: {error}")]
+    Store {
+        /// The inner error contains the error that occurred.
+        error: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+/// This type contains the username and password (and optionally, a one-time password for accounts
+/// with two-factor authentication) that are used for logging in.
+///
+/// The password is wrapped in a [`Secret`], so it does not show up in [`Debug`] output, and it is
+/// overwritten with zeroes when it is dropped.
+///
+/// ```
+/// use fedora::Credentials;
+///
+/// let credentials = Credentials::new("janedoe", "CorrectHorseBatteryStaple").otp("123456");
+/// assert_eq!(credentials.username, "janedoe");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    /// username
+    pub username: String,
+    /// password
+    pub password: Secret,
+    /// one-time password
+    pub otp: Option<Secret>,
+}
+
+impl Credentials {
+    /// This method constructs a new set of [`Credentials`] from a username and password.
+    pub fn new(username: &str, password: impl Into<Secret>) -> Self {
+        Credentials {
+            username: username.to_string(),
+            password: password.into(),
+            otp: None,
+        }
+    }
+
+    /// Log in with the given one-time password.
+    #[must_use]
+    pub fn otp(mut self, otp: impl Into<Secret>) -> Self {
+        self.otp = Some(otp.into());
+        self
+    }
+}
+
+/// This trait is implemented by all sources of login credentials.
+///
+/// Credentials are requested every time they are needed (for example, when an expired session is
+/// re-authenticated), and they are not stored by sessions. Providers are called on a thread where
+/// blocking is allowed (with `tokio::task::spawn_blocking`), so they can wait for user input or
+/// for external programs. The trait is also implemented for closures, so simple sources of
+/// credentials do not need to implement it manually:
+///
+/// ```
+/// use fedora::{CredentialProvider, Credentials};
+///
+/// let provider = || Ok(Credentials::new("janedoe", "CorrectHorseBatteryStaple"));
+/// assert_eq!(provider.credentials().unwrap().username, "janedoe");
+/// ```
+pub trait CredentialProvider: Send + Sync {
+    /// This method returns the credentials that should be used for logging in.
+    fn credentials(&self) -> Result<Credentials, AuthError>;
+}
+
+impl<F> CredentialProvider for F
+where
+    F: Fn() -> Result<Credentials, AuthError> + Send + Sync,
+{
+    fn credentials(&self) -> Result<Credentials, AuthError> {
+        self()
+    }
+}
+
+/// This trait is implemented by all sources of one-time passwords for accounts with two-factor
+/// authentication. One-time passwords are only requested when the server asks for one, and like
+/// [`CredentialProvider`]s, providers are called on a thread where blocking is allowed.
+///
+/// The trait is also implemented for closures:
+///
+/// ```
+/// use fedora::{OtpProvider, Secret};
+///
+/// let provider = || Ok(Secret::from("123456"));
+/// assert_eq!(provider.otp().unwrap().expose(), "123456");
+/// ```
+pub trait OtpProvider: Send + Sync {
+    /// This method returns the one-time password that should be used for logging in.
+    fn otp(&self) -> Result<Secret, AuthError>;
+}
+
+impl<F> OtpProvider for F
+where
+    F: Fn() -> Result<Secret, AuthError> + Send + Sync,
+{
+    fn otp(&self) -> Result<Secret, AuthError> {
+        self()
+    }
+}
+
+/// This type implements [`CredentialProvider`] by interactively prompting for the username (unless
+/// it was specified ahead of time) and password on the terminal. The password is not echoed.
+///
+/// It also implements [`OtpProvider`] by prompting for a one-time password.
+#[derive(Debug, Default)]
+pub struct TerminalPrompt {
+    username: Option<String>,
+}
+
+impl TerminalPrompt {
+    /// This method constructs a new [`TerminalPrompt`] that asks for both username and password.
+    pub fn new() -> Self {
+        TerminalPrompt::default()
+    }
+
+    /// Only prompt for the password of the given user.
+    #[must_use]
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = Some(username.to_string());
+        self
+    }
+}
+
+impl CredentialProvider for TerminalPrompt {
+    fn credentials(&self) -> Result<Credentials, AuthError> {
+        let terminal_error = |error| CredentialError::Terminal { error };
+
+        let username = match &self.username {
+            Some(username) => username.clone(),
+            None => {
+                let mut username = String::new();
+
+                print!("FAS username: ");
+                stdout().flush().map_err(terminal_error)?;
+                stdin().read_line(&mut username).map_err(terminal_error)?;
+
+                username.trim().to_string()
+            },
+        };
+
+        let password = Secret::new(rpassword::prompt_password("FAS password: ").map_err(terminal_error)?);
+
+        Ok(Credentials::new(&username, password))
+    }
+}
+
+impl OtpProvider for TerminalPrompt {
+    fn otp(&self) -> Result<Secret, AuthError> {
+        let terminal_error = |error| CredentialError::Terminal { error };
+
+        let mut otp = String::new();
+
+        print!("FAS one-time password: ");
+        stdout().flush().map_err(terminal_error)?;
+        stdin().read_line(&mut otp).map_err(terminal_error)?;
+
+        let secret = Secret::from(otp.trim());
+        otp.zeroize();
+
+        Ok(secret)
+    }
+}
+
+/// This type implements [`CredentialProvider`] by reading the username and password from
+/// environment variables (by default, `FAS_USERNAME` and `FAS_PASSWORD`).
+///
+/// ```
+/// use fedora::EnvironmentVariables;
+///
+/// let provider = EnvironmentVariables::new("BODHI_USER", "BODHI_PASSWORD");
+/// ```
+#[derive(Debug)]
+pub struct EnvironmentVariables {
+    username: String,
+    password: String,
+}
+
+impl Default for EnvironmentVariables {
+    fn default() -> Self {
+        EnvironmentVariables::new("FAS_USERNAME", "FAS_PASSWORD")
+    }
+}
+
+impl EnvironmentVariables {
+    /// This method constructs a new [`EnvironmentVariables`] provider that reads credentials from
+    /// the environment variables with the given names.
+    pub fn new(username: &str, password: &str) -> Self {
+        EnvironmentVariables {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+}
+
+/// This helper function reads the value of an environment variable.
+fn env_var(name: &str) -> Result<String, CredentialError> {
+    std::env::var(name).map_err(|_| CredentialError::MissingVariable { name: name.to_string() })
+}
+
+impl CredentialProvider for EnvironmentVariables {
+    fn credentials(&self) -> Result<Credentials, AuthError> {
+        let username = env_var(&self.username)?;
+        let password = Secret::new(env_var(&self.password)?);
+
+        Ok(Credentials::new(&username, password))
+    }
+}
@@ -0,0 +1,208 @@
+//! This module contains an implementation of [`CredentialProvider`] that runs external credential
+//! helper programs.
+
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use zeroize::Zeroize;
+
+use super::{CredentialError, CredentialProvider, Credentials};
+use crate::auth::AuthError;
+use crate::secret::Secret;
+
+/// This type implements [`CredentialProvider`] by running an external program, similar to `git`
+/// credential helpers.
+///
+/// The program receives a description of the requested credentials on standard input, in the same
+/// format that is used by `git credential` (`key=value` lines, terminated by an empty line):
+///
+/// ```text
+/// protocol=https
+/// host=id.fedoraproject.org
+/// username=janedoe
+/// ```
+///
+/// It is expected to print the credentials to standard output in the same format. Lines for the
+/// `password` key are required, lines for the `username` key are optional if a username was
+/// specified ahead of time, and all other lines are ignored.
+///
+/// ```
+/// use fedora::CommandHelper;
+///
+/// let provider = CommandHelper::new("pass-helper")
+///     .arg("fedora")
+///     .host("id.fedoraproject.org")
+///     .username("janedoe");
+/// ```
+#[derive(Debug)]
+pub struct CommandHelper {
+    program: OsString,
+    args: Vec<OsString>,
+    host: Option<String>,
+    username: Option<String>,
+}
+
+impl CommandHelper {
+    /// This method constructs a new [`CommandHelper`] that runs the given program.
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        CommandHelper {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            host: None,
+            username: None,
+        }
+    }
+
+    /// Pass an additional argument to the program.
+    #[must_use]
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Request credentials for the given host.
+    #[must_use]
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_string());
+        self
+    }
+
+    /// Request the password for the given user.
+    #[must_use]
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = Some(username.to_string());
+        self
+    }
+
+    /// This method returns the description of the requested credentials that is passed to the
+    /// program.
+    fn request(&self) -> String {
+        let mut request = String::from("protocol=https\n");
+
+        if let Some(host) = &self.host {
+            request.push_str(&format!("host={}\n", host));
+        }
+
+        if let Some(username) = &self.username {
+            request.push_str(&format!("username={}\n", username));
+        }
+
+        request.push('\n');
+        request
+    }
+}
+
+/// This helper function constructs an error for credential helper failures.
+fn helper_error(error: impl ToString) -> CredentialError {
+    CredentialError::Helper {
+        error: error.to_string(),
+    }
+}
+
+impl CredentialProvider for CommandHelper {
+    fn credentials(&self) -> Result<Credentials, AuthError> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(helper_error)?;
+
+        // closing stdin signals the end of the request
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(self.request().as_bytes()).map_err(helper_error)?;
+        }
+
+        let mut output = child.wait_with_output().map_err(helper_error)?;
+
+        if !output.status.success() {
+            output.stdout.zeroize();
+            return Err(helper_error(format!("{:?} exited with {}", self.program, output.status)).into());
+        }
+
+        let mut stdout = String::from_utf8(output.stdout).map_err(|error| {
+            let mut bytes = error.into_bytes();
+            bytes.zeroize();
+            helper_error("output is not valid UTF-8")
+        })?;
+
+        let mut username = self.username.clone();
+        let mut password = None;
+
+        for line in stdout.lines() {
+            match line.split_once('=') {
+                Some(("username", value)) => username = Some(value.to_string()),
+                Some(("password", value)) => password = Some(Secret::from(value)),
+                _ => {},
+            }
+        }
+
+        stdout.zeroize();
+
+        match (username, password) {
+            (Some(username), Some(password)) => Ok(Credentials::new(&username, password)),
+            (None, _) => Err(helper_error("output does not contain a username").into()),
+            (_, None) => Err(helper_error("output does not contain a password").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// This helper function returns a [`CommandHelper`] that runs the given shell script.
+    fn script(script: &str) -> CommandHelper {
+        CommandHelper::new("sh").arg("-c").arg(script)
+    }
+
+    fn helper_failure(helper: CommandHelper) -> String {
+        let error = helper.credentials().unwrap_err();
+        match error.downcast_ref() {
+            Some(CredentialError::Helper { error }) => error.clone(),
+            _ => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn credentials_are_read_from_the_output() {
+        // the request is passed on standard input
+        let credentials =
+            script("grep -q '^host=id.fedoraproject.org$' && printf 'username=janedoe\\npassword=hunter2\\n'")
+                .host("id.fedoraproject.org")
+                .credentials()
+                .unwrap();
+        assert_eq!(credentials.username, "janedoe");
+        assert_eq!(credentials.password.expose(), "hunter2");
+
+        // the username does not need to be repeated
+        let credentials = script("cat > /dev/null; echo password=hunter2")
+            .username("janedoe")
+            .credentials()
+            .unwrap();
+        assert_eq!(credentials.username, "janedoe");
+    }
+
+    #[test]
+    fn failing_helpers_are_errors() {
+        let error = helper_failure(script("cat > /dev/null; echo password=hunter2; exit 3"));
+        assert!(error.contains("exited"), "{}", error);
+        assert!(!error.contains("hunter2"), "{}", error);
+
+        let error = helper_failure(CommandHelper::new("/nonexistent/fedora-rs-helper"));
+        assert!(!error.is_empty());
+    }
+
+    #[test]
+    fn incomplete_output_is_rejected() {
+        let error = helper_failure(script("cat > /dev/null; echo username=janedoe").username("janedoe"));
+        assert!(error.contains("password"), "{}", error);
+
+        let error = helper_failure(script("cat > /dev/null; echo password=hunter2"));
+        assert!(error.contains("username"), "{}", error);
+    }
+}
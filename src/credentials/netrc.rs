@@ -0,0 +1,323 @@
+//! This module contains an implementation of [`CredentialProvider`] that reads credentials from
+//! `.netrc` files.
+
+use std::path::{Path, PathBuf};
+
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{CredentialError, CredentialProvider, Credentials};
+use crate::auth::AuthError;
+use crate::secret::Secret;
+
+/// This type implements [`CredentialProvider`] by looking up the login and password for a host in
+/// a `.netrc` file (by default, the file specified by the `NETRC` environment variable, or
+/// `~/.netrc`). If there is no entry for the host, the `default` entry is used, if present.
+/// Values that contain whitespace can be enclosed in double quotes.
+///
+/// ```
+/// use fedora::NetrcFile;
+///
+/// let provider = NetrcFile::new("id.fedoraproject.org");
+/// ```
+#[derive(Debug)]
+pub struct NetrcFile {
+    host: String,
+    path: Option<PathBuf>,
+}
+
+impl NetrcFile {
+    /// This method constructs a new [`NetrcFile`] provider for the given host.
+    pub fn new(host: &str) -> Self {
+        NetrcFile {
+            host: host.to_string(),
+            path: None,
+        }
+    }
+
+    /// Read the given file instead of the default `.netrc` file.
+    #[must_use]
+    pub fn path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// This method returns the path of the `.netrc` file.
+    fn file_path(&self) -> Result<PathBuf, CredentialError> {
+        if let Some(path) = &self.path {
+            return Ok(path.clone());
+        }
+
+        if let Some(path) = std::env::var_os("NETRC") {
+            return Ok(PathBuf::from(path));
+        }
+
+        Ok(dirs::home_dir().ok_or(CredentialError::NoHomeDir)?.join(".netrc"))
+    }
+}
+
+/// This helper function splits a line of a `.netrc` file into tokens, which are separated by
+/// whitespace. Tokens that contain whitespace can be enclosed in double quotes, and can contain
+/// backslash escapes (`\"`, `\\`, `\n`, `\r`, and `\t`).
+fn tokenize(line: &str) -> Vec<Zeroizing<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let quoted = match chars.peek() {
+            None => break,
+            Some('"') => {
+                chars.next();
+                true
+            },
+            Some(_) => false,
+        };
+
+        // tokens are never longer than the line, so the buffer is not reallocated (which would
+        // leave copies of passwords in memory)
+        let mut token = Zeroizing::new(String::with_capacity(line.len()));
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted => break,
+                '\\' if quoted => match chars.next() {
+                    Some('n') => token.push('\n'),
+                    Some('r') => token.push('\r'),
+                    Some('t') => token.push('\t'),
+                    Some(other) => token.push(other),
+                    None => break,
+                },
+                c if !quoted && c.is_whitespace() => break,
+                c => token.push(c),
+            }
+        }
+
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// This type contains the values of an entry of a `.netrc` file.
+#[derive(Default)]
+struct Entry {
+    login: Option<String>,
+    password: Option<Secret>,
+}
+
+/// This helper function returns the login and password for the given host from the contents of a
+/// `.netrc` file.
+///
+/// The first entry for the host is used, or the `default` entry if there is none. If the entry for
+/// the host does not contain both a login and a password, no credentials are returned (the login of
+/// the `default` entry most likely belongs to a different account).
+fn lookup(contents: &str, host: &str) -> Option<(String, Secret)> {
+    // values of the entry for the host, and of the "default" entry
+    let mut matching: Option<Entry> = None;
+    let mut default: Option<Entry> = None;
+
+    // whether the entry that is currently being parsed is the one for the host, or the default one
+    let mut in_matching = false;
+    let mut in_default = false;
+
+    let mut lines = contents.lines();
+    'lines: while let Some(line) = lines.next() {
+        let mut tokens = tokenize(line).into_iter();
+
+        while let Some(token) = tokens.next() {
+            match token.as_str() {
+                "machine" | "default" => {
+                    // the entry for the host is complete
+                    if in_matching {
+                        break 'lines;
+                    }
+
+                    if token.as_str() == "machine" {
+                        in_matching = tokens.next().map_or(false, |name| name.as_str() == host);
+                        in_default = false;
+                        if in_matching {
+                            matching = Some(Entry::default());
+                        }
+                    } else {
+                        in_default = true;
+                        default = Some(Entry::default());
+                    }
+                },
+                "login" | "password" | "account" => {
+                    let value = tokens.next();
+
+                    let entry = if in_matching {
+                        matching.as_mut()
+                    } else if in_default {
+                        default.as_mut()
+                    } else {
+                        None
+                    };
+
+                    if let (Some(entry), Some(value)) = (entry, value) {
+                        match token.as_str() {
+                            "login" => entry.login = Some(value.to_string()),
+                            "password" => entry.password = Some(Secret::from(value.as_str())),
+                            _ => {},
+                        }
+                    }
+                },
+                "macdef" => {
+                    // macro definitions extend until the next empty line
+                    for line in lines.by_ref() {
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                    }
+                    continue 'lines;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    match matching.or(default) {
+        Some(Entry {
+            login: Some(login),
+            password: Some(password),
+        }) => Some((login, password)),
+        _ => None,
+    }
+}
+
+impl CredentialProvider for NetrcFile {
+    fn credentials(&self) -> Result<Credentials, AuthError> {
+        let path = self.file_path()?;
+
+        let mut contents =
+            std::fs::read_to_string(&path).map_err(|error| CredentialError::FileSystemError { path, error })?;
+        let entry = lookup(&contents, &self.host);
+        contents.zeroize();
+
+        match entry {
+            Some((username, password)) => Ok(Credentials::new(&username, password)),
+            None => Err(CredentialError::NotFound {
+                host: self.host.clone(),
+            }
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn login(contents: &str, host: &str) -> Option<(String, String)> {
+        lookup(contents, host).map(|(login, password)| (login, password.expose().to_string()))
+    }
+
+    fn entry(login: &str, password: &str) -> Option<(String, String)> {
+        Some((login.to_string(), password.to_string()))
+    }
+
+    /// This helper function writes a temporary `.netrc` file with the given contents.
+    fn write_netrc(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fedora-rs-test-{}-{}.netrc", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn entries_are_matched_by_host() {
+        let contents = "machine src.fedoraproject.org login packager password hunter1\n\
+                        machine id.fedoraproject.org\n  login janedoe\n  password hunter2\n\
+                        machine id.fedoraproject.org login other password hunter3\n";
+
+        assert_eq!(login(contents, "src.fedoraproject.org"), entry("packager", "hunter1"));
+        // the first entry for a host is used
+        assert_eq!(login(contents, "id.fedoraproject.org"), entry("janedoe", "hunter2"));
+        assert_eq!(login(contents, "bodhi.fedoraproject.org"), None);
+    }
+
+    #[test]
+    fn default_entries_are_used_for_other_hosts() {
+        let contents = "machine id.fedoraproject.org login janedoe password hunter2\n\
+                        default login anonymous password guest\n";
+
+        assert_eq!(login(contents, "id.fedoraproject.org"), entry("janedoe", "hunter2"));
+        assert_eq!(login(contents, "bodhi.fedoraproject.org"), entry("anonymous", "guest"));
+    }
+
+    #[test]
+    fn macro_definitions_are_skipped() {
+        let contents = "macdef init\nmachine evil.example.com\nlogin mallory password hunter3\n\n\
+                        machine id.fedoraproject.org login janedoe password hunter2\n";
+
+        assert_eq!(login(contents, "id.fedoraproject.org"), entry("janedoe", "hunter2"));
+        assert_eq!(login(contents, "evil.example.com"), None);
+
+        // the macro extends until the end of the file if there is no empty line
+        let contents = "machine id.fedoraproject.org login janedoe\nmacdef init\npassword hunter3\n";
+        assert_eq!(login(contents, "id.fedoraproject.org"), None);
+    }
+
+    #[test]
+    fn quoted_tokens_can_contain_whitespace() {
+        let contents = r#"machine id.fedoraproject.org login "jane doe" password "correct horse \"battery\" staple""#;
+        assert_eq!(
+            login(contents, "id.fedoraproject.org"),
+            entry("jane doe", "correct horse \"battery\" staple")
+        );
+
+        let tokens: Vec<String> = tokenize(r#"password "a\tb\\c" "" last"#)
+            .iter()
+            .map(|token| token.to_string())
+            .collect();
+        assert_eq!(tokens, vec!["password", "a\tb\\c", "", "last"]);
+    }
+
+    #[test]
+    fn entries_without_password_are_not_used() {
+        let contents = "machine id.fedoraproject.org login janedoe\n\
+                        default login anonymous password guest\n";
+
+        // the login of the default entry belongs to a different account
+        assert_eq!(login(contents, "id.fedoraproject.org"), None);
+
+        let path = write_netrc("missing-password", contents);
+        let error = NetrcFile::new("id.fedoraproject.org")
+            .path(&path)
+            .credentials()
+            .unwrap_err();
+        assert!(
+            matches!(error.downcast_ref(), Some(CredentialError::NotFound { .. })),
+            "{:?}",
+            error
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn credentials_are_read_from_files() {
+        let path = write_netrc(
+            "credentials",
+            "machine id.fedoraproject.org login janedoe password hunter2\n",
+        );
+
+        let credentials = NetrcFile::new("id.fedoraproject.org")
+            .path(&path)
+            .credentials()
+            .unwrap();
+        assert_eq!(credentials.username, "janedoe");
+        assert_eq!(credentials.password.expose(), "hunter2");
+
+        std::fs::remove_file(&path).unwrap();
+        let error = NetrcFile::new("id.fedoraproject.org")
+            .path(&path)
+            .credentials()
+            .unwrap_err();
+        assert!(
+            matches!(error.downcast_ref(), Some(CredentialError::FileSystemError { .. })),
+            "{:?}",
+            error
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! This module contains an implementation of [`CredentialProvider`] that looks up passwords in the
+//! Secret Service (for example, GNOME Keyring or KWallet) via D-Bus.
+
+use std::collections::HashMap;
+
+use secret_service::blocking::SecretService;
+use secret_service::EncryptionType;
+use zeroize::Zeroize;
+
+use super::{CredentialError, CredentialProvider, Credentials};
+use crate::auth::AuthError;
+use crate::secret::Secret;
+
+/// value of the "application" attribute of all items that are created by this crate
+const APPLICATION: &str = "fedora-rs";
+
+/// This helper function converts errors from the Secret Service into [`CredentialError`]s.
+fn store_error(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> CredentialError {
+    CredentialError::Store { error: error.into() }
+}
+
+/// This type implements [`CredentialProvider`] by looking up the password of a user in the default
+/// collection of the Secret Service.
+///
+/// Passwords can be stored with [`SecretServiceCredentials::save`], or with other tools (for
+/// example, `secret-tool store --label=... application fedora-rs service <service> username
+/// <username>`).
+///
+/// This type is only available if the `secret-service` feature is enabled.
+#[derive(Debug)]
+pub struct SecretServiceCredentials {
+    service: String,
+    username: String,
+}
+
+impl SecretServiceCredentials {
+    /// This method constructs a new [`SecretServiceCredentials`] provider for the given user of the
+    /// given service (for example, "fas").
+    pub fn new(service: &str, username: &str) -> Self {
+        SecretServiceCredentials {
+            service: service.to_string(),
+            username: username.to_string(),
+        }
+    }
+
+    /// This method returns the attributes that are used to look up the password.
+    fn attributes(&self) -> HashMap<&str, &str> {
+        HashMap::from([
+            ("application", APPLICATION),
+            ("service", self.service.as_str()),
+            ("username", self.username.as_str()),
+        ])
+    }
+
+    /// This method stores the given password in the Secret Service, replacing any existing
+    /// password for the same user and service.
+    pub fn save(&self, password: &Secret) -> Result<(), CredentialError> {
+        let service = SecretService::connect(EncryptionType::Dh).map_err(store_error)?;
+
+        let collection = service.get_default_collection().map_err(store_error)?;
+        collection.ensure_unlocked().map_err(store_error)?;
+
+        let label = format!("fedora-rs password ({}@{})", self.username, self.service);
+
+        collection
+            .create_item(
+                &label,
+                self.attributes(),
+                password.expose().as_bytes(),
+                true,
+                "text/plain",
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+}
+
+impl CredentialProvider for SecretServiceCredentials {
+    fn credentials(&self) -> Result<Credentials, AuthError> {
+        let service = SecretService::connect(EncryptionType::Dh).map_err(store_error)?;
+
+        let result = service.search_items(self.attributes()).map_err(store_error)?;
+
+        if !result.locked.is_empty() {
+            let locked: Vec<_> = result.locked.iter().collect();
+            service.unlock_all(&locked).map_err(store_error)?;
+        }
+
+        let item = match result.unlocked.into_iter().chain(result.locked).next() {
+            Some(item) => item,
+            None => {
+                return Err(CredentialError::NotFound {
+                    host: self.service.clone(),
+                }
+                .into())
+            },
+        };
+
+        let secret = item.get_secret().map_err(store_error)?;
+        let password = String::from_utf8(secret).map_err(|error| {
+            let mut bytes = error.into_bytes();
+            bytes.zeroize();
+            store_error("Stored password is not valid UTF-8.")
+        })?;
+
+        Ok(Credentials::new(&self.username, password))
+    }
+}
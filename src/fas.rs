@@ -0,0 +1,269 @@
+//! This module contains a typed client for the read-only REST API of the Fedora Account System
+//! ([FASJSON](https://fasjson.fedoraproject.org)), which is built on top of a [`Session`].
+//!
+//! It can be used to look up users and groups, and to check whether a user is a member or sponsor
+//! of a group (for example, "packager" or "provenpackager"), which is the basis for most access
+//! checks in Fedora.
+//!
+//! Requests are sent with the authentication of the [`Session`]. Deployments that allow anonymous
+//! access can be queried with an anonymous session, but the Fedora instance of FASJSON requires
+//! Kerberos authentication for all requests (for example, with `Session::kerberos` if the
+//! `gssapi` feature is enabled). Requests that were rejected because of missing authentication
+//! return an error for which [`FasError::is_auth_failure`] returns `true`.
+//!
+//! ```ignore
+//! use fedora::fas::FasClient;
+//! use fedora::Session;
+//!
+//! let fas = FasClient::new(Session::kerberos().build().await?);
+//!
+//! if fas.is_member("provenpackager", "janedoe").await? {
+//!     println!("janedoe can commit to all packages.");
+//! }
+//! ```
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+use crate::client::{api_error, fixed_url, ApiClient, ApiError, ApiPath, PathError};
+use crate::openid::excerpt;
+use crate::session::Session;
+
+mod query;
+pub use query::UserQuery;
+
+mod types;
+pub use types::{Group, Identity, Membership, User};
+
+/// base URL of the production instance of FASJSON
+const FEDORA_FASJSON_URL: &str = "https://fasjson.fedoraproject.org/";
+/// base URL of the staging instance of FASJSON
+const FEDORA_FASJSON_STG_URL: &str = "https://fasjson.stg.fedoraproject.org/";
+
+/// number of results that are requested per page for listings
+const PAGE_SIZE: u32 = 100;
+
+api_error! {
+    service: "FASJSON",
+    /// This collection of errors is returned for failures when sending requests to FASJSON.
+    pub enum FasError {
+        Status {},
+        /// This error represents an issue with constructing the URL of an API endpoint.
+        #[error("Failed to construct URL: {error}")]
+        UrlParsing {
+            /// The inner error contains the error that occurred when parsing the URL.
+            #[from]
+            error: url::ParseError,
+        },
+        /// This error is returned when a user or group name can not be used in the path of an API
+        /// endpoint (because it is empty, or because it is "." or "..").
+        #[error("Invalid name in API path: {name:?}")]
+        InvalidName {
+            /// name that was rejected
+            name: String,
+        },
+    }
+}
+
+impl ApiError for FasError {
+    fn from_response(status: StatusCode, body: &str) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorResponse {
+            message: String,
+        }
+
+        let message = match serde_json::from_str::<ErrorResponse>(body) {
+            Ok(response) => response.message,
+            Err(_) => excerpt(body),
+        };
+
+        FasError::Status { status, message }
+    }
+}
+
+impl From<PathError> for FasError {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::Base(error) => FasError::UrlParsing { error },
+            PathError::Segment(name) => FasError::InvalidName { name },
+        }
+    }
+}
+
+/// This type represents the wrapper around the results of all FASJSON responses.
+#[derive(Deserialize)]
+struct ResultResponse<T> {
+    result: T,
+}
+
+/// This type is a typed client for the read-only FASJSON REST API (version 1).
+///
+/// All requests are sent with [`Session::send`], so they are authenticated by the [`Session`] (if
+/// it is authenticated), and they are retried and rate-limited according to its settings.
+#[derive(Debug)]
+pub struct FasClient {
+    api: ApiClient<FasError>,
+}
+
+impl FasClient {
+    /// This method constructs a new [`FasClient`] for the production instance of FASJSON.
+    pub fn new(session: Session) -> Self {
+        FasClient::with_url(session, fixed_url(FEDORA_FASJSON_URL))
+    }
+
+    /// This method constructs a new [`FasClient`] for the staging instance of FASJSON.
+    pub fn staging(session: Session) -> Self {
+        FasClient::with_url(session, fixed_url(FEDORA_FASJSON_STG_URL))
+    }
+
+    /// This method constructs a new [`FasClient`] for the instance of FASJSON at the given URL.
+    pub fn with_url(session: Session, url: Url) -> Self {
+        FasClient {
+            api: ApiClient::rest(session, url, &["v1"]),
+        }
+    }
+
+    /// This method returns a reference to the wrapped [`Session`].
+    pub fn session(&self) -> &Session {
+        self.api.session()
+    }
+
+    /// This method returns the base URL of the FASJSON instance.
+    pub fn url(&self) -> &Url {
+        self.api.url()
+    }
+
+    /// This method sends a `GET` request to the given API path and returns the deserialized result.
+    async fn get<T: DeserializeOwned>(&self, path: &ApiPath<'_>, params: &[(&str, String)]) -> Result<T, FasError> {
+        let response: ResultResponse<T> = self.api.get(path, params).await?;
+        Ok(response.result)
+    }
+
+    /// This method sends a `GET` request to the given API path and returns the deserialized result,
+    /// or returns `None` if FASJSON returned "404 Not Found".
+    async fn get_optional<T: DeserializeOwned>(&self, path: &ApiPath<'_>) -> Result<Option<T>, FasError> {
+        let response: Option<ResultResponse<T>> = self.api.get_optional(path, &[]).await?;
+        Ok(response.map(|response| response.result))
+    }
+
+    /// This method returns all results of a paginated listing, by requesting all pages one after
+    /// another.
+    async fn all<T: DeserializeOwned>(
+        &self,
+        path: &ApiPath<'_>,
+        params: &[(&'static str, String)],
+    ) -> Result<Vec<T>, FasError> {
+        #[derive(Deserialize)]
+        struct Pagination {
+            page_number: u32,
+            total_pages: u32,
+        }
+
+        #[derive(Deserialize)]
+        struct PageResponse<T> {
+            result: Vec<T>,
+            page: Option<Pagination>,
+        }
+
+        let mut items = Vec::new();
+        let mut page: u32 = 1;
+
+        loop {
+            let mut params = params.to_vec();
+            params.push(("page_size", PAGE_SIZE.to_string()));
+            params.push(("page_number", page.to_string()));
+
+            let mut response: PageResponse<T> = self.api.get(path, &params).await?;
+
+            let done = match response.page {
+                Some(pagination) => {
+                    log::debug!(
+                        "Received page {} of {} for FASJSON listing {}.",
+                        pagination.page_number,
+                        pagination.total_pages,
+                        path.segments.join("/")
+                    );
+                    response.result.is_empty() || pagination.page_number >= pagination.total_pages
+                },
+                // listing was not paginated
+                None => true,
+            };
+            items.append(&mut response.result);
+
+            if done {
+                return Ok(items);
+            }
+            page += 1;
+        }
+    }
+
+    /// This method returns the user or service that the session is authenticated as.
+    pub async fn me(&self) -> Result<Identity, FasError> {
+        self.get(&ApiPath::dir(&["me"]), &[]).await
+    }
+
+    /// This method returns the user with the given name (or `None` if it does not exist).
+    pub async fn user(&self, username: &str) -> Result<Option<User>, FasError> {
+        self.get_optional(&ApiPath::dir(&["users", username])).await
+    }
+
+    /// This method returns the groups that the given user is a member of.
+    ///
+    /// Only the names of the groups are included in the results.
+    pub async fn user_groups(&self, username: &str) -> Result<Vec<Group>, FasError> {
+        self.all(&ApiPath::dir(&["users", username, "groups"]), &[]).await
+    }
+
+    /// This method returns the users that match the query.
+    pub async fn search_users(&self, query: &UserQuery<'_>) -> Result<Vec<User>, FasError> {
+        self.all(&ApiPath::dir(&["search", "users"]), &query.params()).await
+    }
+
+    /// This method returns the group with the given name (or `None` if it does not exist).
+    pub async fn group(&self, groupname: &str) -> Result<Option<Group>, FasError> {
+        self.get_optional(&ApiPath::dir(&["groups", groupname])).await
+    }
+
+    /// This method returns all groups.
+    pub async fn groups(&self) -> Result<Vec<Group>, FasError> {
+        self.all(&ApiPath::dir(&["groups"]), &[]).await
+    }
+
+    /// This method returns the members of the given group.
+    ///
+    /// Only the names of the users are included in the results.
+    pub async fn group_members(&self, groupname: &str) -> Result<Vec<User>, FasError> {
+        self.all(&ApiPath::dir(&["groups", groupname, "members"]), &[]).await
+    }
+
+    /// This method returns the sponsors of the given group, i.e. the users who can add new members
+    /// to the group.
+    ///
+    /// Only the names of the users are included in the results.
+    pub async fn group_sponsors(&self, groupname: &str) -> Result<Vec<User>, FasError> {
+        self.all(&ApiPath::dir(&["groups", groupname, "sponsors"]), &[]).await
+    }
+
+    /// This method checks whether the given user is a member of the given group.
+    pub async fn is_member(&self, groupname: &str, username: &str) -> Result<bool, FasError> {
+        self.get(&ApiPath::file(&["groups", groupname, "is-member", username]), &[])
+            .await
+    }
+
+    /// This method checks whether the given user is a sponsor of the given group.
+    pub async fn is_sponsor(&self, groupname: &str, username: &str) -> Result<bool, FasError> {
+        self.get(&ApiPath::file(&["groups", groupname, "is-sponsor", username]), &[])
+            .await
+    }
+
+    /// This method returns both the membership and the sponsorship status of the given user in the
+    /// given group.
+    pub async fn membership(&self, groupname: &str, username: &str) -> Result<Membership, FasError> {
+        let member = self.is_member(groupname, username).await?;
+        let sponsor = self.is_sponsor(groupname, username).await?;
+
+        Ok(Membership { member, sponsor })
+    }
+}
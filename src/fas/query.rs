@@ -0,0 +1,93 @@
+//! This module contains the query builder for searching users.
+
+/// This type represents a search for users.
+///
+/// All filters match substrings of the corresponding attribute, and they can contain `*` as
+/// wildcard. At least one filter needs to be set.
+///
+/// ```
+/// use fedora::fas::UserQuery;
+///
+/// let query = UserQuery::new()
+///     .username("jane*")
+///     .github_username("janedoe");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct UserQuery<'a> {
+    username: Option<&'a str>,
+    email: Option<&'a str>,
+    ircnick: Option<&'a str>,
+    human_name: Option<&'a str>,
+    github_username: Option<&'a str>,
+    gitlab_username: Option<&'a str>,
+}
+
+impl<'a> UserQuery<'a> {
+    /// This method constructs a new [`UserQuery`] without any filters.
+    pub fn new() -> Self {
+        UserQuery::default()
+    }
+
+    /// Only return users whose user name matches the given pattern.
+    pub fn username(mut self, username: &'a str) -> Self {
+        self.username = Some(username);
+        self
+    }
+
+    /// Only return users whose email address matches the given pattern.
+    pub fn email(mut self, email: &'a str) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// Only return users whose IRC or Matrix nickname matches the given pattern.
+    pub fn ircnick(mut self, ircnick: &'a str) -> Self {
+        self.ircnick = Some(ircnick);
+        self
+    }
+
+    /// Only return users whose full name matches the given pattern.
+    pub fn human_name(mut self, human_name: &'a str) -> Self {
+        self.human_name = Some(human_name);
+        self
+    }
+
+    /// Only return users whose GitHub user name matches the given pattern.
+    pub fn github_username(mut self, github_username: &'a str) -> Self {
+        self.github_username = Some(github_username);
+        self
+    }
+
+    /// Only return users whose GitLab user name matches the given pattern.
+    pub fn gitlab_username(mut self, gitlab_username: &'a str) -> Self {
+        self.gitlab_username = Some(gitlab_username);
+        self
+    }
+
+    /// This method returns the query parameters of the query.
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+
+        if let Some(username) = self.username {
+            params.push(("username", username.to_string()));
+        }
+        if let Some(email) = self.email {
+            params.push(("email", email.to_string()));
+        }
+        if let Some(ircnick) = self.ircnick {
+            params.push(("ircnick", ircnick.to_string()));
+        }
+        if let Some(human_name) = self.human_name {
+            params.push(("human_name", human_name.to_string()));
+        }
+        if let Some(github_username) = self.github_username {
+            params.push(("github_username", github_username.to_string()));
+        }
+        if let Some(gitlab_username) = self.gitlab_username {
+            params.push(("gitlab_username", gitlab_username.to_string()));
+        }
+
+        params
+    }
+}
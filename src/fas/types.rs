@@ -0,0 +1,115 @@
+//! This module contains the data types that are returned by the Fedora Account System.
+//!
+//! Listings only contain some attributes of users and groups (for example, only the user name of
+//! group members), so most fields are optional. All types keep attributes that are not captured by
+//! known fields in a catch-all map, so no data is lost if FASJSON adds new attributes.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// This type represents the identity that the session is authenticated as.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Identity {
+    /// name of the user (if the session is authenticated as a user)
+    pub username: Option<String>,
+    /// name of the service (if the session is authenticated as a service)
+    pub service: Option<String>,
+    /// Kerberos principal of the user or service
+    pub krbname: Option<String>,
+    /// URL of the user or service in the FASJSON API
+    pub uri: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a Fedora account.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct User {
+    /// name of the user
+    pub username: String,
+    /// full name of the user
+    pub human_name: Option<String>,
+    /// given name of the user
+    pub givenname: Option<String>,
+    /// surname of the user
+    pub surname: Option<String>,
+    /// email addresses of the user
+    #[serde(default)]
+    pub emails: Vec<String>,
+    /// email address of the user in Red Hat Bugzilla (if it is different from the primary email
+    /// address)
+    pub rhbzemail: Option<String>,
+    /// IRC and Matrix nicknames of the user
+    #[serde(default)]
+    pub ircnicks: Vec<String>,
+    /// IDs of the GPG keys of the user
+    #[serde(default)]
+    pub gpgkeyids: Vec<String>,
+    /// pronouns of the user
+    #[serde(default)]
+    pub pronouns: Vec<String>,
+    /// preferred locale of the user
+    pub locale: Option<String>,
+    /// time zone of the user
+    pub timezone: Option<String>,
+    /// GitHub user name of the user
+    pub github_username: Option<String>,
+    /// GitLab user name of the user
+    pub gitlab_username: Option<String>,
+    /// website of the user
+    pub website: Option<String>,
+    /// time when the account was created
+    pub creation: Option<String>,
+    /// whether the account is locked
+    #[serde(default)]
+    pub locked: bool,
+    /// whether the user chose to hide their personal information
+    #[serde(default)]
+    pub is_private: bool,
+    /// URL of the user in the FASJSON API
+    pub uri: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a group (for example, "packager").
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Group {
+    /// name of the group
+    pub groupname: String,
+    /// description of the group
+    pub description: Option<String>,
+    /// mailing list of the group
+    pub mailing_list: Option<String>,
+    /// website of the group
+    pub url: Option<String>,
+    /// IRC and Matrix channels of the group
+    #[serde(default)]
+    pub irc: Vec<String>,
+    /// URL of the discussion forum of the group
+    pub discussion_url: Option<String>,
+    /// URL of the group in the FASJSON API
+    pub uri: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents the membership of a user in a group.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Membership {
+    /// whether the user is a member of the group
+    pub member: bool,
+    /// whether the user is a sponsor of the group (sponsors can add new members)
+    pub sponsor: bool,
+}
@@ -0,0 +1,407 @@
+//! This module contains an implementation of sessions that are authenticated with Kerberos tickets
+//! via GSSAPI, by answering `WWW-Authenticate: Negotiate` (SPNEGO) challenges.
+//!
+//! This module is only available if the `gssapi` feature is enabled.
+
+use std::ffi::{CStr, CString};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use libgssapi::context::{ClientCtx, CtxFlags};
+use libgssapi::credential::{Cred, CredUsage};
+use libgssapi::error::MajorFlags;
+use libgssapi::name::Name;
+use libgssapi::oid::{GSS_MECH_SPNEGO, GSS_NT_HOSTBASED_SERVICE, GSS_NT_KRB5_PRINCIPAL};
+use libgssapi_sys::{
+    gss_OID_desc,
+    gss_OID_set_desc,
+    gss_acquire_cred_from,
+    gss_buffer_desc,
+    gss_cred_id_t,
+    gss_cred_usage_t,
+    gss_import_name,
+    gss_key_value_element_desc,
+    gss_key_value_set_desc,
+    gss_name_t,
+    gss_release_name,
+    OM_uint32,
+    _GSS_C_INDEFINITE,
+    GSS_C_INITIATE,
+    GSS_S_COMPLETE,
+};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Request, Response};
+use url::Url;
+
+use crate::auth::{AuthError, Authenticator, BoxFuture};
+use crate::config::{client_config_methods, ClientConfig};
+use crate::session::Session;
+use crate::tls::TlsError;
+
+/// This collection of errors is returned for failures when setting up or using a session that is
+/// authenticated with Kerberos.
+#[derive(Debug, thiserror::Error)]
+pub enum KerberosError {
+    /// This error is returned when a GSSAPI operation failed (for example, because there is no
+    /// valid ticket in the credential cache).
+    #[error("GSSAPI error: {error}")]
+    Gssapi {
+        /// The inner error contains the major and minor status codes returned by GSSAPI.
+        #[from]
+        error: libgssapi::error::Error,
+    },
+    /// This error is returned when a request URL does not contain a host name.
+    #[error("Request URL does not contain a host name: {url}")]
+    MissingHost {
+        /// URL of the request
+        url: Url,
+    },
+    /// This error is returned when the Negotiate token could not be used as an HTTP header.
+    #[error("Failed to construct Negotiate authorization header.")]
+    InvalidToken,
+    /// This error is returned when the path of a client keytab can not be passed to GSSAPI
+    /// (because it contains a NUL byte).
+    #[error("Invalid keytab path: {}", path.display())]
+    InvalidKeytab {
+        /// path of the keytab
+        path: PathBuf,
+    },
+    /// This error is returned when the background task that runs blocking GSSAPI calls panicked
+    /// or was cancelled.
+    #[error("Failed to run GSSAPI task: {error}")]
+    Task {
+        /// The inner error contains the error that was returned when joining the task.
+        #[from]
+        error: tokio::task::JoinError,
+    },
+    /// This error is returned when the TLS backend rejected the configured client certificate or
+    /// CA certificates.
+    #[error("Failed to initialize TLS: {error}")]
+    Tls {
+        /// The inner error contains the error that was returned by the TLS backend.
+        #[from]
+        error: TlsError,
+    },
+}
+
+/// This type encapsulates the (optional) arguments that are required for building a session that
+/// is authenticated with Kerberos.
+#[derive(Debug, Default)]
+pub struct KerberosSessionBuilder<'a> {
+    principal: Option<&'a str>,
+    keytab: Option<&'a Path>,
+    config: ClientConfig<'a>,
+}
+
+impl<'a> KerberosSessionBuilder<'a> {
+    /// This method constructs a new [`KerberosSessionBuilder`] instance, which uses the default
+    /// principal from the default credential cache (for example, the ticket obtained with
+    /// `fkinit`).
+    pub fn new() -> Self {
+        KerberosSessionBuilder {
+            principal: None,
+            keytab: None,
+            config: ClientConfig::default(),
+        }
+    }
+
+    /// Use credentials for the given principal (for example, `janedoe@FEDORAPROJECT.ORG`) instead
+    /// of the default principal.
+    #[must_use]
+    pub fn principal(mut self, principal: &'a str) -> Self {
+        self.principal = Some(principal);
+        self
+    }
+
+    /// Acquire initial credentials from the given client keytab instead of the credential cache.
+    ///
+    /// The keytab is only used for this session (the environment of the process is not modified),
+    /// and tickets are stored in a private in-memory credential cache.
+    #[must_use]
+    pub fn keytab(mut self, keytab: &'a Path) -> Self {
+        self.keytab = Some(keytab);
+        self
+    }
+
+    client_config_methods!('a);
+
+    /// This method consumes the [`KerberosSessionBuilder`], acquires Kerberos credentials, and
+    /// returns a [`Session`] that answers `Negotiate` challenges with these credentials.
+    ///
+    /// If the TLS backend rejects the configured client certificate or CA certificates, an error
+    /// is returned. Otherwise, this method will panic if the network stack cannot be initialized.
+    pub fn build(self) -> Result<Session, KerberosError> {
+        // fail early if there are no usable credentials
+        let cred = match self.keytab {
+            Some(keytab) => acquire_from_keytab(self.principal, keytab)?,
+            None => {
+                let name = match self.principal {
+                    Some(principal) => Some(Name::new(principal.as_bytes(), Some(GSS_NT_KRB5_PRINCIPAL))?),
+                    None => None,
+                };
+                Cred::acquire(name.as_ref(), None, CredUsage::Initiate, None)?
+            },
+        };
+        log::debug!("Acquired Kerberos credentials: {:?}", cred);
+
+        let template = self.config.resolve();
+
+        // construct reqwest session with:
+        // - custom default headers
+        // - cookie store for session cookies that are set after successful authentication
+        let client = template.build(template.client().cookie_store(true))?;
+
+        Ok(Session {
+            auth: Some(Arc::new(Negotiator { cred })),
+            ..template.session(client)
+        })
+    }
+}
+
+/// counter for the names of private in-memory credential caches
+static CCACHE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// This helper function converts GSSAPI status codes into an error.
+fn gss_error(major: OM_uint32, minor: OM_uint32) -> KerberosError {
+    KerberosError::Gssapi {
+        error: libgssapi::error::Error {
+            major: MajorFlags::from_bits_retain(major),
+            minor,
+        },
+    }
+}
+
+/// This helper function converts the path of a keytab into a string that can be passed to GSSAPI.
+#[cfg(unix)]
+fn keytab_name(keytab: &Path) -> Option<CString> {
+    CString::new(keytab.as_os_str().as_bytes()).ok()
+}
+
+/// This helper function converts the path of a keytab into a string that can be passed to GSSAPI.
+#[cfg(not(unix))]
+fn keytab_name(keytab: &Path) -> Option<CString> {
+    CString::new(keytab.to_str()?).ok()
+}
+
+/// This helper function acquires initiator credentials for the given principal (or the first
+/// principal in the keytab) from the given client keytab.
+///
+/// The keytab is passed in the credential store instead of through the `KRB5_CLIENT_KTNAME`
+/// environment variable, which would affect all threads of the process. Tickets are stored in a
+/// new in-memory credential cache, so the default credential cache of the user is not modified.
+fn acquire_from_keytab(principal: Option<&str>, keytab: &Path) -> Result<Cred, KerberosError> {
+    let keytab_name = keytab_name(keytab).ok_or_else(|| KerberosError::InvalidKeytab {
+        path: keytab.to_path_buf(),
+    })?;
+    let ccache_name = CString::new(format!(
+        "MEMORY:fedora-rs-{}-{}",
+        std::process::id(),
+        CCACHE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+    .expect("Failed to construct name of credential cache.");
+
+    acquire_cred_from(principal, &keytab_name, &ccache_name)
+}
+
+/// This helper function wraps `gss_acquire_cred_from`, which is not exposed by the safe API of
+/// libgssapi, and contains all unsafe code of this module.
+///
+/// The principal name is imported with the `GSS_NT_KRB5_PRINCIPAL` name type, and released again
+/// before this function returns. All other pointers that are passed to GSSAPI borrow from the
+/// arguments or from local variables, which outlive the calls, and GSSAPI does not modify or keep
+/// them. On success, the returned credential handle is owned by the caller and wrapped in a
+/// [`Cred`], which releases it when it is dropped.
+fn acquire_cred_from(principal: Option<&str>, keytab: &CStr, ccache: &CStr) -> Result<Cred, KerberosError> {
+    let mut elements = [
+        gss_key_value_element_desc {
+            key: b"client_keytab\0".as_ptr().cast(),
+            value: keytab.as_ptr(),
+        },
+        gss_key_value_element_desc {
+            key: b"ccache\0".as_ptr().cast(),
+            value: ccache.as_ptr(),
+        },
+    ];
+    let store = gss_key_value_set_desc {
+        count: elements.len() as OM_uint32,
+        elements: elements.as_mut_ptr(),
+    };
+
+    let mut minor: OM_uint32 = GSS_S_COMPLETE;
+    let mut name: gss_name_t = ptr::null_mut();
+
+    if let Some(principal) = principal {
+        let mut name_type = gss_OID_desc {
+            length: GSS_NT_KRB5_PRINCIPAL.len() as OM_uint32,
+            elements: GSS_NT_KRB5_PRINCIPAL.as_ptr() as *mut _,
+        };
+        let mut buffer = gss_buffer_desc {
+            length: principal.len(),
+            value: principal.as_ptr() as *mut _,
+        };
+
+        // SAFETY: see above; GSSAPI copies the name and the name type
+        let major = unsafe { gss_import_name(&mut minor, &mut buffer, &mut name_type, &mut name) };
+        if major != GSS_S_COMPLETE {
+            return Err(gss_error(major, minor));
+        }
+    }
+
+    let mut cred: gss_cred_id_t = ptr::null_mut();
+
+    // SAFETY: see above
+    let major = unsafe {
+        gss_acquire_cred_from(
+            &mut minor,
+            name,
+            _GSS_C_INDEFINITE,
+            ptr::null_mut::<gss_OID_set_desc>(),
+            GSS_C_INITIATE as gss_cred_usage_t,
+            &store,
+            &mut cred,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+
+    if !name.is_null() {
+        let mut release_minor: OM_uint32 = GSS_S_COMPLETE;
+        // SAFETY: the name was allocated by gss_import_name and is not used after this call
+        unsafe { gss_release_name(&mut release_minor, &mut name) };
+    }
+
+    if major != GSS_S_COMPLETE {
+        return Err(gss_error(major, minor));
+    }
+
+    // SAFETY: the credential handle was just allocated by GSSAPI and is not shared
+    Ok(unsafe { Cred::from_c(cred) })
+}
+
+/// This type answers SPNEGO challenges with the Kerberos credentials of a session.
+#[derive(Debug)]
+pub(crate) struct Negotiator {
+    cred: Cred,
+}
+
+impl Negotiator {
+    /// This method returns whether the server asked for `Negotiate` authentication.
+    fn is_challenge(headers: &HeaderMap) -> bool {
+        headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| value.trim_start().to_ascii_lowercase().starts_with("negotiate"))
+    }
+
+    /// This method computes the value of the `Authorization` header for a request to the given URL.
+    ///
+    /// GSSAPI calls might block while tickets are requested from the KDC, so they are run on the
+    /// blocking thread pool.
+    ///
+    /// Mutual authentication is not requested, because the token in the final response of the
+    /// server is never passed back to GSSAPI (it would only be checked after the response has
+    /// already been accepted).
+    async fn authorization(&self, url: &Url) -> Result<HeaderValue, KerberosError> {
+        let host = match url.host_str() {
+            Some(host) => host.to_string(),
+            None => return Err(KerberosError::MissingHost { url: url.clone() }),
+        };
+
+        let cred = self.cred.clone();
+        let token = tokio::task::spawn_blocking(move || -> Result<Vec<u8>, KerberosError> {
+            let target = Name::new(format!("HTTP@{}", host).as_bytes(), Some(GSS_NT_HOSTBASED_SERVICE))?;
+            let mut ctx = ClientCtx::new(Some(cred), target, CtxFlags::empty(), Some(GSS_MECH_SPNEGO));
+
+            match ctx.step(None, None)? {
+                Some(token) => Ok(token.to_vec()),
+                None => Ok(Vec::new()),
+            }
+        })
+        .await??;
+
+        Negotiator::header(&token)
+    }
+
+    /// This method encodes an initial SPNEGO token as the value of an `Authorization` header.
+    fn header(token: &[u8]) -> Result<HeaderValue, KerberosError> {
+        let mut value = HeaderValue::from_str(&format!("Negotiate {}", STANDARD.encode(token)))
+            .map_err(|_| KerberosError::InvalidToken)?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+// Kerberos sessions only send credentials when the server asks for them
+impl Authenticator for Negotiator {
+    fn recover<'a>(
+        &'a self,
+        request: &'a mut Request,
+        response: &'a Response,
+    ) -> BoxFuture<'a, Result<bool, AuthError>> {
+        Box::pin(async move {
+            if !Negotiator::is_challenge(response.headers()) {
+                return Ok(false);
+            }
+
+            log::debug!("Server requested Negotiate authentication.");
+            let authorization = self.authorization(request.url()).await?;
+            request.headers_mut().insert(AUTHORIZATION, authorization);
+            Ok(true)
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keytab_paths_with_nul_bytes_are_rejected() {
+        let error = acquire_from_keytab(None, Path::new("/tmp/fedora\0rs.keytab")).unwrap_err();
+        assert!(matches!(error, KerberosError::InvalidKeytab { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn keytabs_do_not_modify_the_environment() {
+        let keytab = std::env::temp_dir().join(format!("fedora-rs-missing-{}.keytab", std::process::id()));
+
+        let result = KerberosSessionBuilder::new()
+            .principal("janedoe@EXAMPLE.COM")
+            .keytab(&keytab)
+            .build();
+
+        assert!(matches!(result, Err(KerberosError::Gssapi { .. })), "{:?}", result);
+        assert!(std::env::var_os("KRB5_CLIENT_KTNAME").is_none());
+    }
+
+    #[test]
+    fn only_negotiate_challenges_are_answered() {
+        let mut headers = HeaderMap::new();
+        assert!(!Negotiator::is_challenge(&headers));
+
+        headers.append(
+            WWW_AUTHENTICATE,
+            HeaderValue::from_static("Basic realm=\"FEDORAPROJECT.ORG\""),
+        );
+        assert!(!Negotiator::is_challenge(&headers));
+
+        // servers can offer more than one scheme, in separate headers
+        headers.append(WWW_AUTHENTICATE, HeaderValue::from_static("negotiate"));
+        assert!(Negotiator::is_challenge(&headers));
+    }
+
+    #[test]
+    fn tokens_are_sent_as_sensitive_headers() {
+        let value = Negotiator::header(b"token").unwrap();
+        assert_eq!(value.to_str().unwrap(), "Negotiate dG9rZW4=");
+        assert!(value.is_sensitive());
+    }
+}
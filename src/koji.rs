@@ -0,0 +1,363 @@
+//! This module contains a client for the XML-RPC API of the [Koji](https://koji.fedoraproject.org)
+//! build system, which is built on top of a [`Session`].
+//!
+//! Read-only calls (for example, [`KojiClient::get_build`] or [`KojiClient::list_tagged`]) do not
+//! need authentication. Calls that need authentication require logging in to the hub with
+//! [`KojiClient::login`] first, which uses the authentication of the [`Session`] (for example,
+//! Kerberos with `Session::kerberos` if the `gssapi` feature is enabled, or a TLS client
+//! certificate).
+//!
+//! ```ignore
+//! use fedora::koji::{KojiClient, TaggedQuery};
+//! use fedora::Session;
+//!
+//! let koji = KojiClient::new(Session::anonymous().build());
+//!
+//! let builds = koji.list_tagged("f40-updates", &TaggedQuery::new().package("rust-fedora").latest(true)).await?;
+//! ```
+
+use std::sync::Mutex;
+
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+use crate::client::{api_error, fixed_url, ApiClient, ApiError};
+use crate::openid::excerpt;
+use crate::secret::Secret;
+use crate::session::Session;
+
+mod query;
+pub use query::{BuildQuery, TaggedQuery};
+
+mod types;
+pub use types::{Build, BuildState, TaskInfo, TaskState};
+
+mod xmlrpc;
+pub use xmlrpc::Value;
+
+/// URL of the production Koji hub
+const FEDORA_KOJI_URL: &str = "https://koji.fedoraproject.org/kojihub";
+/// URL of the staging Koji hub
+const FEDORA_KOJI_STG_URL: &str = "https://koji.stg.fedoraproject.org/kojihub";
+
+/// Koji fault code for generic authentication errors
+const FAULT_AUTH_ERROR: i64 = 1002;
+/// Koji fault code for sessions that are locked
+const FAULT_AUTH_LOCK_ERROR: i64 = 1006;
+/// Koji fault code for expired sessions
+const FAULT_AUTH_EXPIRED: i64 = 1007;
+/// Koji fault code for calls with an out-of-sequence call number (for example, when a previous
+/// call with the same call number was retried)
+const FAULT_SEQUENCE_ERROR: i64 = 1008;
+/// Koji fault code for calls that the hub asks to be retried
+const FAULT_RETRY_ERROR: i64 = 1009;
+/// Koji fault code for a hub that is temporarily offline
+const FAULT_SERVER_OFFLINE: i64 = 1014;
+/// Koji fault code for failed GSSAPI authentication
+const FAULT_GSSAPI_AUTH_ERROR: i64 = 1019;
+
+api_error! {
+    service: "Koji",
+    /// This collection of errors is returned for failures when sending calls to a Koji hub.
+    pub enum KojiError {
+        Status {},
+        /// This error is returned when a parameter can not be represented in XML-RPC (for example,
+        /// a [`Value::Double`] that is NaN or infinite).
+        #[error("Failed to encode XML-RPC call: {error}")]
+        Encoding {
+            /// The inner string contains a description of the problem.
+            error: String,
+        },
+        /// This error is returned when the response is not a valid XML-RPC response.
+        #[error("Failed to parse XML-RPC response: {error}")]
+        Parsing {
+            /// The inner string contains a description of the problem.
+            error: String,
+        },
+        /// This error is returned when the hub returned an XML-RPC fault.
+        #[error("Koji returned fault {code}: {message}")]
+        Fault {
+            /// fault code (for example, 1002 for authentication errors)
+            code: i64,
+            /// fault message
+            message: String,
+        },
+    }
+}
+
+impl ApiError for KojiError {
+    fn from_response(status: StatusCode, body: &str) -> Self {
+        KojiError::Status {
+            status,
+            message: excerpt(body),
+        }
+    }
+
+    /// Every call is sent with a new call number, so out-of-sequence calls can be retried.
+    fn is_service_retryable(&self) -> bool {
+        matches!(
+            self,
+            KojiError::Fault { code, .. } if matches!(*code, FAULT_SEQUENCE_ERROR | FAULT_RETRY_ERROR | FAULT_SERVER_OFFLINE)
+        )
+    }
+
+    /// Expired or locked hub sessions are reported as faults (in which case
+    /// [`KojiClient::login`] needs to be called again).
+    fn is_service_auth_failure(&self) -> bool {
+        matches!(
+            self,
+            KojiError::Fault { code, .. } if matches!(
+                *code,
+                FAULT_AUTH_ERROR | FAULT_AUTH_LOCK_ERROR | FAULT_AUTH_EXPIRED | FAULT_GSSAPI_AUTH_ERROR
+            )
+        )
+    }
+}
+
+/// This type contains the state of a logged-in hub session.
+#[derive(Debug)]
+struct HubSession {
+    id: i64,
+    key: Secret,
+    callnum: u64,
+    header_auth: bool,
+}
+
+/// This type is a client for the XML-RPC API of a Koji hub.
+///
+/// All calls are sent with [`Session::send`], so they are retried and rate-limited according to the
+/// settings of the [`Session`]. After logging in with [`KojiClient::login`], calls are
+/// authenticated with the hub session.
+#[derive(Debug)]
+pub struct KojiClient {
+    api: ApiClient<KojiError>,
+    hub_session: Mutex<Option<HubSession>>,
+}
+
+impl KojiClient {
+    /// This method constructs a new [`KojiClient`] for the production Koji hub.
+    pub fn new(session: Session) -> Self {
+        KojiClient::with_url(session, fixed_url(FEDORA_KOJI_URL))
+    }
+
+    /// This method constructs a new [`KojiClient`] for the staging Koji hub.
+    pub fn staging(session: Session) -> Self {
+        KojiClient::with_url(session, fixed_url(FEDORA_KOJI_STG_URL))
+    }
+
+    /// This method constructs a new [`KojiClient`] for the Koji hub at the given URL.
+    pub fn with_url(session: Session, url: Url) -> Self {
+        KojiClient {
+            api: ApiClient::new(session, url),
+            hub_session: Mutex::new(None),
+        }
+    }
+
+    /// This method returns a reference to the wrapped [`Session`].
+    pub fn session(&self) -> &Session {
+        self.api.session()
+    }
+
+    /// This method returns the URL of the Koji hub.
+    pub fn url(&self) -> &Url {
+        self.api.url()
+    }
+
+    /// This method returns `true` if the client is logged in to the hub.
+    pub fn is_logged_in(&self) -> bool {
+        self.hub_session.lock().expect("Poisoned lock!").is_some()
+    }
+
+    /// This method sends an XML-RPC request with the given method and parameters to the given URL,
+    /// and returns the result.
+    async fn send(&self, url: Url, method: &str, params: &[Value], authenticated: bool) -> Result<Value, KojiError> {
+        let mut request = self
+            .session()
+            .session()
+            .post(url)
+            .header(CONTENT_TYPE, "text/xml")
+            .body(xmlrpc::encode_call(method, params)?);
+
+        if authenticated {
+            let mut hub_session = self.hub_session.lock().expect("Poisoned lock!");
+
+            if let Some(hub_session) = hub_session.as_mut() {
+                // every call needs a unique, increasing call number
+                hub_session.callnum += 1;
+
+                let id = hub_session.id.to_string();
+                let callnum = hub_session.callnum.to_string();
+
+                if hub_session.header_auth {
+                    request = request
+                        .header(HeaderName::from_static("koji-session-id"), id)
+                        .header(
+                            HeaderName::from_static("koji-session-key"),
+                            header_value(&hub_session.key)?,
+                        )
+                        .header(HeaderName::from_static("koji-session-callnum"), callnum);
+                } else {
+                    request = request.query(&[
+                        ("session-id", id.as_str()),
+                        ("session-key", hub_session.key.expose()),
+                        ("callnum", callnum.as_str()),
+                    ]);
+                }
+            }
+        }
+
+        let response = self.session().send(request).await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(KojiError::from_response(status, &body));
+        }
+
+        xmlrpc::decode_response(&body)
+    }
+
+    /// This method calls the given method with the given (positional) parameters, and returns the
+    /// result. Keyword arguments can be passed as the last parameter with [`Value::kwargs`].
+    ///
+    /// ```ignore
+    /// use fedora::koji::Value;
+    ///
+    /// let version = koji.call("getKojiVersion", vec![]).await?;
+    /// let tag = koji.call("getTag", vec!["f40-updates".into(), Value::kwargs([("strict", true.into())])]).await?;
+    /// ```
+    pub async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, KojiError> {
+        self.send(self.url().clone(), method, &params, true).await
+    }
+
+    /// This method calls the given method and deserializes the result.
+    async fn call_typed<T: DeserializeOwned>(&self, method: &str, params: Vec<Value>) -> Result<T, KojiError> {
+        let value = self.call(method, params).await?;
+        Ok(serde_json::from_value(value.into())?)
+    }
+
+    /// This method sends multiple calls in one request (with `system.multicall`), and returns the
+    /// results in the same order. Faults of individual calls are returned as
+    /// [`KojiError::Fault`] in the corresponding result, and do not affect other calls.
+    ///
+    /// ```ignore
+    /// let results = koji
+    ///     .multicall(vec![
+    ///         ("getBuild", vec!["rust-fedora-2.1.2-1.fc40".into()]),
+    ///         ("getTag", vec!["f40-updates".into()]),
+    ///     ])
+    ///     .await?;
+    /// ```
+    pub async fn multicall(&self, calls: Vec<(&str, Vec<Value>)>) -> Result<Vec<Result<Value, KojiError>>, KojiError> {
+        let count = calls.len();
+
+        let calls = calls
+            .into_iter()
+            .map(|(method, params)| {
+                let mut call = std::collections::BTreeMap::new();
+                call.insert(String::from("methodName"), Value::from(method));
+                call.insert(String::from("params"), Value::Array(params));
+                Value::Struct(call)
+            })
+            .collect();
+
+        let results = match self.call("system.multicall", vec![Value::Array(calls)]).await? {
+            Value::Array(results) if results.len() == count => results,
+            _ => {
+                return Err(KojiError::Parsing {
+                    error: String::from("Invalid result of multicall."),
+                })
+            },
+        };
+
+        // results of successful calls are wrapped in an array with a single element
+        Ok(results
+            .into_iter()
+            .map(|result| match result {
+                Value::Array(mut values) if values.len() == 1 => Ok(values.remove(0)),
+                Value::Struct(_) => Err(xmlrpc::fault_error(&result)),
+                _ => Err(KojiError::Parsing {
+                    error: String::from("Invalid result of multicall."),
+                }),
+            })
+            .collect())
+    }
+
+    /// This method logs in to the hub with the authentication of the [`Session`] (for example,
+    /// Kerberos or a TLS client certificate). Subsequent calls are authenticated with the new hub
+    /// session.
+    pub async fn login(&self) -> Result<(), KojiError> {
+        #[derive(Deserialize)]
+        struct LoginResponse {
+            #[serde(rename = "session-id")]
+            id: i64,
+            #[serde(rename = "session-key")]
+            key: String,
+            #[serde(rename = "header-auth", default)]
+            header_auth: bool,
+        }
+
+        // the login endpoint is a sub-path of the hub URL
+        let mut url = self.url().clone();
+        let path = format!("{}/ssllogin", url.path().trim_end_matches('/'));
+        url.set_path(&path);
+
+        let value = self.send(url, "sslLogin", &[], false).await?;
+        let response: LoginResponse = serde_json::from_value(value.into())?;
+
+        *self.hub_session.lock().expect("Poisoned lock!") = Some(HubSession {
+            id: response.id,
+            key: Secret::new(response.key),
+            callnum: 0,
+            header_auth: response.header_auth,
+        });
+
+        Ok(())
+    }
+
+    /// This method logs out of the hub session (if the client is logged in).
+    pub async fn logout(&self) -> Result<(), KojiError> {
+        if !self.is_logged_in() {
+            return Ok(());
+        }
+
+        let result = self.call("logout", Vec::new()).await;
+        *self.hub_session.lock().expect("Poisoned lock!") = None;
+
+        result.map(|_| ())
+    }
+
+    /// This method returns the build with the given NVR or ID (or `None` if it does not exist).
+    pub async fn get_build(&self, build: impl Into<Value>) -> Result<Option<Build>, KojiError> {
+        self.call_typed("getBuild", vec![build.into()]).await
+    }
+
+    /// This method returns the builds in the given tag that match the query.
+    pub async fn list_tagged(&self, tag: &str, query: &TaggedQuery<'_>) -> Result<Vec<Build>, KojiError> {
+        self.call_typed("listTagged", vec![tag.into(), query.kwargs()]).await
+    }
+
+    /// This method returns the task with the given ID (or `None` if it does not exist). If
+    /// `request` is `true`, the parameters of the task are included.
+    pub async fn get_task_info(&self, task_id: i64, request: bool) -> Result<Option<TaskInfo>, KojiError> {
+        self.call_typed("getTaskInfo", vec![task_id.into(), request.into()])
+            .await
+    }
+
+    /// This method returns the builds that match the query.
+    pub async fn list_builds(&self, query: &BuildQuery<'_>) -> Result<Vec<Build>, KojiError> {
+        self.call_typed("listBuilds", vec![query.kwargs()]).await
+    }
+}
+
+/// This helper function converts a session key into an HTTP header value.
+fn header_value(key: &Secret) -> Result<HeaderValue, KojiError> {
+    let mut value = HeaderValue::from_str(key.expose()).map_err(|_| KojiError::Parsing {
+        error: String::from("Session key contains invalid characters."),
+    })?;
+    value.set_sensitive(true);
+    Ok(value)
+}
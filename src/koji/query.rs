@@ -0,0 +1,285 @@
+//! This module contains the query builders for the `listBuilds` and `listTagged` calls.
+//!
+//! Filters are sent as keyword arguments, so only filters that were set are sent to the hub.
+
+use super::types::BuildState;
+use super::xmlrpc::Value;
+
+/// This type represents the filters for listing builds with [`KojiClient::list_builds`].
+///
+/// [`KojiClient::list_builds`]: super::KojiClient::list_builds
+///
+/// ```
+/// use fedora::koji::{BuildQuery, BuildState};
+///
+/// let query = BuildQuery::new()
+///     .package("rust-fedora")
+///     .state(BuildState::Complete)
+///     .limit(10);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct BuildQuery<'a> {
+    package: Option<&'a str>,
+    user: Option<&'a str>,
+    state: Option<BuildState>,
+    prefix: Option<&'a str>,
+    pattern: Option<&'a str>,
+    task_id: Option<i64>,
+    build_type: Option<&'a str>,
+    created_after: Option<&'a str>,
+    created_before: Option<&'a str>,
+    completed_after: Option<&'a str>,
+    completed_before: Option<&'a str>,
+    order: Option<&'a str>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+impl<'a> BuildQuery<'a> {
+    /// This method constructs a new [`BuildQuery`] without any filters.
+    pub fn new() -> Self {
+        BuildQuery::default()
+    }
+
+    /// Only return builds of the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    /// Only return builds that were submitted by the given user.
+    pub fn user(mut self, user: &'a str) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Only return builds in the given state.
+    pub fn state(mut self, state: BuildState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Only return builds whose package name starts with the given string.
+    pub fn prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Only return builds whose NVR matches the given glob pattern.
+    pub fn pattern(mut self, pattern: &'a str) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Only return the build that was produced by the given task.
+    pub fn task_id(mut self, task_id: i64) -> Self {
+        self.task_id = Some(task_id);
+        self
+    }
+
+    /// Only return builds of the given type (for example, "rpm", "module", or "image").
+    pub fn build_type(mut self, build_type: &'a str) -> Self {
+        self.build_type = Some(build_type);
+        self
+    }
+
+    /// Only return builds that were started after the given time (for example, "2024-01-01").
+    pub fn created_after(mut self, time: &'a str) -> Self {
+        self.created_after = Some(time);
+        self
+    }
+
+    /// Only return builds that were started before the given time (for example, "2024-01-01").
+    pub fn created_before(mut self, time: &'a str) -> Self {
+        self.created_before = Some(time);
+        self
+    }
+
+    /// Only return builds that were completed after the given time (for example, "2024-01-01").
+    pub fn completed_after(mut self, time: &'a str) -> Self {
+        self.completed_after = Some(time);
+        self
+    }
+
+    /// Only return builds that were completed before the given time (for example, "2024-01-01").
+    pub fn completed_before(mut self, time: &'a str) -> Self {
+        self.completed_before = Some(time);
+        self
+    }
+
+    /// Sort results by the given field (prefixed with "-" for descending order, for example,
+    /// "-build_id").
+    pub fn order(mut self, order: &'a str) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Return at most the given number of results.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip the given number of results.
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// This method returns the keyword arguments for the `listBuilds` call.
+    pub(crate) fn kwargs(&self) -> Value {
+        let mut args = Vec::new();
+
+        let strings = [
+            ("packageID", self.package),
+            ("userID", self.user),
+            ("prefix", self.prefix),
+            ("pattern", self.pattern),
+            ("type", self.build_type),
+            ("createdAfter", self.created_after),
+            ("createdBefore", self.created_before),
+            ("completeAfter", self.completed_after),
+            ("completeBefore", self.completed_before),
+        ];
+        args.extend(
+            strings
+                .into_iter()
+                .filter_map(|(key, value)| value.map(|value| (key, Value::from(value)))),
+        );
+
+        if let Some(state) = self.state {
+            args.push(("state", Value::Int(state.into())));
+        }
+        if let Some(task_id) = self.task_id {
+            args.push(("taskID", Value::Int(task_id)));
+        }
+
+        let options = query_options(self.order, self.limit, self.offset);
+        if let Some(options) = options {
+            args.push(("queryOpts", options));
+        }
+
+        Value::kwargs(args)
+    }
+}
+
+/// This type represents the filters for listing the builds in a tag with
+/// [`KojiClient::list_tagged`].
+///
+/// [`KojiClient::list_tagged`]: super::KojiClient::list_tagged
+///
+/// ```
+/// use fedora::koji::TaggedQuery;
+///
+/// let query = TaggedQuery::new()
+///     .package("rust-fedora")
+///     .latest(true)
+///     .inherit(true);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct TaggedQuery<'a> {
+    package: Option<&'a str>,
+    owner: Option<&'a str>,
+    build_type: Option<&'a str>,
+    prefix: Option<&'a str>,
+    event: Option<i64>,
+    inherit: bool,
+    latest: bool,
+}
+
+impl<'a> TaggedQuery<'a> {
+    /// This method constructs a new [`TaggedQuery`] without any filters.
+    pub fn new() -> Self {
+        TaggedQuery::default()
+    }
+
+    /// Only return builds of the given package.
+    pub fn package(mut self, package: &'a str) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    /// Only return builds that were submitted by the given user.
+    pub fn owner(mut self, owner: &'a str) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Only return builds of the given type (for example, "rpm", "module", or "image").
+    pub fn build_type(mut self, build_type: &'a str) -> Self {
+        self.build_type = Some(build_type);
+        self
+    }
+
+    /// Only return builds whose package name starts with the given string.
+    pub fn prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Return the contents of the tag at the given event (instead of the current contents).
+    pub fn event(mut self, event: i64) -> Self {
+        self.event = Some(event);
+        self
+    }
+
+    /// Also return builds that are inherited from parent tags.
+    pub fn inherit(mut self, inherit: bool) -> Self {
+        self.inherit = inherit;
+        self
+    }
+
+    /// Only return the latest build of each package.
+    pub fn latest(mut self, latest: bool) -> Self {
+        self.latest = latest;
+        self
+    }
+
+    /// This method returns the keyword arguments for the `listTagged` call.
+    pub(crate) fn kwargs(&self) -> Value {
+        let mut args = vec![
+            ("inherit", Value::from(self.inherit)),
+            ("latest", Value::from(self.latest)),
+        ];
+
+        let strings = [
+            ("package", self.package),
+            ("owner", self.owner),
+            ("type", self.build_type),
+            ("prefix", self.prefix),
+        ];
+        args.extend(
+            strings
+                .into_iter()
+                .filter_map(|(key, value)| value.map(|value| (key, Value::from(value)))),
+        );
+
+        if let Some(event) = self.event {
+            args.push(("event", Value::Int(event)));
+        }
+
+        Value::kwargs(args)
+    }
+}
+
+/// This helper function constructs the `queryOpts` argument, if any options are set.
+fn query_options(order: Option<&str>, limit: Option<u32>, offset: Option<u32>) -> Option<Value> {
+    if order.is_none() && limit.is_none() && offset.is_none() {
+        return None;
+    }
+
+    let mut options = std::collections::BTreeMap::new();
+    if let Some(order) = order {
+        options.insert(String::from("order"), Value::from(order));
+    }
+    if let Some(limit) = limit {
+        options.insert(String::from("limit"), Value::from(limit));
+    }
+    if let Some(offset) = offset {
+        options.insert(String::from("offset"), Value::from(offset));
+    }
+
+    Some(Value::Struct(options))
+}
@@ -0,0 +1,172 @@
+//! This module contains the data types that are returned by the Koji hub.
+//!
+//! All types keep attributes that are not captured by known fields in a catch-all map, so no data
+//! is lost if Koji adds new attributes.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum represents the state of a build.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(from = "i64", into = "i64")]
+pub enum BuildState {
+    /// build is in progress
+    Building,
+    /// build has finished successfully
+    Complete,
+    /// build has been deleted
+    Deleted,
+    /// build has failed
+    Failed,
+    /// build has been canceled
+    Canceled,
+    /// state that is not known to this crate
+    Unknown(i64),
+}
+
+impl From<i64> for BuildState {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => BuildState::Building,
+            1 => BuildState::Complete,
+            2 => BuildState::Deleted,
+            3 => BuildState::Failed,
+            4 => BuildState::Canceled,
+            other => BuildState::Unknown(other),
+        }
+    }
+}
+
+impl From<BuildState> for i64 {
+    fn from(value: BuildState) -> Self {
+        match value {
+            BuildState::Building => 0,
+            BuildState::Complete => 1,
+            BuildState::Deleted => 2,
+            BuildState::Failed => 3,
+            BuildState::Canceled => 4,
+            BuildState::Unknown(other) => other,
+        }
+    }
+}
+
+/// This enum represents the state of a task.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(from = "i64", into = "i64")]
+pub enum TaskState {
+    /// task is waiting to be assigned to a builder
+    Free,
+    /// task is running
+    Open,
+    /// task has finished successfully
+    Closed,
+    /// task has been canceled
+    Canceled,
+    /// task has been assigned to a builder, but it has not started yet
+    Assigned,
+    /// task has failed
+    Failed,
+    /// state that is not known to this crate
+    Unknown(i64),
+}
+
+impl From<i64> for TaskState {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => TaskState::Free,
+            1 => TaskState::Open,
+            2 => TaskState::Closed,
+            3 => TaskState::Canceled,
+            4 => TaskState::Assigned,
+            5 => TaskState::Failed,
+            other => TaskState::Unknown(other),
+        }
+    }
+}
+
+impl From<TaskState> for i64 {
+    fn from(value: TaskState) -> Self {
+        match value {
+            TaskState::Free => 0,
+            TaskState::Open => 1,
+            TaskState::Closed => 2,
+            TaskState::Canceled => 3,
+            TaskState::Assigned => 4,
+            TaskState::Failed => 5,
+            TaskState::Unknown(other) => other,
+        }
+    }
+}
+
+/// This type represents a build (as returned by `getBuild`, `listBuilds`, and `listTagged`).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Build {
+    /// ID of the build
+    pub build_id: i64,
+    /// name-version-release of the build
+    pub nvr: String,
+    /// name of the package
+    pub name: String,
+    /// version of the build
+    pub version: String,
+    /// release of the build
+    pub release: String,
+    /// epoch of the build, if any
+    pub epoch: Option<i64>,
+    /// state of the build
+    pub state: BuildState,
+    /// ID of the package
+    pub package_id: Option<i64>,
+    /// ID of the task that produced the build, if any
+    pub task_id: Option<i64>,
+    /// name of the user who submitted the build
+    pub owner_name: Option<String>,
+    /// time when the build was started
+    pub creation_time: Option<String>,
+    /// time when the build was completed
+    pub completion_time: Option<String>,
+    /// name of the tag (only for results of `listTagged`)
+    pub tag_name: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a task (as returned by `getTaskInfo`).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct TaskInfo {
+    /// ID of the task
+    pub id: i64,
+    /// method of the task (for example, "build" or "buildArch")
+    pub method: String,
+    /// state of the task
+    pub state: TaskState,
+    /// ID of the user who submitted the task
+    pub owner: Option<i64>,
+    /// ID of the parent task, if any
+    pub parent: Option<i64>,
+    /// architecture of the task
+    pub arch: Option<String>,
+    /// label of the task, if any
+    pub label: Option<String>,
+    /// ID of the host that the task was assigned to, if any
+    pub host_id: Option<i64>,
+    /// priority of the task
+    pub priority: Option<i64>,
+    /// time when the task was created
+    pub create_time: Option<String>,
+    /// time when the task was started
+    pub start_time: Option<String>,
+    /// time when the task was completed
+    pub completion_time: Option<String>,
+    /// parameters of the task (only if they were requested)
+    pub request: Option<Vec<serde_json::Value>>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
@@ -0,0 +1,646 @@
+//! This module contains a minimal implementation of the XML-RPC wire format, including the `nil`
+//! and 64-bit integer (`i8`) extensions that are used by Koji.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::KojiError;
+
+/// This enum represents XML-RPC values.
+///
+/// Values can be constructed from the corresponding Rust types with [`From`] (where `None` is
+/// converted into [`Value::Nil`]), and they can be converted into [`serde_json::Value`], which is
+/// used for deserializing typed results.
+///
+/// ```
+/// use fedora::koji::Value;
+///
+/// let params: Vec<Value> = vec!["f40-updates".into(), 42.into(), None::<i64>.into()];
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// integer (`int`, `i4`, or `i8`)
+    Int(i64),
+    /// boolean
+    Boolean(bool),
+    /// string
+    String(String),
+    /// double-precision floating point number
+    Double(f64),
+    /// date and time (in the `YYYYMMDDTHH:MM:SS` format that is used by XML-RPC)
+    DateTime(String),
+    /// binary data
+    Base64(Vec<u8>),
+    /// array of values
+    Array(Vec<Value>),
+    /// struct with named members
+    Struct(BTreeMap<String, Value>),
+    /// missing value (`nil` extension)
+    Nil,
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(i64::from(value))
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Int(i64::from(value))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Double(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Value::Array(value)
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Value {
+    fn from(value: BTreeMap<String, Value>) -> Self {
+        Value::Struct(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Value::Nil,
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        use serde_json::Value as Json;
+
+        match value {
+            Value::Int(int) => Json::from(int),
+            Value::Boolean(boolean) => Json::Bool(boolean),
+            Value::String(string) | Value::DateTime(string) => Json::String(string),
+            Value::Double(double) => Json::from(double),
+            Value::Base64(bytes) => Json::String(STANDARD.encode(bytes)),
+            Value::Array(values) => Json::Array(values.into_iter().map(Json::from).collect()),
+            Value::Struct(members) => Json::Object(members.into_iter().map(|(k, v)| (k, Json::from(v))).collect()),
+            Value::Nil => Json::Null,
+        }
+    }
+}
+
+impl Value {
+    /// This method constructs a struct of keyword arguments, which Koji accepts as the last
+    /// positional argument of a call.
+    pub fn kwargs<'a>(args: impl IntoIterator<Item = (&'a str, Value)>) -> Self {
+        let mut members: BTreeMap<String, Value> = args.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+        members.insert(String::from("__starstar"), Value::Boolean(true));
+        Value::Struct(members)
+    }
+
+    /// This method returns the value of a struct member, if this value is a struct.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Struct(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    /// This method appends the XML representation of this value to the given string.
+    ///
+    /// XML-RPC has no representation for NaN or infinite numbers, so they are rejected.
+    fn write_xml(&self, xml: &mut String) -> Result<(), KojiError> {
+        xml.push_str("<value>");
+
+        match self {
+            Value::Int(int) => {
+                // values that do not fit into 32 bits need the "i8" extension
+                if i32::try_from(*int).is_ok() {
+                    let _ = write!(xml, "<int>{}</int>", int);
+                } else {
+                    let _ = write!(xml, "<i8>{}</i8>", int);
+                }
+            },
+            Value::Boolean(boolean) => {
+                let _ = write!(xml, "<boolean>{}</boolean>", u8::from(*boolean));
+            },
+            Value::String(string) => {
+                let _ = write!(xml, "<string>{}</string>", escape(string));
+            },
+            Value::Double(double) => {
+                if !double.is_finite() {
+                    return Err(KojiError::Encoding {
+                        error: format!("{} can not be represented as an XML-RPC double.", double),
+                    });
+                }
+                let _ = write!(xml, "<double>{}</double>", double);
+            },
+            Value::DateTime(datetime) => {
+                let _ = write!(xml, "<dateTime.iso8601>{}</dateTime.iso8601>", escape(datetime));
+            },
+            Value::Base64(bytes) => {
+                let _ = write!(xml, "<base64>{}</base64>", STANDARD.encode(bytes));
+            },
+            Value::Array(values) => {
+                xml.push_str("<array><data>");
+                for value in values {
+                    value.write_xml(xml)?;
+                }
+                xml.push_str("</data></array>");
+            },
+            Value::Struct(members) => {
+                xml.push_str("<struct>");
+                for (name, value) in members {
+                    let _ = write!(xml, "<member><name>{}</name>", escape(name));
+                    value.write_xml(xml)?;
+                    xml.push_str("</member>");
+                }
+                xml.push_str("</struct>");
+            },
+            Value::Nil => xml.push_str("<nil/>"),
+        }
+
+        xml.push_str("</value>");
+        Ok(())
+    }
+}
+
+/// This helper function serializes a method call with the given parameters.
+pub(crate) fn encode_call(method: &str, params: &[Value]) -> Result<String, KojiError> {
+    let mut xml = String::from("<?xml version=\"1.0\"?>\n<methodCall><methodName>");
+    xml.push_str(&escape(method));
+    xml.push_str("</methodName><params>");
+
+    for param in params {
+        xml.push_str("<param>");
+        param.write_xml(&mut xml)?;
+        xml.push_str("</param>");
+    }
+
+    xml.push_str("</params></methodCall>\n");
+    Ok(xml)
+}
+
+/// This helper function parses a method response. Faults are returned as [`KojiError::Fault`].
+pub(crate) fn decode_response(xml: &str) -> Result<Value, KojiError> {
+    let mut parser = Parser::new(xml)?;
+
+    parser.expect_start("methodResponse")?;
+
+    match parser.next_element()? {
+        Token::Start(name) if name == "params" => {
+            // a response without parameters is treated like a "None" return value
+            let value = match parser.next_element()? {
+                Token::Start(name) if name == "param" => {
+                    parser.expect_start("value")?;
+                    let value = parser.parse_value()?;
+                    parser.expect_end("param")?;
+                    parser.expect_end("params")?;
+                    value
+                },
+                Token::End(name) if name == "params" => Value::Nil,
+                token => return Err(unexpected(&token)),
+            };
+            parser.expect_end("methodResponse")?;
+            Ok(value)
+        },
+        Token::Start(name) if name == "fault" => {
+            parser.expect_start("value")?;
+            let fault = parser.parse_value()?;
+            Err(fault_error(&fault))
+        },
+        token => Err(unexpected(&token)),
+    }
+}
+
+/// This helper function converts the struct of an XML-RPC fault into a [`KojiError::Fault`].
+pub(crate) fn fault_error(fault: &Value) -> KojiError {
+    let code = match fault.get("faultCode") {
+        Some(Value::Int(code)) => *code,
+        _ => 0,
+    };
+    let message = match fault.get("faultString") {
+        Some(Value::String(message)) => message.clone(),
+        _ => String::new(),
+    };
+
+    KojiError::Fault { code, message }
+}
+
+/// This enum represents the XML tokens that are relevant for parsing XML-RPC responses. Element
+/// names do not include namespace prefixes (for example, `ex:nil` is represented as `nil`).
+#[derive(Debug)]
+enum Token {
+    Start(String),
+    End(String),
+    Empty(String),
+    Text(String),
+}
+
+/// This helper function constructs an error for an unexpected token.
+fn unexpected(token: &Token) -> KojiError {
+    KojiError::Parsing {
+        error: format!("Unexpected {:?} in XML-RPC response.", token),
+    }
+}
+
+/// This type is a simple recursive-descent parser over the tokens of an XML-RPC response.
+struct Parser {
+    tokens: std::vec::IntoIter<Token>,
+}
+
+impl Parser {
+    fn new(xml: &str) -> Result<Self, KojiError> {
+        let parsing_error = |error: quick_xml::Error| KojiError::Parsing {
+            error: error.to_string(),
+        };
+        let local_name = |name: &[u8]| String::from_utf8_lossy(name).into_owned();
+
+        let mut reader = Reader::from_str(xml);
+        let mut tokens = Vec::new();
+
+        loop {
+            let token = match reader.read_event().map_err(parsing_error)? {
+                Event::Start(start) => Token::Start(local_name(start.local_name().as_ref())),
+                Event::End(end) => Token::End(local_name(end.local_name().as_ref())),
+                Event::Empty(empty) => Token::Empty(local_name(empty.local_name().as_ref())),
+                Event::Text(text) => Token::Text(text.unescape().map_err(parsing_error)?.into_owned()),
+                Event::CData(data) => Token::Text(String::from_utf8_lossy(&data.into_inner()).into_owned()),
+                Event::Eof => break,
+                // XML declarations, comments, and processing instructions are ignored
+                _ => continue,
+            };
+
+            // adjacent text and CDATA sections are merged
+            match (tokens.last_mut(), token) {
+                (Some(Token::Text(previous)), Token::Text(text)) => previous.push_str(&text),
+                (_, token) => tokens.push(token),
+            }
+        }
+
+        Ok(Parser {
+            tokens: tokens.into_iter(),
+        })
+    }
+
+    /// This method returns the next token.
+    fn next(&mut self) -> Result<Token, KojiError> {
+        self.tokens.next().ok_or_else(|| KojiError::Parsing {
+            error: String::from("Unexpected end of XML-RPC response."),
+        })
+    }
+
+    /// This method returns the next token that is not whitespace.
+    fn next_element(&mut self) -> Result<Token, KojiError> {
+        loop {
+            match self.next()? {
+                Token::Text(text) if text.trim().is_empty() => continue,
+                token => return Ok(token),
+            }
+        }
+    }
+
+    fn expect_start(&mut self, expected: &str) -> Result<(), KojiError> {
+        match self.next_element()? {
+            Token::Start(name) if name == expected => Ok(()),
+            token => Err(unexpected(&token)),
+        }
+    }
+
+    fn expect_end(&mut self, expected: &str) -> Result<(), KojiError> {
+        match self.next_element()? {
+            Token::End(name) if name == expected => Ok(()),
+            token => Err(unexpected(&token)),
+        }
+    }
+
+    /// This method reads the text content of an element, up to its end tag.
+    fn text(&mut self, element: &str) -> Result<String, KojiError> {
+        match self.next()? {
+            Token::Text(text) => {
+                self.expect_end(element)?;
+                Ok(text)
+            },
+            Token::End(name) if name == element => Ok(String::new()),
+            token => Err(unexpected(&token)),
+        }
+    }
+
+    /// This method parses a value, after the start tag of the `value` element has been consumed.
+    fn parse_value(&mut self) -> Result<Value, KojiError> {
+        // values without a type element are strings
+        let mut text = String::new();
+
+        let value = loop {
+            match self.next()? {
+                Token::Text(content) => text.push_str(&content),
+                Token::End(name) if name == "value" => return Ok(Value::String(text)),
+                Token::Empty(name) => break empty_value(&name)?,
+                Token::Start(name) => break self.parse_typed(&name)?,
+                token => return Err(unexpected(&token)),
+            }
+        };
+
+        self.expect_end("value")?;
+        Ok(value)
+    }
+
+    /// This method parses the contents of a type element, after its start tag has been consumed.
+    fn parse_typed(&mut self, kind: &str) -> Result<Value, KojiError> {
+        let invalid = |text: &str| KojiError::Parsing {
+            error: format!("Invalid {} value in XML-RPC response: {:?}", kind, text),
+        };
+
+        match kind {
+            "int" | "i4" | "i8" => {
+                let text = self.text(kind)?;
+                text.trim().parse().map(Value::Int).map_err(|_| invalid(&text))
+            },
+            "boolean" => match self.text(kind)?.trim() {
+                "1" => Ok(Value::Boolean(true)),
+                "0" => Ok(Value::Boolean(false)),
+                text => Err(invalid(text)),
+            },
+            "string" => Ok(Value::String(self.text(kind)?)),
+            "double" => {
+                let text = self.text(kind)?;
+                text.trim().parse().map(Value::Double).map_err(|_| invalid(&text))
+            },
+            "dateTime.iso8601" => Ok(Value::DateTime(self.text(kind)?.trim().to_string())),
+            "base64" => {
+                let text = self.text(kind)?;
+                let cleaned: String = text.split_whitespace().collect();
+                STANDARD.decode(cleaned).map(Value::Base64).map_err(|_| invalid(&text))
+            },
+            "nil" => {
+                self.expect_end(kind)?;
+                Ok(Value::Nil)
+            },
+            "array" => {
+                let mut values = Vec::new();
+
+                match self.next_element()? {
+                    Token::Start(name) if name == "data" => loop {
+                        match self.next_element()? {
+                            Token::Start(name) if name == "value" => values.push(self.parse_value()?),
+                            Token::Empty(name) if name == "value" => values.push(Value::String(String::new())),
+                            Token::End(name) if name == "data" => break,
+                            token => return Err(unexpected(&token)),
+                        }
+                    },
+                    Token::Empty(name) if name == "data" => {},
+                    token => return Err(unexpected(&token)),
+                }
+
+                self.expect_end(kind)?;
+                Ok(Value::Array(values))
+            },
+            "struct" => {
+                let mut members = BTreeMap::new();
+
+                loop {
+                    match self.next_element()? {
+                        Token::Start(name) if name == "member" => {
+                            self.expect_start("name")?;
+                            let name = self.text("name")?;
+                            let value = match self.next_element()? {
+                                Token::Start(element) if element == "value" => self.parse_value()?,
+                                Token::Empty(element) if element == "value" => Value::String(String::new()),
+                                token => return Err(unexpected(&token)),
+                            };
+                            self.expect_end("member")?;
+                            members.insert(name, value);
+                        },
+                        Token::End(name) if name == kind => break,
+                        token => return Err(unexpected(&token)),
+                    }
+                }
+
+                Ok(Value::Struct(members))
+            },
+            _ => Err(KojiError::Parsing {
+                error: format!("Unsupported XML-RPC type: {}", kind),
+            }),
+        }
+    }
+}
+
+/// This helper function returns the value of an empty type element (for example, `<nil/>`).
+fn empty_value(kind: &str) -> Result<Value, KojiError> {
+    match kind {
+        "nil" => Ok(Value::Nil),
+        "string" => Ok(Value::String(String::new())),
+        "array" => Ok(Value::Array(Vec::new())),
+        "struct" => Ok(Value::Struct(BTreeMap::new())),
+        "base64" => Ok(Value::Base64(Vec::new())),
+        _ => Err(KojiError::Parsing {
+            error: format!("Empty {} value in XML-RPC response.", kind),
+        }),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// This helper function wraps a value into a method response and parses it again.
+    fn round_trip(value: &Value) -> Value {
+        let mut xml = String::from("<?xml version=\"1.0\"?>\n<methodResponse><params><param>");
+        value.write_xml(&mut xml).unwrap();
+        xml.push_str("</param></params></methodResponse>\n");
+
+        decode_response(&xml).unwrap()
+    }
+
+    #[test]
+    fn scalars_round_trip() {
+        let values = vec![
+            Value::Int(42),
+            Value::Int(-7),
+            Value::Int(i64::MAX),
+            Value::Boolean(true),
+            Value::Boolean(false),
+            Value::String(String::from("<rust-fedora> & \"friends\"")),
+            Value::String(String::new()),
+            Value::Double(0.5),
+            Value::Double(-1e100),
+            Value::DateTime(String::from("20240408T10:00:00")),
+            Value::Base64(b"\x00\x01binary\xff".to_vec()),
+            Value::Nil,
+        ];
+
+        for value in values {
+            assert_eq!(round_trip(&value), value);
+        }
+    }
+
+    #[test]
+    fn compound_values_round_trip() {
+        let mut inner = BTreeMap::new();
+        inner.insert(String::from("nvr"), Value::from("rust-fedora-2.1.2-1.fc40"));
+        inner.insert(String::from("epoch"), Value::Nil);
+        inner.insert(
+            String::from("tags"),
+            Value::Array(vec!["f40".into(), "f40-updates".into()]),
+        );
+
+        let value = Value::Array(vec![
+            Value::Struct(inner),
+            Value::Array(Vec::new()),
+            Value::Struct(BTreeMap::new()),
+            Value::kwargs([("latest", true.into())]),
+        ]);
+
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn large_integers_use_i8() {
+        let mut xml = String::new();
+        Value::Int(i64::from(i32::MAX)).write_xml(&mut xml).unwrap();
+        Value::Int(i64::from(i32::MAX) + 1).write_xml(&mut xml).unwrap();
+
+        assert_eq!(
+            xml,
+            "<value><int>2147483647</int></value><value><i8>2147483648</i8></value>"
+        );
+    }
+
+    #[test]
+    fn non_finite_doubles_are_rejected() {
+        for double in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let error = encode_call("echo", &[Value::Array(vec![Value::Double(double)])]).unwrap_err();
+            assert!(matches!(error, KojiError::Encoding { .. }), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn encode_method_call() {
+        let xml = encode_call("getBuild", &["rust-fedora-2.1.2-1.fc40".into(), 1.into()]).unwrap();
+
+        assert_eq!(
+            xml,
+            "<?xml version=\"1.0\"?>\n<methodCall><methodName>getBuild</methodName><params>\
+             <param><value><string>rust-fedora-2.1.2-1.fc40</string></value></param>\
+             <param><value><int>1</int></value></param></params></methodCall>\n"
+        );
+    }
+
+    #[test]
+    fn decode_extensions_and_untyped_values() {
+        let xml = r#"<?xml version="1.0"?>
+<methodResponse xmlns:ex="http://ws.apache.org/xmlrpc/namespaces/extensions">
+  <params>
+    <param>
+      <value>
+        <array>
+          <data>
+            <value>untyped &amp; escaped</value>
+            <value><ex:nil/></value>
+            <value><ex:i8>8589934592</ex:i8></value>
+            <value><i4> 12 </i4></value>
+            <value><string><![CDATA[<cdata>]]></string></value>
+            <value/>
+          </data>
+        </array>
+      </value>
+    </param>
+  </params>
+</methodResponse>"#;
+
+        assert_eq!(
+            decode_response(xml).unwrap(),
+            Value::Array(vec![
+                Value::String(String::from("untyped & escaped")),
+                Value::Nil,
+                Value::Int(8589934592),
+                Value::Int(12),
+                Value::String(String::from("<cdata>")),
+                Value::String(String::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn decode_empty_response() {
+        let xml = "<methodResponse><params></params></methodResponse>";
+        assert_eq!(decode_response(xml).unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn decode_fault() {
+        let xml = r#"<?xml version="1.0"?>
+<methodResponse>
+  <fault>
+    <value>
+      <struct>
+        <member><name>faultCode</name><value><int>1007</int></value></member>
+        <member><name>faultString</name><value><string>session expired</string></value></member>
+      </struct>
+    </value>
+  </fault>
+</methodResponse>"#;
+
+        match decode_response(xml).unwrap_err() {
+            KojiError::Fault { code, message } => {
+                assert_eq!(code, 1007);
+                assert_eq!(message, "session expired");
+            },
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn decode_invalid_responses() {
+        let invalid = [
+            "",
+            "<html><body>502 Bad Gateway</body></html>",
+            "<methodResponse><params><param><value><int>x</int></value></param></params></methodResponse>",
+            "<methodResponse><params><param><value><boolean>2</boolean></value></param></params></methodResponse>",
+            "<methodResponse><params><param><value><unknown/></value></param></params></methodResponse>",
+            "<methodResponse><params><param><value><string>unterminated",
+        ];
+
+        for xml in invalid {
+            let error = decode_response(xml).unwrap_err();
+            assert!(matches!(error, KojiError::Parsing { .. }), "{}: {:?}", xml, error);
+        }
+    }
+}
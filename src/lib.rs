@@ -1,8 +1,9 @@
 //! This crate contains code that helps serve as the basis for interacting with Fedora (web)
 //! services and implementing other features or API bindings on top of it.
 //!
-//! Currently, an implementation for OpenID authentication against one of the Fedora Project
-//! OpenID providers and a generic, anonymous, unauthenticated session are available.
+//! Currently, implementations for OpenID Connect (OIDC) authentication and (legacy) OpenID
+//! authentication against one of the Fedora Project identity providers and a generic, anonymous,
+//! unauthenticated session are available.
 
 #![deny(missing_docs)]
 #![warn(missing_debug_implementations)]
@@ -23,13 +24,97 @@ const FEDORA_USER_AGENT: &str = concat!("fedora-rs v", env!("CARGO_PKG_VERSION")
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
 mod session;
-pub use session::Session;
+pub use session::{Session, SessionError};
+
+mod config;
+
+mod auth;
+pub use auth::{Anonymous, AuthError, Authenticator, BoxFuture, SessionBuilder};
 
 mod anonymous;
 pub use anonymous::AnonymousSessionBuilder;
 
+#[cfg(any(
+    feature = "bodhi",
+    feature = "copr",
+    feature = "fas",
+    feature = "koji",
+    feature = "pagure"
+))]
+mod client;
+
+#[cfg(feature = "bodhi")]
+pub mod bodhi;
+
+#[cfg(feature = "copr")]
+pub mod copr;
+
+#[cfg(feature = "fas")]
+pub mod fas;
+
+#[cfg(feature = "koji")]
+pub mod koji;
+
+#[cfg(feature = "pagure")]
+pub mod pagure;
+
 mod openid;
-pub use openid::{OpenIDClientError, OpenIDSessionBuilder, OpenIDSessionKind, OpenIDSessionLogin};
+pub use openid::{
+    CookieCache,
+    CookieCacheError,
+    CookieInfo,
+    OpenIDAuthenticator,
+    OpenIDClientError,
+    OpenIDSessionBuilder,
+    OpenIDSessionKind,
+    OpenIDSessionLogin,
+    OtpMode,
+};
+
+#[cfg(feature = "encryption")]
+pub use openid::CacheKey;
+
+mod credentials;
+#[cfg(feature = "secret-service")]
+pub use credentials::SecretServiceCredentials;
+pub use credentials::{
+    CommandHelper,
+    CredentialError,
+    CredentialProvider,
+    Credentials,
+    EnvironmentVariables,
+    NetrcFile,
+    OtpProvider,
+    TerminalPrompt,
+};
+
+mod oidc;
+pub use oidc::{DeviceAuthorization, OIDCClientError, OIDCSessionBuilder, OIDCSessionKind, OIDCSessionLogin};
+
+mod ratelimit;
+pub use ratelimit::{InvalidRate, Rate, RateLimiter};
+
+mod retry;
+pub use retry::RetryPolicy;
+
+mod secret;
+pub use secret::Secret;
+
+mod store;
+#[cfg(feature = "secret-service")]
+pub use store::SecretServiceStore;
+pub use store::{FileStore, MemoryStore, PermissionCheck, SessionStore};
+
+mod tls;
+pub use tls::{CaBundle, ClientIdentity, TlsError};
+
+mod token;
+pub use token::{ApiToken, ApiTokenError, TokenSessionBuilder};
+
+#[cfg(feature = "gssapi")]
+mod kerberos;
+#[cfg(feature = "gssapi")]
+pub use kerberos::{KerberosError, KerberosSessionBuilder};
 
 // re-export reqwest and url, they are part of the public API
 pub use reqwest;
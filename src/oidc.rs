@@ -0,0 +1,519 @@
+//! This module contains an implementation of a session that is authenticated with an OpenID
+//! Connect (OIDC) provider, using the OAuth 2.0 authorization code flow with PKCE.
+
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::redirect::Policy;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use url::{Origin, Url};
+
+use crate::config::{client_config_methods, ClientConfig, ClientTemplate};
+use crate::secret::Secret;
+use crate::session::{is_transient_error, Session};
+use crate::tls::TlsError;
+
+mod device;
+pub use device::DeviceAuthorization;
+
+mod tokens;
+use tokens::{bearer_header, TokenManager};
+
+/// This is the OIDC issuer for "production" instances of fedora services.
+pub const FEDORA_OIDC_ISSUER: &str = "https://id.fedoraproject.org/openidc/";
+
+/// This is the OIDC issuer for "staging" instances of fedora services.
+pub const FEDORA_OIDC_STG_ISSUER: &str = "https://id.stg.fedoraproject.org/openidc/";
+
+/// This is the default redirect URI for clients that can not receive redirects themselves. The
+/// OIDC provider displays the authorization code to the user instead of redirecting.
+pub const OOB_REDIRECT_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
+
+/// This collection of errors is returned for various failure modes when setting up a session
+/// authenticated via OpenID Connect.
+#[derive(Debug, thiserror::Error)]
+pub enum OIDCClientError {
+    /// This error represents a network-related issue that occurred within [`reqwest`].
+    #[error("Failed to contact OIDC provider: {error}")]
+    Request {
+        /// The inner error contains the error passed from [`reqwest`](https://docs.rs/reqwest).
+        #[from]
+        error: reqwest::Error,
+    },
+    /// This error is returned when an input URL was invalid.
+    #[error("Failed to parse URL: {error}")]
+    UrlParsing {
+        /// The inner error contains the error that occurred when parsing the invalid URL.
+        #[from]
+        error: url::ParseError,
+    },
+    /// This error is returned when the JSON response from the OIDC provider was not in the
+    /// standard format, or was missing expected values.
+    #[error("Failed to deserialize JSON returned by OIDC provider: {error}")]
+    Deserialization {
+        /// The inner error contains the deserialization error message from
+        /// [`serde_json`](https://docs.rs/serde_json).
+        #[from]
+        error: serde_json::error::Error,
+    },
+    /// This error is returned if the provider metadata could not be discovered.
+    #[error("Failed to discover OIDC provider configuration: {error}")]
+    Discovery {
+        /// The inner error contains an explanation why provider discovery failed.
+        error: String,
+    },
+    /// This error is returned if the redirect back from the OIDC provider did not contain a valid
+    /// authorization code, or if its `state` parameter did not match.
+    #[error("Invalid authorization response: {error}")]
+    Redirection {
+        /// The inner error contains more details (missing code / mismatched state).
+        error: String,
+    },
+    /// This error is returned when the OIDC provider responded with an OAuth 2.0 error.
+    #[error("OIDC provider returned an error: {error}")]
+    Provider {
+        /// OAuth 2.0 error code (for example, `invalid_grant`)
+        error: String,
+        /// optional human-readable description of the error
+        description: Option<String>,
+    },
+    /// This error is returned when the access token returned by the OIDC provider can not be used
+    /// as the value of an HTTP header.
+    #[error("OIDC provider returned an invalid access token.")]
+    InvalidToken,
+    /// This error is returned when a request to a different origin (scheme, host, and port) than
+    /// the one of the service that the session was built for would be sent with the access token.
+    #[error("Refusing to send the access token to {url}")]
+    UntrustedOrigin {
+        /// URL of the rejected request
+        url: Url,
+    },
+    /// This error is returned when the TLS backend rejected the configured client certificate or
+    /// CA certificates.
+    #[error("Failed to initialize TLS: {error}")]
+    Tls {
+        /// The inner error contains the error that was returned by the TLS backend.
+        #[from]
+        error: TlsError,
+    },
+}
+
+impl OIDCClientError {
+    /// This method returns `true` if the error is likely temporary (for example, a timeout, or
+    /// the provider returning `temporarily_unavailable`), and the request can be retried later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            OIDCClientError::Request { error } => is_transient_error(error),
+            OIDCClientError::Provider { error, .. } => {
+                matches!(error.as_str(), "temporarily_unavailable" | "server_error")
+            },
+            _ => false,
+        }
+    }
+
+    /// This method returns `true` if the error indicates that the authorization was denied, or
+    /// that the tokens or client credentials were rejected by the provider.
+    pub fn is_auth_failure(&self) -> bool {
+        match self {
+            OIDCClientError::Provider { error, .. } => matches!(
+                error.as_str(),
+                "invalid_grant" | "invalid_client" | "unauthorized_client" | "access_denied" | "expired_token"
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// This type represents the subset of OIDC provider metadata that is used by this crate, as
+/// returned from the `/.well-known/openid-configuration` endpoint.
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct ProviderMetadata {
+    pub(crate) issuer: String,
+    pub(crate) authorization_endpoint: Url,
+    pub(crate) token_endpoint: Url,
+    pub(crate) device_authorization_endpoint: Option<Url>,
+    pub(crate) revocation_endpoint: Option<Url>,
+}
+
+/// This type represents the JSON format of error responses from OAuth 2.0 endpoints.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ErrorResponse {
+    pub(crate) error: String,
+    pub(crate) error_description: Option<String>,
+}
+
+impl From<ErrorResponse> for OIDCClientError {
+    fn from(response: ErrorResponse) -> Self {
+        OIDCClientError::Provider {
+            error: response.error,
+            description: response.error_description,
+        }
+    }
+}
+
+/// This type represents the JSON format of successful responses from the token endpoint.
+#[derive(Debug, Deserialize)]
+pub(crate) struct TokenResponse {
+    pub(crate) access_token: Secret,
+    pub(crate) token_type: String,
+    pub(crate) expires_in: Option<u64>,
+    pub(crate) refresh_token: Option<Secret>,
+}
+
+/// This enum represents the different kinds of OIDC providers that can be interacted with.
+#[derive(Debug)]
+pub enum OIDCSessionKind {
+    /// the default Fedora OIDC provider
+    Default,
+    /// the Fedora OIDC provider staging instance
+    Staging,
+    /// a non-standard OIDC provider with a custom issuer URL
+    Custom {
+        /// issuer URL of the OIDC provider
+        issuer: Url,
+    },
+}
+
+/// This type encapsulates the mandatory and optional arguments that are required for building a
+/// session that is authenticated via OpenID Connect.
+#[derive(Debug)]
+pub struct OIDCSessionBuilder<'a> {
+    issuer: Url,
+    origin: Origin,
+    client_id: &'a str,
+    client_secret: Option<&'a str>,
+    redirect_uri: Option<Url>,
+    scopes: Vec<&'a str>,
+    config: ClientConfig<'a>,
+}
+
+impl<'a> OIDCSessionBuilder<'a> {
+    /// Construct a new [`OIDCSessionBuilder`] instance for the given OIDC provider and client ID.
+    ///
+    /// The access token is only sent with requests to the scheme, host, and port of the given
+    /// service URL.
+    pub fn new(kind: OIDCSessionKind, client_id: &'a str, service: &Url) -> Self {
+        use OIDCSessionKind::*;
+
+        let issuer = match kind {
+            Default => Url::parse(FEDORA_OIDC_ISSUER).expect("Failed to parse a hardcoded URL."),
+            Staging => Url::parse(FEDORA_OIDC_STG_ISSUER).expect("Failed to parse a hardcoded URL."),
+            Custom { issuer } => {
+                log::warn!("Authenticating with nonstandard OIDC provider URL: {}", issuer);
+                issuer
+            },
+        };
+
+        OIDCSessionBuilder {
+            issuer,
+            origin: service.origin(),
+            client_id,
+            client_secret: None,
+            redirect_uri: None,
+            scopes: vec!["openid"],
+            config: ClientConfig::default(),
+        }
+    }
+
+    /// Set the client secret for confidential clients (public clients do not have one).
+    #[must_use]
+    pub fn client_secret(mut self, client_secret: &'a str) -> Self {
+        self.client_secret = Some(client_secret);
+        self
+    }
+
+    /// Override the default redirect URI (`urn:ietf:wg:oauth:2.0:oob`).
+    #[must_use]
+    pub fn redirect_uri(mut self, redirect_uri: Url) -> Self {
+        self.redirect_uri = Some(redirect_uri);
+        self
+    }
+
+    /// Request an additional scope (the `openid` scope is always requested).
+    #[must_use]
+    pub fn scope(mut self, scope: &'a str) -> Self {
+        if !self.scopes.contains(&scope) {
+            self.scopes.push(scope);
+        }
+        self
+    }
+
+    client_config_methods!('a);
+
+    /// This method consumes the [`OIDCSessionBuilder`], discovers the configuration of the OIDC
+    /// provider, and returns an [`OIDCSessionLogin`] that can subsequently be used for logging in.
+    pub async fn build(self) -> Result<OIDCSessionLogin, OIDCClientError> {
+        let template = self.config.resolve();
+
+        // construct reqwest session for talking to the OIDC provider with:
+        // - custom default headers
+        // - no-redirects policy
+        let client = template.build(template.client().redirect(Policy::none()))?;
+
+        let metadata = discover(&client, &self.issuer).await?;
+
+        let redirect_uri = match self.redirect_uri {
+            Some(redirect_uri) => redirect_uri,
+            None => Url::parse(OOB_REDIRECT_URI).expect("Failed to parse a hardcoded URL."),
+        };
+
+        let verifier: String = random_string(64);
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        Ok(OIDCSessionLogin {
+            client,
+            template,
+            metadata,
+            origin: self.origin,
+            client_id: self.client_id.to_string(),
+            client_secret: self.client_secret.map(Secret::from),
+            redirect_uri,
+            scope: self.scopes.join(" "),
+            state: Secret::new(random_string(32)),
+            verifier: Secret::new(verifier),
+            challenge,
+        })
+    }
+}
+
+/// This helper function generates a random string of the given length that only contains
+/// characters which are safe to use both in URLs and as a PKCE code verifier.
+fn random_string(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+/// This helper function fetches the provider metadata from the OIDC discovery endpoint of the
+/// given issuer, and checks that the issuer in the metadata matches.
+pub(crate) async fn discover(client: &Client, issuer: &Url) -> Result<ProviderMetadata, OIDCClientError> {
+    // the issuer URL might or might not have a trailing slash
+    let mut discovery_url = issuer.clone();
+    discovery_url.set_path(&format!(
+        "{}/.well-known/openid-configuration",
+        issuer.path().trim_end_matches('/')
+    ));
+
+    let response = client.get(discovery_url).send().await?;
+
+    if !response.status().is_success() {
+        return Err(OIDCClientError::Discovery {
+            error: format!("Discovery endpoint returned HTTP status {}.", response.status()),
+        });
+    }
+
+    let metadata: ProviderMetadata = serde_json::from_str(&response.text().await?)?;
+
+    if metadata.issuer.trim_end_matches('/') != issuer.as_str().trim_end_matches('/') {
+        return Err(OIDCClientError::Discovery {
+            error: format!("Issuer mismatch: expected {}, got {}.", issuer, metadata.issuer),
+        });
+    }
+
+    Ok(metadata)
+}
+
+/// This helper function parses the response from a token endpoint, returning either the token
+/// response or the OAuth 2.0 error that was returned by the provider.
+pub(crate) async fn parse_token_response(response: reqwest::Response) -> Result<TokenResponse, OIDCClientError> {
+    let status = response.status();
+    let string = response.text().await?;
+
+    if !status.is_success() {
+        return match serde_json::from_str::<ErrorResponse>(&string) {
+            Ok(error) => Err(error.into()),
+            Err(_) => Err(OIDCClientError::Provider {
+                error: String::from("invalid_response"),
+                description: Some(format!("Token endpoint returned HTTP status {}.", status)),
+            }),
+        };
+    }
+
+    let token: TokenResponse = serde_json::from_str(&string)?;
+
+    if !token.token_type.eq_ignore_ascii_case("bearer") {
+        return Err(OIDCClientError::Provider {
+            error: String::from("unsupported_token_type"),
+            description: Some(format!("Unsupported token type: {}", token.token_type)),
+        });
+    }
+
+    Ok(token)
+}
+
+/// This type represents an OIDC login handler that encapsulates all parameters for authenticating
+/// except the authorization code that is returned by the OIDC provider.
+///
+/// Clients that can not open a web browser can use the device authorization grant instead (see
+/// [`OIDCSessionLogin::login_with_device_code`]).
+pub struct OIDCSessionLogin {
+    client: Client,
+    template: ClientTemplate,
+    metadata: ProviderMetadata,
+    origin: Origin,
+    client_id: String,
+    client_secret: Option<Secret>,
+    redirect_uri: Url,
+    scope: String,
+    state: Secret,
+    verifier: Secret,
+    challenge: String,
+}
+
+impl Debug for OIDCSessionLogin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // do not leak the client secret, the PKCE code verifier, or the state into logs
+        f.debug_struct("OIDCSessionLogin")
+            .field("client", &self.client)
+            .field("template", &self.template)
+            .field("metadata", &self.metadata)
+            .field("origin", &self.origin)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &self.client_secret)
+            .field("redirect_uri", &self.redirect_uri)
+            .field("scope", &self.scope)
+            .field("state", &self.state)
+            .field("verifier", &self.verifier)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OIDCSessionLogin {
+    /// This method returns the URL that the user needs to visit (in a web browser) to authorize
+    /// this client with the OIDC provider.
+    pub fn authorization_url(&self) -> Url {
+        let mut url = self.metadata.authorization_endpoint.clone();
+
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", self.redirect_uri.as_str())
+            .append_pair("scope", &self.scope)
+            .append_pair("state", self.state.expose())
+            .append_pair("code_challenge", &self.challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        url
+    }
+
+    /// This method extracts the authorization code from the URL that the OIDC provider redirected
+    /// to, checks that the `state` parameter matches, and then completes the login.
+    pub async fn login_with_redirect(self, redirect: &Url) -> Result<Session, OIDCClientError> {
+        let mut code = None;
+        let mut state = None;
+        let mut error = None;
+        let mut description = None;
+
+        for (key, value) in redirect.query_pairs() {
+            match key.as_ref() {
+                "code" => code = Some(value.into_owned()),
+                "state" => state = Some(value.into_owned()),
+                "error" => error = Some(value.into_owned()),
+                "error_description" => description = Some(value.into_owned()),
+                _ => {},
+            }
+        }
+
+        if let Some(error) = error {
+            return Err(OIDCClientError::Provider { error, description });
+        }
+
+        if state.as_deref() != Some(self.state.expose()) {
+            return Err(OIDCClientError::Redirection {
+                error: String::from("State parameter does not match."),
+            });
+        }
+
+        match code {
+            Some(code) => self.login(&code).await,
+            None => Err(OIDCClientError::Redirection {
+                error: String::from("No authorization code provided in redirect URL."),
+            }),
+        }
+    }
+
+    /// This method exchanges an authorization code for an access token, and returns a session that
+    /// sends this token with every request.
+    ///
+    /// ```ignore
+    /// use fedora::{OIDCSessionKind, Session};
+    /// use url::Url;
+    ///
+    /// let bodhi = Url::parse("https://bodhi.fedoraproject.org/").unwrap();
+    /// let login = Session::oidc_auth(OIDCSessionKind::Default, "my-client-id", &bodhi).build().await.unwrap();
+    /// println!("Visit this URL to log in: {}", login.authorization_url());
+    ///
+    /// let auth_session = login.login("CODE-DISPLAYED-BY-PROVIDER").await.unwrap();
+    /// ```
+    pub async fn login(self, code: &str) -> Result<Session, OIDCClientError> {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("client_id", self.client_id.as_str()),
+            ("code_verifier", self.verifier.expose()),
+        ];
+
+        let response = self
+            .post(self.metadata.token_endpoint.clone())
+            .form(&form)
+            .send()
+            .await?;
+        let token = parse_token_response(response).await?;
+
+        self.into_session(token)
+    }
+
+    /// This helper method prepares a POST request to an endpoint of the OIDC provider, which is
+    /// authenticated with the client secret for confidential clients.
+    fn post(&self, endpoint: Url) -> RequestBuilder {
+        // public clients only identify themselves with their client ID
+        let request = self.client.post(endpoint);
+        match &self.client_secret {
+            Some(secret) => request.basic_auth(&self.client_id, Some(secret.expose())),
+            None => request,
+        }
+    }
+
+    /// This helper method consumes the [`OIDCSessionLogin`] and returns a session that sends the
+    /// access token from the given token response with every request.
+    ///
+    /// Requests that are sent with [`Session::send`] or [`Session::execute`] always carry the
+    /// current access token, which is transparently refreshed when it expires (if the provider
+    /// issued a refresh token). Since the wrapped client adds the access token to every request,
+    /// these methods refuse to send requests to other origins than the one of the service.
+    fn into_session(self, token: TokenResponse) -> Result<Session, OIDCClientError> {
+        // add the access token to the default headers of our requests
+        let mut authorization = HeaderMap::new();
+        authorization.insert(AUTHORIZATION, bearer_header(token.access_token.expose())?);
+
+        // construct new client with default headers and default redirect handling
+        let client = self
+            .template
+            .build(self.template.client().default_headers(authorization).cookie_store(true))?;
+
+        let tokens = TokenManager::new(
+            self.client,
+            self.metadata.token_endpoint,
+            self.metadata.revocation_endpoint,
+            self.origin,
+            self.client_id,
+            self.client_secret,
+            token,
+        );
+
+        Ok(Session {
+            auth: Some(Arc::new(tokens)),
+            ..self.template.session(client)
+        })
+    }
+}
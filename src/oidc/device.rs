@@ -0,0 +1,152 @@
+//! This module contains an implementation of the OAuth 2.0 device authorization grant (RFC 8628)
+//! for logging in on machines that can not open a web browser.
+
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use url::Url;
+
+use super::{parse_token_response, ErrorResponse, OIDCClientError, OIDCSessionLogin};
+use crate::session::Session;
+
+/// grant type for polling the token endpoint in the device authorization flow
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// default polling interval if the provider does not specify one
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// increment of the polling interval when the provider asks to slow down
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// This type represents the JSON response format of the device authorization endpoint.
+#[derive(Debug, Deserialize)]
+struct DeviceAuthorizationResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: Url,
+    verification_uri_complete: Option<Url>,
+    expires_in: u64,
+    interval: Option<u64>,
+}
+
+/// This type contains the information that needs to be presented to the user to complete a login
+/// with the device authorization grant.
+#[derive(Debug)]
+pub struct DeviceAuthorization {
+    /// URL that the user needs to visit on another device
+    pub verification_uri: Url,
+    /// code that the user needs to enter after visiting the verification URL
+    pub user_code: String,
+    /// optional URL that already includes the user code
+    pub verification_uri_complete: Option<Url>,
+    /// duration after which the device code and user code expire
+    pub expires_in: Duration,
+}
+
+impl OIDCSessionLogin {
+    /// This method logs in with the OAuth 2.0 device authorization grant, which does not require a
+    /// web browser on the current machine.
+    ///
+    /// The `prompt` callback receives the verification URL and the user code, which need to be
+    /// presented to the user. The token endpoint is then polled until the user has approved (or
+    /// denied) the request, or until the device code has expired.
+    ///
+    /// ```ignore
+    /// use fedora::{OIDCSessionKind, Session};
+    /// use url::Url;
+    ///
+    /// let bodhi = Url::parse("https://bodhi.fedoraproject.org/").unwrap();
+    /// let login = Session::oidc_auth(OIDCSessionKind::Default, "my-client-id", &bodhi).build().await.unwrap();
+    ///
+    /// let auth_session = login
+    ///     .login_with_device_code(|auth| {
+    ///         println!("Visit {} and enter the code {}.", auth.verification_uri, auth.user_code);
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// ```
+    pub async fn login_with_device_code<F>(self, prompt: F) -> Result<Session, OIDCClientError>
+    where
+        F: FnOnce(&DeviceAuthorization),
+    {
+        let endpoint = match &self.metadata.device_authorization_endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => {
+                return Err(OIDCClientError::Discovery {
+                    error: String::from("OIDC provider does not support the device authorization grant."),
+                });
+            },
+        };
+
+        // request device code and user code
+        let form = [("client_id", self.client_id.as_str()), ("scope", self.scope.as_str())];
+
+        let response = self.post(endpoint).form(&form).send().await?;
+        let status = response.status();
+        let string = response.text().await?;
+
+        if !status.is_success() {
+            return match serde_json::from_str::<ErrorResponse>(&string) {
+                Ok(error) => Err(error.into()),
+                Err(_) => Err(OIDCClientError::Provider {
+                    error: String::from("invalid_response"),
+                    description: Some(format!(
+                        "Device authorization endpoint returned HTTP status {}.",
+                        status
+                    )),
+                }),
+            };
+        }
+
+        let device: DeviceAuthorizationResponse = serde_json::from_str(&string)?;
+
+        let deadline = Instant::now() + Duration::from_secs(device.expires_in);
+        let mut interval = device.interval.map(Duration::from_secs).unwrap_or(DEFAULT_INTERVAL);
+
+        prompt(&DeviceAuthorization {
+            verification_uri: device.verification_uri,
+            user_code: device.user_code,
+            verification_uri_complete: device.verification_uri_complete,
+            expires_in: Duration::from_secs(device.expires_in),
+        });
+
+        // poll token endpoint until the user has completed the authorization
+        let form = [
+            ("grant_type", DEVICE_CODE_GRANT_TYPE),
+            ("device_code", device.device_code.as_str()),
+            ("client_id", self.client_id.as_str()),
+        ];
+
+        loop {
+            tokio::time::sleep(interval).await;
+
+            if Instant::now() >= deadline {
+                return Err(OIDCClientError::Provider {
+                    error: String::from("expired_token"),
+                    description: Some(String::from("The device code has expired.")),
+                });
+            }
+
+            let response = self
+                .post(self.metadata.token_endpoint.clone())
+                .form(&form)
+                .send()
+                .await?;
+
+            match parse_token_response(response).await {
+                Ok(token) => return self.into_session(token),
+                Err(OIDCClientError::Provider { error, description }) => match error.as_str() {
+                    "authorization_pending" => {
+                        log::debug!("Device authorization is still pending.");
+                    },
+                    "slow_down" => {
+                        log::debug!("OIDC provider requested to slow down polling.");
+                        interval += SLOW_DOWN_INCREMENT;
+                    },
+                    _ => return Err(OIDCClientError::Provider { error, description }),
+                },
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! This module contains the implementation of access token management for sessions that are
+//! authenticated via OpenID Connect, including single-flight token refresh.
+
+use std::fmt::{self, Debug};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Request, Response};
+use url::{Origin, Url};
+
+use super::{parse_token_response, ErrorResponse, OIDCClientError, TokenResponse};
+use crate::auth::{AuthError, Authenticator, BoxFuture};
+use crate::secret::Secret;
+
+/// access tokens are refreshed ahead of time if they expire within this duration
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// This type holds the current set of tokens for an OIDC session.
+struct TokenState {
+    access_token: Secret,
+    refresh_token: Option<Secret>,
+    expires_at: Option<Instant>,
+}
+
+impl TokenState {
+    fn from_response(response: TokenResponse, previous_refresh_token: Option<Secret>) -> Self {
+        TokenState {
+            access_token: response.access_token,
+            // providers are not required to rotate refresh tokens
+            refresh_token: response.refresh_token.or(previous_refresh_token),
+            expires_at: response
+                .expires_in
+                .map(|expires_in| Instant::now() + Duration::from_secs(expires_in)),
+        }
+    }
+
+    fn needs_refresh(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => self.refresh_token.is_some() && expires_at <= Instant::now() + REFRESH_MARGIN,
+            None => false,
+        }
+    }
+}
+
+/// This type keeps track of the access token and refresh token of an OIDC session, and refreshes
+/// the access token when it is about to expire, or when it was rejected by a server.
+///
+/// The access token is restricted to requests to the origin (scheme, host, and port) of the
+/// service that the session was built for, so it is not leaked to other hosts.
+///
+/// Concurrent refresh attempts are coalesced, so the token endpoint is only contacted once even
+/// if many requests notice an expired access token at the same time.
+pub(crate) struct TokenManager {
+    client: Client,
+    token_endpoint: Url,
+    revocation_endpoint: Option<Url>,
+    origin: Origin,
+    client_id: String,
+    client_secret: Option<Secret>,
+    state: RwLock<TokenState>,
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl Debug for TokenManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // do not leak tokens or client secrets into logs
+        f.debug_struct("TokenManager")
+            .field("token_endpoint", &self.token_endpoint)
+            .field("origin", &self.origin)
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenManager {
+    pub(crate) fn new(
+        client: Client,
+        token_endpoint: Url,
+        revocation_endpoint: Option<Url>,
+        origin: Origin,
+        client_id: String,
+        client_secret: Option<Secret>,
+        token: TokenResponse,
+    ) -> Self {
+        TokenManager {
+            client,
+            token_endpoint,
+            revocation_endpoint,
+            origin,
+            client_id,
+            client_secret,
+            state: RwLock::new(TokenState::from_response(token, None)),
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// This method returns the current access token.
+    fn current(&self) -> Secret {
+        self.state.read().expect("Poisoned lock!").access_token.clone()
+    }
+
+    /// This method returns whether a refresh token is available.
+    fn can_refresh(&self) -> bool {
+        self.state.read().expect("Poisoned lock!").refresh_token.is_some()
+    }
+
+    /// This method returns a valid access token, refreshing it first if it is about to expire.
+    async fn access_token(&self) -> Result<Secret, OIDCClientError> {
+        let (token, needs_refresh) = {
+            let state = self.state.read().expect("Poisoned lock!");
+            (state.access_token.clone(), state.needs_refresh())
+        };
+
+        if needs_refresh {
+            log::debug!("Access token is about to expire, refreshing it.");
+            self.refresh(token.expose()).await
+        } else {
+            Ok(token)
+        }
+    }
+
+    /// This method refreshes the access token, unless the given (stale) access token has already
+    /// been replaced by a concurrent refresh in the meantime. It returns the new access token.
+    async fn refresh(&self, stale: &str) -> Result<Secret, OIDCClientError> {
+        let _guard = self.refresh_lock.lock().await;
+
+        // another task refreshed the token while we were waiting for the lock
+        let current = self.current();
+        if current.expose() != stale {
+            return Ok(current);
+        }
+
+        let refresh_token = match &self.state.read().expect("Poisoned lock!").refresh_token {
+            Some(refresh_token) => refresh_token.clone(),
+            None => {
+                return Err(OIDCClientError::Provider {
+                    error: String::from("invalid_grant"),
+                    description: Some(String::from("No refresh token available.")),
+                });
+            },
+        };
+
+        let form = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.expose()),
+            ("client_id", self.client_id.as_str()),
+        ];
+
+        let mut request = self.client.post(self.token_endpoint.clone()).form(&form);
+        if let Some(secret) = &self.client_secret {
+            request = request.basic_auth(&self.client_id, Some(secret.expose()));
+        }
+
+        let response = request.send().await?;
+        let token = parse_token_response(response).await?;
+        let state = TokenState::from_response(token, Some(refresh_token));
+
+        let access_token = state.access_token.clone();
+        *self.state.write().expect("Poisoned lock!") = state;
+
+        log::debug!("Successfully refreshed access token.");
+        Ok(access_token)
+    }
+
+    /// This method revokes the refresh token (which also invalidates access tokens with most
+    /// providers) and the access token, if the provider supports token revocation (RFC 7009).
+    async fn revoke(&self) -> Result<(), OIDCClientError> {
+        let revocation_endpoint = match &self.revocation_endpoint {
+            Some(revocation_endpoint) => revocation_endpoint,
+            None => {
+                log::info!("OIDC provider does not support token revocation.");
+                return Ok(());
+            },
+        };
+
+        let tokens = {
+            let state = self.state.read().expect("Poisoned lock!");
+
+            let mut tokens = Vec::new();
+            if let Some(refresh_token) = &state.refresh_token {
+                tokens.push((refresh_token.clone(), "refresh_token"));
+            }
+            tokens.push((state.access_token.clone(), "access_token"));
+            tokens
+        };
+
+        for (token, hint) in tokens {
+            let form = [
+                ("token", token.expose()),
+                ("token_type_hint", hint),
+                ("client_id", self.client_id.as_str()),
+            ];
+
+            let mut request = self.client.post(revocation_endpoint.clone()).form(&form);
+            if let Some(secret) = &self.client_secret {
+                request = request.basic_auth(&self.client_id, Some(secret.expose()));
+            }
+
+            let response = request.send().await?;
+            let status = response.status();
+
+            if !status.is_success() {
+                let string = response.text().await?;
+                return match serde_json::from_str::<ErrorResponse>(&string) {
+                    Ok(error) => Err(error.into()),
+                    Err(_) => Err(OIDCClientError::Provider {
+                        error: String::from("invalid_response"),
+                        description: Some(format!("Revocation endpoint returned HTTP status {}.", status)),
+                    }),
+                };
+            }
+        }
+
+        log::debug!("Successfully revoked tokens.");
+        Ok(())
+    }
+}
+
+// OIDC sessions add the current access token to every request to the service, and refresh it if it
+// expired
+impl Authenticator for TokenManager {
+    fn authorize<'a>(&'a self, request: &'a mut Request) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move {
+            // the wrapped client would add the initial access token to requests to other origins
+            if request.url().origin() != self.origin {
+                return Err(OIDCClientError::UntrustedOrigin {
+                    url: request.url().clone(),
+                }
+                .into());
+            }
+
+            let access_token = self.access_token().await?;
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, bearer_header(access_token.expose())?);
+            Ok(())
+        })
+    }
+
+    fn recover<'a>(
+        &'a self,
+        request: &'a mut Request,
+        _response: &'a Response,
+    ) -> BoxFuture<'a, Result<bool, AuthError>> {
+        Box::pin(async move {
+            if !self.can_refresh() {
+                return Ok(false);
+            }
+
+            // the rejected access token is the one that was sent with the request
+            let stale = request
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(Secret::from)
+                .unwrap_or_else(|| Secret::from(""));
+
+            log::info!("Access token was rejected, refreshing it.");
+            let access_token = self.refresh(stale.expose()).await?;
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, bearer_header(access_token.expose())?);
+            Ok(true)
+        })
+    }
+
+    fn logout<'a>(&'a self, _client: &'a Client) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move { Ok(self.revoke().await?) })
+    }
+}
+
+/// This helper function constructs the value of an `Authorization` header for the given bearer
+/// token, and marks it as sensitive.
+pub(crate) fn bearer_header(access_token: &str) -> Result<HeaderValue, OIDCClientError> {
+    let mut value =
+        HeaderValue::from_str(&format!("Bearer {}", access_token)).map_err(|_| OIDCClientError::InvalidToken)?;
+    value.set_sensitive(true);
+    Ok(value)
+}
@@ -2,19 +2,29 @@
 //! provider.
 
 use std::borrow::Cow;
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use cookies::{CachingJar, CookieCacheError};
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+pub(crate) use cookies::check_profile_name;
+#[cfg(feature = "encryption")]
+pub use cookies::CacheKey;
+pub(crate) use cookies::{CacheConfig, CachingJar};
+pub use cookies::{CookieCache, CookieCacheError, CookieInfo};
+use reqwest::header::{HeaderValue, CONTENT_TYPE, LOCATION};
 use reqwest::redirect::Policy;
-use reqwest::Client;
+use reqwest::{Client, Method, Request, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::session::Session;
-use crate::{DEFAULT_TIMEOUT, FEDORA_USER_AGENT};
+use crate::auth::{AuthError, Authenticator, BoxFuture};
+use crate::config::{client_config_methods, ClientConfig, ClientTemplate};
+use crate::credentials::{CredentialProvider, Credentials, OtpProvider};
+use crate::secret::Secret;
+use crate::session::{is_transient_error, is_transient_status, Session};
+use crate::store::{PermissionCheck, SessionStore};
+use crate::tls::TlsError;
 
 mod cookies;
 
@@ -42,17 +52,69 @@ pub enum OpenIDClientError {
         #[from]
         error: url::ParseError,
     },
-    /// This error is returned if a HTTP redirect was invalid.
-    #[error("{error}")]
+    /// This error is returned if a HTTP redirect was invalid (the `Location` header was missing or
+    /// could not be decoded).
+    #[error("Invalid redirect from {url}: {}", location.as_deref().unwrap_or("missing Location header"))]
     Redirection {
-        /// The inner error contains more details (failed to decode URL / missing URL from headers).
-        error: String,
+        /// URL of the request that was redirected
+        url: Url,
+        /// value of the `Location` header, if present
+        location: Option<String>,
     },
-    /// This error is returned for authentication-related issues.
-    #[error("Failed to authenticate with OpenID service: {error}")]
-    Authentication {
-        /// The inner error contains an explanation why the authentication request failed.
-        error: String,
+    /// This error is returned if following redirects led back to an already visited URL.
+    #[error("Redirect loop detected at {url}")]
+    RedirectLoop {
+        /// URL that was visited more than once
+        url: Url,
+    },
+    /// This error is returned if reaching the login form required more redirects than allowed.
+    #[error("Too many redirects (more than {limit}), last redirect to {url}")]
+    TooManyRedirects {
+        /// URL of the last redirect that was followed
+        url: Url,
+        /// maximum number of redirects
+        limit: usize,
+    },
+    /// This error is returned if a redirect led to a host that is neither the host of the login URL
+    /// nor a trusted host, or if the OpenID response would be sent back to an untrusted host.
+    #[error("Refusing to contact untrusted host: {url}")]
+    UntrustedHost {
+        /// URL on the untrusted host
+        url: Url,
+    },
+    /// This error is returned if credentials would be sent over an unencrypted connection.
+    #[error("Refusing to send credentials over an unencrypted connection: {url}")]
+    InsecureTransport {
+        /// URL that does not use HTTPS
+        url: Url,
+    },
+    /// This error is returned when a server responded with an unexpected HTTP status code.
+    #[error("Unexpected response from {url}: {status}")]
+    HttpStatus {
+        /// URL of the request
+        url: Url,
+        /// HTTP status code of the response
+        status: StatusCode,
+        /// the beginning of the response body (for diagnostics)
+        body: String,
+    },
+    /// This error is returned when the OpenID endpoint returned a response that is neither JSON nor
+    /// the HTML login form.
+    #[error("Unexpected content type from {url}: {}", content_type.as_deref().unwrap_or("none"))]
+    UnexpectedContentType {
+        /// URL of the request
+        url: Url,
+        /// value of the `Content-Type` header, if present
+        content_type: Option<String>,
+    },
+    /// This error is returned when the OpenID endpoint rejected the authentication request with an
+    /// error response.
+    #[error("OpenID provider returned an error: {}", message.as_deref().or(code.as_deref()).unwrap_or("unknown error"))]
+    Provider {
+        /// error code returned by the OpenID provider, if any
+        code: Option<String>,
+        /// error message returned by the OpenID provider, if any
+        message: Option<String>,
     },
     /// This error is returned when the JSON response from the OpenID endpoint was not in the
     /// standard format, or was missing expected values.
@@ -63,10 +125,220 @@ pub enum OpenIDClientError {
         #[from]
         error: serde_json::error::Error,
     },
+    /// This error is returned when credentials could not be obtained from a [`CredentialProvider`].
+    #[error("Failed to obtain credentials: {error}")]
+    Credentials {
+        /// The inner error contains the error that was returned by the provider.
+        error: AuthError,
+    },
     /// This error is returned when an error occurs during authentication, primarily due to wrong
     /// combinations of username and password.
     #[error("Authentication failed, possibly due to wrong username / password.")]
     Login,
+    /// This error is returned when the OpenID provider asks for a one-time password, but neither
+    /// the credentials nor an [`OtpProvider`] supplied one.
+    #[error("Authentication failed, a one-time password is required.")]
+    OtpRequired,
+    /// This error is returned when the OpenID provider rejected the one-time password.
+    #[error("Authentication failed, the one-time password was rejected.")]
+    InvalidOtp,
+    /// This error is returned when the TLS backend rejected the configured client certificate or
+    /// CA certificates.
+    #[error("Failed to initialize TLS: {error}")]
+    Tls {
+        /// The inner error contains the error that was returned by the TLS backend.
+        #[from]
+        error: TlsError,
+    },
+}
+
+impl OpenIDClientError {
+    /// This method returns `true` if the error is likely temporary (for example, a timeout or a
+    /// "503 Service Unavailable" response), and the login can be retried later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            OpenIDClientError::Request { error } => is_transient_error(error),
+            OpenIDClientError::HttpStatus { status, .. } => is_transient_status(*status),
+            _ => false,
+        }
+    }
+
+    /// This method returns `true` if the error indicates that the supplied credentials were
+    /// rejected or could not be obtained (as opposed to network or protocol errors).
+    pub fn is_auth_failure(&self) -> bool {
+        match self {
+            OpenIDClientError::Login
+            | OpenIDClientError::OtpRequired
+            | OpenIDClientError::InvalidOtp
+            | OpenIDClientError::Provider { .. }
+            | OpenIDClientError::Credentials { .. } => true,
+            OpenIDClientError::HttpStatus { status, .. } => {
+                *status == StatusCode::UNAUTHORIZED || *status == StatusCode::FORBIDDEN
+            },
+            _ => false,
+        }
+    }
+}
+
+/// maximum number of characters of response bodies that are included in errors
+const BODY_EXCERPT_LENGTH: usize = 512;
+
+/// This helper function returns the beginning of a response body for inclusion in errors.
+pub(crate) fn excerpt(body: &str) -> String {
+    match body.char_indices().nth(BODY_EXCERPT_LENGTH) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body.to_string(),
+    }
+}
+
+/// This enum represents the different ways of submitting one-time passwords for accounts with
+/// two-factor authentication to the OpenID provider.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OtpMode {
+    /// The one-time password is appended to the password (this is what FAS expects by default).
+    #[default]
+    Append,
+    /// The one-time password is submitted as a separate `otp` form field.
+    Separate,
+}
+
+/// This type contains the settings for one-time passwords.
+#[derive(Clone, Default)]
+struct OtpConfig {
+    mode: OtpMode,
+    provider: Option<Arc<dyn OtpProvider>>,
+}
+
+impl Debug for OtpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtpConfig")
+            .field("mode", &self.mode)
+            .field("provider", &self.provider.is_some())
+            .finish()
+    }
+}
+
+/// default maximum number of redirects that are followed from the login URL to the login form
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// This type contains the settings for following redirects from the login URL to the OpenID
+/// provider.
+#[derive(Clone, Debug)]
+struct RedirectConfig {
+    max_redirects: usize,
+    trusted_hosts: Vec<String>,
+    insecure_loopback: bool,
+}
+
+impl Default for RedirectConfig {
+    fn default() -> Self {
+        RedirectConfig {
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            trusted_hosts: Vec::new(),
+            insecure_loopback: false,
+        }
+    }
+}
+
+impl RedirectConfig {
+    /// This method checks whether the host of the given URL may be contacted while logging in. The
+    /// hosts of the login URL and of the OpenID provider are always trusted.
+    fn is_trusted(&self, url: &Url, login_url: &Url, auth_url: &Url) -> bool {
+        match url.host_str() {
+            Some(host) => {
+                Some(host) == login_url.host_str()
+                    || Some(host) == auth_url.host_str()
+                    || self.trusted_hosts.iter().any(|trusted| trusted == host)
+            },
+            None => false,
+        }
+    }
+
+    /// This method checks whether requests to the given URL are encrypted. Plain HTTP is only
+    /// considered safe for loopback addresses, and only if this was explicitly allowed.
+    fn is_secure(&self, url: &Url) -> bool {
+        if url.scheme() == "https" {
+            return true;
+        }
+
+        if !self.insecure_loopback {
+            return false;
+        }
+
+        match url.host() {
+            Some(url::Host::Domain(domain)) => domain == "localhost",
+            Some(url::Host::Ipv4(address)) => address.is_loopback(),
+            Some(url::Host::Ipv6(address)) => address.is_loopback(),
+            None => false,
+        }
+    }
+}
+
+/// This enum represents the result of submitting credentials to the OpenID provider.
+enum AuthAttempt {
+    /// The OpenID provider accepted the credentials.
+    Success(Box<OpenIDResponse>),
+    /// The OpenID provider rejected the credentials (with a JSON error response, or by returning
+    /// the login form again).
+    Failure { body: String, error: OpenIDClientError },
+}
+
+/// This helper function extracts the error code and message from JSON error responses of the
+/// OpenID provider.
+fn provider_error(value: &serde_json::Value) -> OpenIDClientError {
+    let field = |keys: &[&str]| {
+        keys.iter().find_map(|key| match value.get(key) {
+            Some(serde_json::Value::String(string)) => Some(string.clone()),
+            Some(serde_json::Value::Number(number)) => Some(number.to_string()),
+            _ => None,
+        })
+    };
+
+    OpenIDClientError::Provider {
+        code: field(&["error_code", "code", "status"]),
+        message: field(&["message", "error", "error_description"]),
+    }
+}
+
+/// error messages of the OpenID provider (in lower case) which indicate that a one-time password is
+/// missing or was rejected
+const OTP_ERROR_MESSAGES: &[&str] = &[
+    "one-time password is required",
+    "one-time password is invalid",
+    "invalid one-time password",
+    "otp is required",
+    "otp required",
+    "invalid otp",
+    "two-factor authentication is required",
+    "two-factor authentication failed",
+];
+
+/// This helper function checks whether a rejected authentication request was rejected because a
+/// (valid) one-time password is required, rather than because the password was wrong.
+///
+/// For JSON error responses, only the error message is checked for references to one-time
+/// passwords (as whole words). When the login form is returned again, it usually contains an input
+/// field for one-time passwords regardless of why the login failed, so only the specific error
+/// messages in [`OTP_ERROR_MESSAGES`] are taken into account.
+fn mentions_otp(error: &OpenIDClientError, body: &str) -> bool {
+    match error {
+        OpenIDClientError::Provider { message, .. } => {
+            let message = message.as_deref().unwrap_or_default().to_lowercase();
+            let words: Vec<&str> = message
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                .filter(|word| !word.is_empty())
+                .collect();
+
+            OTP_ERROR_MESSAGES.iter().any(|marker| message.contains(marker))
+                || words
+                    .iter()
+                    .any(|word| matches!(*word, "otp" | "2fa" | "one-time" | "two-factor" | "totp"))
+        },
+        _ => {
+            let body = body.to_lowercase();
+            OTP_ERROR_MESSAGES.iter().any(|marker| body.contains(marker))
+        },
+    }
 }
 
 /// This type represents the JSON response format of OpenID providers.
@@ -128,8 +400,13 @@ struct OpenIDParameters {
 pub struct OpenIDSessionBuilder<'a> {
     login_url: Url,
     auth_url: Url,
-    timeout: Option<Duration>,
-    user_agent: Option<&'a str>,
+    config: ClientConfig<'a>,
+    cookie_cache: CacheConfig,
+    logout_url: Option<Url>,
+    validation_url: Option<Url>,
+    otp: OtpConfig,
+    redirects: RedirectConfig,
+    replay_non_idempotent: bool,
 }
 
 /// This enum represents the different kinds of OpenID providers that can be interacted with.
@@ -146,68 +423,148 @@ pub enum OpenIDSessionKind {
     },
 }
 
-impl<'a> OpenIDSessionBuilder<'a> {
-    /// Construct a new [`OpenIDSessionBuilder`] instance with given login and authentication URLs.
-    pub fn new(login_url: Url, kind: OpenIDSessionKind) -> Self {
+impl OpenIDSessionKind {
+    /// This method returns the URL of the authentication endpoint of the OpenID provider.
+    fn auth_url(self) -> Url {
         use OpenIDSessionKind::*;
 
-        let auth_url = match kind {
+        match self {
             Default => Url::parse(FEDORA_OPENID_API).expect("Failed to parse a hardcoded URL."),
             Staging => Url::parse(FEDORA_OPENID_STG_API).expect("Failed to parse a hardcoded URL."),
             Custom { auth_url } => {
                 log::warn!("Authenticating with nonstandard OpenID provider URL: {}", auth_url);
                 auth_url
             },
-        };
+        }
+    }
+}
 
+impl<'a> OpenIDSessionBuilder<'a> {
+    /// Construct a new [`OpenIDSessionBuilder`] instance with given login and authentication URLs.
+    pub fn new(login_url: Url, kind: OpenIDSessionKind) -> Self {
         OpenIDSessionBuilder {
             login_url,
-            auth_url,
-            timeout: None,
-            user_agent: None,
+            auth_url: kind.auth_url(),
+            config: ClientConfig::default(),
+            cookie_cache: CacheConfig::default(),
+            logout_url: None,
+            validation_url: None,
+            otp: OtpConfig::default(),
+            redirects: RedirectConfig::default(),
+            replay_non_idempotent: false,
         }
     }
 
-    /// Override the default request timeout duration.
+    client_config_methods!('a);
+
+    /// Override the default location of the on-disk cookie cache (for example, to use a separate
+    /// [`CookieCache::profile`] for every identity provider and username).
     #[must_use]
-    pub fn timeout(mut self, timeout: Duration) -> Self {
-        self.timeout = Some(timeout);
+    pub fn cookie_cache(mut self, cookie_cache: CookieCache) -> Self {
+        self.cookie_cache.location = cookie_cache;
         self
     }
 
-    /// Override the default User-Agent header.
+    /// Persist session cookies for the given profile in a custom [`SessionStore`] instead of the
+    /// on-disk cookie cache.
     #[must_use]
-    pub fn user_agent(mut self, user_agent: &'a str) -> Self {
-        self.user_agent = Some(user_agent);
+    pub fn session_store<S: SessionStore + 'static>(mut self, store: S, profile: &str) -> Self {
+        self.cookie_cache.store = Some((Arc::new(store), profile.to_string()));
         self
     }
 
-    /// This method consumes the [`OpenIDSessionBuilder`] and returns an [`OpenIDSessionLogin`] that
-    /// can subsequently be used for logging in by just supplying a username and password.
-    pub fn build(self) -> OpenIDSessionLogin {
-        let timeout = match self.timeout {
-            Some(timeout) => timeout,
-            None => DEFAULT_TIMEOUT,
-        };
+    /// Override how existing cookie caches that are accessible by other users are handled (by
+    /// default, they are not loaded).
+    #[must_use]
+    pub fn cookie_permissions(mut self, check: PermissionCheck) -> Self {
+        self.cookie_cache.permissions = check;
+        self
+    }
 
-        let user_agent = match self.user_agent {
-            Some(user_agent) => user_agent,
-            None => FEDORA_USER_AGENT,
-        };
+    /// Encrypt the on-disk cookie cache with the given key.
+    ///
+    /// This method is only available if the `encryption` feature is enabled.
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn cookie_encryption(mut self, key: CacheKey) -> Self {
+        self.cookie_cache.key = Some(key);
+        self
+    }
 
-        // set default headers for our requests
-        // - User Agent
-        // - Accept: application/json
-        let mut default_headers = HeaderMap::new();
+    /// Set the URL that is visited by [`Session::logout`] for logging out on the server (for
+    /// example, `https://bodhi.fedoraproject.org/logout`).
+    #[must_use]
+    pub fn logout_url(mut self, logout_url: Url) -> Self {
+        self.logout_url = Some(logout_url);
+        self
+    }
 
-        default_headers.append(
-            USER_AGENT,
-            HeaderValue::from_str(user_agent).expect("Failed to parse hardcoded HTTP headers."),
-        );
-        default_headers.append(ACCEPT, HeaderValue::from_static("application/json"));
+    /// Check whether cached session cookies are still accepted by the server before using them, by
+    /// sending a request to the given URL (for example, an endpoint that returns information about
+    /// the current user). If the server rejects the cookies, the session is re-authenticated.
+    #[must_use]
+    pub fn validation_url(mut self, validation_url: Url) -> Self {
+        self.validation_url = Some(validation_url);
+        self
+    }
+
+    /// Request a one-time password from the given provider if the OpenID provider asks for one
+    /// (for accounts with two-factor authentication).
+    #[must_use]
+    pub fn otp_provider<P: OtpProvider + 'static>(mut self, provider: P) -> Self {
+        self.otp.provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Override how one-time passwords are submitted to the OpenID provider.
+    #[must_use]
+    pub fn otp_mode(mut self, mode: OtpMode) -> Self {
+        self.otp.mode = mode;
+        self
+    }
+
+    /// Override the maximum number of redirects that are followed from the login URL to the login
+    /// form of the OpenID provider (10 by default).
+    #[must_use]
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.redirects.max_redirects = max_redirects;
+        self
+    }
+
+    /// Allow contacting the given host while logging in. By default, only redirects to the hosts of
+    /// the login URL and of the OpenID provider are followed. Credentials are only ever sent to the
+    /// OpenID provider, and only over HTTPS.
+    #[must_use]
+    pub fn trusted_host(mut self, host: &str) -> Self {
+        self.redirects.trusted_hosts.push(host.to_string());
+        self
+    }
+
+    /// Allow sending credentials over plain HTTP to loopback addresses (`localhost`, `127.0.0.1`,
+    /// or `::1`), for example, for testing with a local server. This is disabled by default.
+    #[must_use]
+    pub fn insecure_loopback(mut self, allow: bool) -> Self {
+        self.redirects.insecure_loopback = allow;
+        self
+    }
 
+    /// Send requests with non-idempotent methods (for example, `POST`) again after the session was
+    /// re-authenticated because it had expired (see
+    /// [`OpenIDAuthenticator::replay_non_idempotent`]). This only applies to sessions that were
+    /// built with [`OpenIDSessionLogin::login_with_provider`].
+    #[must_use]
+    pub fn replay_non_idempotent(mut self, replay: bool) -> Self {
+        self.replay_non_idempotent = replay;
+        self
+    }
+
+    /// This method consumes the [`OpenIDSessionBuilder`] and returns an [`OpenIDSessionLogin`] that
+    /// can subsequently be used for logging in by just supplying a username and password.
+    pub fn build(self) -> OpenIDSessionLogin {
         // try loading persistent cookie jar
-        let (jar, fresh): (CachingJar, bool) = match CachingJar::read_from_disk() {
+        let mut cache_error = None;
+
+        let (jar, fresh): (CachingJar, bool) = match CachingJar::read_from_store(&self.cookie_cache) {
             Ok(jar) => {
                 let fresh = jar
                     .store
@@ -230,10 +587,18 @@ impl<'a> OpenIDSessionBuilder<'a> {
                 if let CookieCacheError::DoesNotExist = error {
                     // on-disk cache does not exist yet
                     log::info!("Creating new cookie cache.");
+                } else if let CookieCacheError::InsecurePermissions { .. } = error {
+                    // on-disk cache is accessible by other users
+                    log::warn!("Ignoring cached cookies: {}", error);
                 } else {
-                    // failed to deserialize on-disk cache
+                    // failed to deserialize or decrypt on-disk cache
                     log::info!("Failed to load cached cookies: {}", error);
                 }
+
+                if !matches!(error, CookieCacheError::DoesNotExist) {
+                    cache_error = Some(error);
+                }
+
                 (CachingJar::empty(), false)
             },
         };
@@ -241,10 +606,16 @@ impl<'a> OpenIDSessionBuilder<'a> {
         OpenIDSessionLogin {
             login_url: self.login_url,
             auth_url: self.auth_url,
-            headers: default_headers,
-            timeout,
+            template: self.config.resolve(),
+            cookie_cache: self.cookie_cache,
+            logout_url: self.logout_url,
+            validation_url: self.validation_url,
+            otp: self.otp,
+            redirects: self.redirects,
+            replay_non_idempotent: self.replay_non_idempotent,
             jar,
             fresh,
+            cache_error,
         }
     }
 }
@@ -255,13 +626,26 @@ impl<'a> OpenIDSessionBuilder<'a> {
 pub struct OpenIDSessionLogin {
     login_url: Url,
     auth_url: Url,
-    headers: HeaderMap,
-    timeout: Duration,
+    template: ClientTemplate,
+    cookie_cache: CacheConfig,
+    logout_url: Option<Url>,
+    validation_url: Option<Url>,
+    otp: OtpConfig,
+    redirects: RedirectConfig,
+    replay_non_idempotent: bool,
     jar: CachingJar,
     fresh: bool,
+    cache_error: Option<CookieCacheError>,
 }
 
 impl OpenIDSessionLogin {
+    /// This method returns the error that occurred when loading cached session cookies, if any
+    /// (for example, if the cookie cache could not be read or decrypted). In this case, logging in
+    /// starts with an empty cookie jar. A missing cookie cache is not considered an error.
+    pub fn cookie_cache_error(&self) -> Option<&CookieCacheError> {
+        self.cache_error.as_ref()
+    }
+
     /// This method Attempts to authenticate with the specified OpenID provider, and return a
     /// pre-authenticated session on success.
     ///
@@ -278,85 +662,408 @@ impl OpenIDSessionLogin {
     /// let auth_session = login.login("janedoe", "CorrectHorseBatteryStaple").await.unwrap();
     /// ```
     pub async fn login(self, username: &str, password: &str) -> Result<Session, OpenIDClientError> {
-        let jar = Arc::new(self.jar);
+        let login_url = self.login_url.clone();
+        let auth_url = self.auth_url.clone();
 
-        if self.fresh {
-            // write non-expired cookies back to disk
-            if let Err(error) = jar.write_to_disk() {
-                log::error!("Failed to write cached cookies: {}", error);
-            }
+        let credentials = CredentialSource::Static(Credentials::new(username, password));
+        let authenticator = OpenIDAuthenticator::from_urls(login_url, auth_url, credentials);
 
-            // construct new client with default redirect handling, but keep all cookies
-            let client: Client = Client::builder()
-                .default_headers(self.headers)
-                .cookie_store(true)
-                .cookie_provider(jar)
-                .timeout(self.timeout)
-                .build()
-                .expect("Failed to initialize the network stack.");
+        self.start(authenticator, false).await
+    }
 
-            return Ok(Session { client });
-        }
+    /// This method Attempts to authenticate with the specified OpenID provider, and return a
+    /// pre-authenticated session on success. Credentials are requested from the given
+    /// [`CredentialProvider`] (or closure) only when they are needed, and they are not stored.
+    ///
+    /// The returned session remembers how it was authenticated: If the server indicates that the
+    /// session has expired (see [`OpenIDAuthenticator`]), credentials are requested from the
+    /// provider again, the login is repeated, and the failed request is sent again (this only
+    /// happens for requests that are sent with [`Session::send`] or [`Session::execute`], and only
+    /// for idempotent methods unless [`OpenIDSessionBuilder::replay_non_idempotent`] was set).
+    ///
+    /// ```ignore
+    /// use fedora::{OpenIDSessionKind, Session, TerminalPrompt};
+    /// use url::Url;
+    ///
+    /// let session = Session::openid_auth(
+    ///     Url::parse("https://bodhi.fedoraproject.org/login").unwrap(),
+    ///     OpenIDSessionKind::Default
+    /// )
+    /// .build()
+    /// .login_with_provider(TerminalPrompt::new().username("janedoe"))
+    /// .await
+    /// .unwrap();
+    /// ```
+    pub async fn login_with_provider<P>(self, provider: P) -> Result<Session, OpenIDClientError>
+    where
+        P: CredentialProvider + 'static,
+    {
+        let login_url = self.login_url.clone();
+        let auth_url = self.auth_url.clone();
+
+        let credentials = CredentialSource::Provider(Arc::new(provider));
+        let authenticator = OpenIDAuthenticator::from_urls(login_url, auth_url, credentials);
+
+        self.start(authenticator, true).await
+    }
+
+    /// This method authenticates with the given [`OpenIDAuthenticator`] (unless cached session
+    /// cookies can be used), and returns the authenticated session. If `retain` is `true`, the
+    /// session keeps the authenticator for re-authenticating later.
+    async fn start(self, authenticator: OpenIDAuthenticator, retain: bool) -> Result<Session, OpenIDClientError> {
+        let authenticator = OpenIDAuthenticator {
+            otp: self.otp,
+            redirects: self.redirects,
+            replay_non_idempotent: self.replay_non_idempotent,
+            ..authenticator
+        };
+
+        let jar = Arc::new(self.jar);
 
         // construct reqwest session for authentication with:
         // - custom default headers
         // - no-redirects policy
-        let client: Client = Client::builder()
-            .default_headers(self.headers.clone())
-            .cookie_store(true)
-            .cookie_provider(jar.clone())
-            .timeout(self.timeout)
-            .redirect(Policy::none())
-            .build()
-            .expect("Failed to initialize the network stack.");
-
-        // start log in process
-        let mut url = self.login_url;
-        let mut state: HashMap<Cow<str>, Cow<str>> = HashMap::new();
-
-        // ask fedora OpenID system how to authenticate:
-        // - follow redirects until the login form is reached
-        // - collect authentication request parameters along the way
-        loop {
-            let response = client.get(url.clone()).send().await?;
-            let status = response.status();
+        let client: Client = self.template.build(
+            self.template
+                .client()
+                .cookie_store(true)
+                .cookie_provider(jar.clone())
+                .redirect(Policy::none()),
+        )?;
 
-            // get and keep track of URL query arguments
-            for (key, value) in url.query_pairs() {
-                // key/value-pairs must be converted to owned strings, because the
-                // URL they are borrowed from is dropped after every loop iteration
-                state.insert(Cow::Owned(key.to_string()), Cow::Owned(value.to_string()));
-            }
+        let mut fresh = self.fresh;
+
+        // check if the server still accepts cached session cookies
+        if let (true, Some(validation_url)) = (fresh, &self.validation_url) {
+            fresh = validate_session(&client, validation_url, &self.login_url, &self.auth_url).await;
 
-            if status.is_redirection() {
-                // set next URL to redirect destination
-                let header: &HeaderValue = match response.headers().get("location") {
-                    Some(value) => value,
-                    None => {
-                        return Err(OpenIDClientError::Redirection {
-                            error: String::from("No redirect URL provided in HTTP redirect headers."),
-                        });
-                    },
-                };
-
-                let string = match header.to_str() {
-                    Ok(string) => string,
-                    Err(_) => {
-                        return Err(OpenIDClientError::Redirection {
-                            error: String::from("Failed to decode redirect URL."),
-                        });
-                    },
-                };
-
-                url = Url::parse(string)?;
-            } else {
-                break;
+            if !fresh {
+                log::info!("Session cookie(s) were rejected by the server, re-authentication necessary.");
+                if let Some(host) = self.login_url.host_str() {
+                    jar.remove(Some(host));
+                }
             }
         }
 
-        // insert username and password into the state / query
-        state.insert(Cow::Borrowed("username"), Cow::Borrowed(username));
-        state.insert(Cow::Borrowed("password"), Cow::Borrowed(password));
+        if fresh {
+            authenticator.remember(&client);
+        } else {
+            authenticator.start(&client).await?;
+        }
+
+        // write non-expired or freshly baked cookies back to disk
+        if let Err(error) = jar.write_to_store(&self.cookie_cache) {
+            log::error!("Failed to write cookie jar to disk: {}", error);
+        }
+
+        // construct new client with default redirect handling, but keep all cookies
+        let client: Client = self
+            .template
+            .build(self.template.client().cookie_store(true).cookie_provider(jar.clone()))?;
+
+        let auth: Option<Arc<dyn Authenticator>> = if retain { Some(Arc::new(authenticator)) } else { None };
+
+        Ok(Session {
+            auth,
+            cookies: Some(jar),
+            cookie_cache: Some(self.cookie_cache),
+            logout_url: self.logout_url,
+            ..self.template.session(client)
+        })
+    }
+}
+
+/// This helper function sends a request to the validation URL with the cached session cookies, and
+/// returns whether the server still accepts them. Responses with status "401 Unauthorized" or
+/// "403 Forbidden", redirects to the login URL or to the OpenID provider, and failed requests are
+/// treated as rejections.
+async fn validate_session(client: &Client, validation_url: &Url, login_url: &Url, auth_url: &Url) -> bool {
+    let response = match client.get(validation_url.clone()).send().await {
+        Ok(response) => response,
+        Err(error) => {
+            log::info!("Failed to validate session cookie(s): {}", error);
+            return false;
+        },
+    };
+    let status = response.status();
+
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        return false;
+    }
+
+    if !status.is_redirection() {
+        return true;
+    }
+
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| validation_url.join(value).ok());
+
+    let location = match location {
+        Some(location) => location,
+        None => return false,
+    };
+
+    let login_redirect = location.host_str() == auth_url.host_str()
+        || (location.origin() == login_url.origin() && location.path() == login_url.path());
+
+    !login_redirect
+}
+
+/// This enum represents where an [`OpenIDAuthenticator`] gets its credentials from.
+#[derive(Clone)]
+enum CredentialSource {
+    /// fixed credentials
+    Static(Credentials),
+    /// credentials that are requested from a provider every time they are needed
+    Provider(Arc<dyn CredentialProvider>),
+}
+
+impl CredentialSource {
+    async fn get(&self) -> Result<Credentials, OpenIDClientError> {
+        match self {
+            CredentialSource::Static(credentials) => Ok(credentials.clone()),
+            CredentialSource::Provider(provider) => {
+                let provider = provider.clone();
+                run_provider(move || provider.credentials()).await
+            },
+        }
+    }
+}
+
+/// This helper function calls a [`CredentialProvider`] or [`OtpProvider`] on a thread where
+/// blocking is allowed, since providers can wait for user input on the terminal, run external
+/// programs, or talk to the Secret Service.
+async fn run_provider<T, F>(provider: F) -> Result<T, OpenIDClientError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AuthError> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(provider).await {
+        Ok(result) => result.map_err(|error| OpenIDClientError::Credentials { error }),
+        Err(error) => Err(OpenIDClientError::Credentials { error: error.into() }),
+    }
+}
+
+/// re-authentication is skipped if the last login happened less than this duration ago (for
+/// example, if multiple concurrent requests failed because the session expired)
+const REAUTHENTICATION_INTERVAL: Duration = Duration::from_secs(10);
+
+/// This type implements the OpenID login flow as an [`Authenticator`], which can be used with
+/// [`Session::builder`] to build sessions with custom settings.
+///
+/// When the server indicates that the session has expired, the login flow is repeated, and the
+/// failed request is sent again. The following responses are treated as an indication that the
+/// session has expired:
+///
+/// - responses with status "401 Unauthorized",
+/// - responses that were redirected to the login URL or to the OpenID provider.
+///
+/// Other responses (for example, "403 Forbidden", which usually means that the user is not
+/// allowed to do something) are returned as they are.
+///
+/// Only requests with idempotent methods (`GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT`, and `DELETE`)
+/// are sent again after logging in again, since the server might already have processed the
+/// first attempt. For other methods (for example, `POST`), the session is re-authenticated, but
+/// the original response is returned, unless [`OpenIDAuthenticator::replay_non_idempotent`] was
+/// set.
+///
+/// Unlike [`OpenIDSessionLogin`], this type does not use the on-disk cookie cache.
+pub struct OpenIDAuthenticator {
+    login_url: Url,
+    auth_url: Url,
+    credentials: CredentialSource,
+    otp: OtpConfig,
+    redirects: RedirectConfig,
+    replay_non_idempotent: bool,
+    /// client (without redirects) that shares its cookie jar with the session, used for
+    /// re-authenticating
+    client: Mutex<Option<Client>>,
+    last_login: Mutex<Option<Instant>>,
+    login_lock: tokio::sync::Mutex<()>,
+}
+
+impl Debug for OpenIDAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // do not leak credentials into logs
+        f.debug_struct("OpenIDAuthenticator")
+            .field("login_url", &self.login_url)
+            .field("auth_url", &self.auth_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OpenIDAuthenticator {
+    /// Construct a new [`OpenIDAuthenticator`] instance with the given login URL, OpenID provider,
+    /// and credentials.
+    pub fn new(login_url: Url, kind: OpenIDSessionKind, username: &str, password: &str) -> Self {
+        let credentials = CredentialSource::Static(Credentials::new(username, password));
+        OpenIDAuthenticator::from_urls(login_url, kind.auth_url(), credentials)
+    }
+
+    /// Construct a new [`OpenIDAuthenticator`] instance with the given login URL and OpenID
+    /// provider, which requests credentials from the given [`CredentialProvider`] every time it
+    /// needs to log in.
+    pub fn with_provider<P>(login_url: Url, kind: OpenIDSessionKind, provider: P) -> Self
+    where
+        P: CredentialProvider + 'static,
+    {
+        let credentials = CredentialSource::Provider(Arc::new(provider));
+        OpenIDAuthenticator::from_urls(login_url, kind.auth_url(), credentials)
+    }
+
+    /// This method constructs a new [`OpenIDAuthenticator`] instance with the given credential
+    /// source and default settings.
+    fn from_urls(login_url: Url, auth_url: Url, credentials: CredentialSource) -> Self {
+        OpenIDAuthenticator {
+            login_url,
+            auth_url,
+            credentials,
+            otp: OtpConfig::default(),
+            redirects: RedirectConfig::default(),
+            replay_non_idempotent: false,
+            client: Mutex::new(None),
+            last_login: Mutex::new(None),
+            login_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Request a one-time password from the given provider if the OpenID provider asks for one
+    /// (for accounts with two-factor authentication).
+    #[must_use]
+    pub fn otp_provider<P: OtpProvider + 'static>(mut self, provider: P) -> Self {
+        self.otp.provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Override how one-time passwords are submitted to the OpenID provider.
+    #[must_use]
+    pub fn otp_mode(mut self, mode: OtpMode) -> Self {
+        self.otp.mode = mode;
+        self
+    }
+
+    /// Override the maximum number of redirects that are followed from the login URL to the login
+    /// form of the OpenID provider (10 by default).
+    #[must_use]
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.redirects.max_redirects = max_redirects;
+        self
+    }
+
+    /// Allow contacting the given host while logging in. By default, only redirects to the hosts of
+    /// the login URL and of the OpenID provider are followed. Credentials are only ever sent to the
+    /// OpenID provider, and only over HTTPS.
+    #[must_use]
+    pub fn trusted_host(mut self, host: &str) -> Self {
+        self.redirects.trusted_hosts.push(host.to_string());
+        self
+    }
+
+    /// Allow sending credentials over plain HTTP to loopback addresses (`localhost`, `127.0.0.1`,
+    /// or `::1`), for example, for testing with a local server. This is disabled by default.
+    #[must_use]
+    pub fn insecure_loopback(mut self, allow: bool) -> Self {
+        self.redirects.insecure_loopback = allow;
+        self
+    }
+
+    /// Send requests with non-idempotent methods (for example, `POST`) again after the session was
+    /// re-authenticated because it had expired. This is only safe if the server did not process
+    /// the first attempt (which is the case if it was rejected or redirected to the login page
+    /// before it was handled).
+    #[must_use]
+    pub fn replay_non_idempotent(mut self, replay: bool) -> Self {
+        self.replay_non_idempotent = replay;
+        self
+    }
+
+    /// This method keeps a handle to the given client, which is used for re-authenticating.
+    fn remember(&self, client: &Client) {
+        *self.client.lock().expect("Poisoned lock!") = Some(client.clone());
+    }
+
+    /// This method runs the initial login flow with the given client, and keeps a handle to the
+    /// client for re-authenticating.
+    async fn start(&self, client: &Client) -> Result<(), OpenIDClientError> {
+        self.remember(client);
+        self.authenticate(client).await?;
+        *self.last_login.lock().expect("Poisoned lock!") = Some(Instant::now());
+        Ok(())
+    }
+
+    /// This method repeats the login flow, unless another request already did this recently.
+    async fn reauthenticate(&self) -> Result<bool, OpenIDClientError> {
+        let client = match &*self.client.lock().expect("Poisoned lock!") {
+            Some(client) => client.clone(),
+            None => return Ok(false),
+        };
+
+        let _guard = self.login_lock.lock().await;
+
+        let recent = self
+            .last_login
+            .lock()
+            .expect("Poisoned lock!")
+            .map_or(false, |last_login| last_login.elapsed() < REAUTHENTICATION_INTERVAL);
+
+        if recent {
+            return Ok(true);
+        }
+
+        log::info!("Session has expired, logging in again.");
+        self.authenticate(&client).await?;
+        *self.last_login.lock().expect("Poisoned lock!") = Some(Instant::now());
+        Ok(true)
+    }
+
+    /// This method checks whether the given URL points to the login URL or to the OpenID provider.
+    fn is_login_page(&self, url: &Url) -> bool {
+        url.host_str() == self.auth_url.host_str()
+            || (url.host_str() == self.login_url.host_str() && url.path() == self.login_url.path())
+    }
+
+    /// This method checks whether a response with the given status, for a request to the given
+    /// URL, indicates that the session has expired.
+    fn is_expired(&self, status: StatusCode, request_url: &Url, response_url: &Url) -> bool {
+        if status == StatusCode::UNAUTHORIZED {
+            return true;
+        }
+
+        // redirects are followed by the session, so check where the request ended up
+        response_url != request_url && self.is_login_page(response_url)
+    }
+
+    /// This method checks whether a request with the given method can be sent again after
+    /// re-authenticating.
+    fn may_replay(&self, method: &Method) -> bool {
+        let idempotent = matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+        );
+
+        idempotent || self.replay_non_idempotent
+    }
+
+    /// This method runs the OpenID login flow with the given client, which must not follow
+    /// redirects. Session cookies are stored in the cookie jar of the client.
+    async fn authenticate(&self, client: &Client) -> Result<(), OpenIDClientError> {
+        // ask fedora OpenID system how to authenticate
+        let parameters = self.walk_redirects(client).await?;
+
+        // credentials must never be sent in plain text
+        if !self.redirects.is_secure(&self.auth_url) {
+            return Err(OpenIDClientError::InsecureTransport {
+                url: self.auth_url.clone(),
+            });
+        }
+
+        let mut state: HashMap<Cow<str>, Cow<str>> = parameters
+            .iter()
+            .map(|(key, value)| (Cow::Borrowed(key.as_str()), Cow::Borrowed(value.as_str())))
+            .collect();
 
         // insert additional query arguments into the state / query
         state.insert(
@@ -369,25 +1076,57 @@ impl OpenIDSessionLogin {
             .entry(Cow::Borrowed("openid.mode"))
             .or_insert_with(|| Cow::Borrowed("checkid_setup"));
 
-        // send authentication request
-        let response = client.post(self.auth_url).form(&state).send().await.map_err(|error| {
-            OpenIDClientError::Authentication {
-                error: error.to_string(),
+        let credentials = self.credentials.get().await?;
+        let mut otp = credentials.otp.clone();
+
+        let openid_auth = loop {
+            // insert username, password, and one-time password into the state / query
+            let mut form = state.clone();
+            form.insert(Cow::Borrowed("username"), Cow::Borrowed(credentials.username.as_str()));
+
+            let password = match (&otp, self.otp.mode) {
+                (Some(otp), OtpMode::Append) => {
+                    Secret::new(format!("{}{}", credentials.password.expose(), otp.expose()))
+                },
+                _ => credentials.password.clone(),
+            };
+            form.insert(Cow::Borrowed("password"), Cow::Borrowed(password.expose()));
+
+            if let (Some(otp), OtpMode::Separate) = (&otp, self.otp.mode) {
+                form.insert(Cow::Borrowed("otp"), Cow::Borrowed(otp.expose()));
             }
-        })?;
 
-        // the only indication that authenticating failed is a non-JSON response, or invalid message
-        let string = response.text().await?;
-        let openid_auth: OpenIDResponse = serde_json::from_str(&string).map_err(|_| OpenIDClientError::Login)?;
+            match self.submit(client, &form).await? {
+                AuthAttempt::Success(openid_auth) => break *openid_auth,
+                AuthAttempt::Failure { body, error } => {
+                    if !mentions_otp(&error, &body) {
+                        return Err(error);
+                    }
 
-        if !openid_auth.success {
-            return Err(OpenIDClientError::Authentication {
-                error: String::from("OpenID endpoint returned an error code."),
-            });
-        }
+                    if otp.is_some() {
+                        return Err(OpenIDClientError::InvalidOtp);
+                    }
+
+                    // ask for a one-time password and try again
+                    match &self.otp.provider {
+                        Some(provider) => {
+                            log::info!("OpenID provider requires a one-time password.");
+                            let provider = provider.clone();
+                            otp = Some(run_provider(move || provider.otp()).await?);
+                        },
+                        None => return Err(OpenIDClientError::OtpRequired),
+                    }
+                },
+            }
+        };
 
         let return_url = Url::parse(&openid_auth.response.return_to)?;
 
+        // the signed OpenID response must only be sent back to the original site
+        if !self.redirects.is_trusted(&return_url, &self.login_url, &self.auth_url) {
+            return Err(OpenIDClientError::UntrustedHost { url: return_url });
+        }
+
         let response = client
             .post(return_url)
             .form(&openid_auth.response)
@@ -395,26 +1134,295 @@ impl OpenIDSessionLogin {
             .await
             .map_err(|error| OpenIDClientError::Request { error })?;
 
-        if !response.status().is_success() && !response.status().is_redirection() {
-            return Err(OpenIDClientError::Authentication {
-                error: String::from("Failed to complete authentication with the original site."),
+        let status = response.status();
+        if !status.is_success() && !status.is_redirection() {
+            let url = response.url().clone();
+            let body = response.text().await.unwrap_or_default();
+
+            return Err(OpenIDClientError::HttpStatus {
+                url,
+                status,
+                body: excerpt(&body),
             });
         };
 
-        // write freshly baked cookies back to disk
-        if let Err(error) = jar.write_to_disk() {
-            log::error!("Failed to write cookie jar to disk: {}", error);
+        Ok(())
+    }
+}
+
+impl OpenIDAuthenticator {
+    /// This method follows redirects from the login URL until the login form of the OpenID
+    /// provider is reached, and returns the authentication request parameters that were passed to
+    /// the OpenID provider.
+    ///
+    /// Every hop is logged, relative `Location` headers are resolved against the current URL, and
+    /// the walk is aborted if it exceeds the hop limit, revisits a URL, leaves the set of trusted
+    /// hosts, or downgrades from HTTPS to plain HTTP.
+    async fn walk_redirects(&self, client: &Client) -> Result<HashMap<String, String>, OpenIDClientError> {
+        let mut url = self.login_url.clone();
+        let mut parameters: HashMap<String, String> = HashMap::new();
+        let mut visited: HashSet<Url> = HashSet::new();
+
+        loop {
+            if !visited.insert(url.clone()) {
+                return Err(OpenIDClientError::RedirectLoop { url });
+            }
+
+            // only parameters that are passed to the OpenID provider are part of the request
+            if url.host_str() == self.auth_url.host_str() {
+                for (key, value) in url.query_pairs() {
+                    parameters.insert(key.into_owned(), value.into_owned());
+                }
+            }
+
+            let response = client.get(url.clone()).send().await?;
+
+            if !response.status().is_redirection() {
+                log::debug!("Reached OpenID login form at {}", url);
+                return Ok(parameters);
+            }
+
+            if visited.len() > self.redirects.max_redirects {
+                return Err(OpenIDClientError::TooManyRedirects {
+                    url,
+                    limit: self.redirects.max_redirects,
+                });
+            }
+
+            let header: &HeaderValue = match response.headers().get(LOCATION) {
+                Some(value) => value,
+                None => {
+                    return Err(OpenIDClientError::Redirection { url, location: None });
+                },
+            };
+
+            let location = match header.to_str() {
+                Ok(location) => location,
+                Err(_) => {
+                    return Err(OpenIDClientError::Redirection {
+                        location: Some(String::from_utf8_lossy(header.as_bytes()).into_owned()),
+                        url,
+                    });
+                },
+            };
+
+            // Location headers can contain URLs that are relative to the current URL
+            let next = url.join(location)?;
+            log::debug!("Following redirect: {} -> {}", url, next);
+
+            if !self.redirects.is_trusted(&next, &self.login_url, &self.auth_url) {
+                return Err(OpenIDClientError::UntrustedHost { url: next });
+            }
+
+            if self.redirects.is_secure(&url) && !self.redirects.is_secure(&next) {
+                return Err(OpenIDClientError::InsecureTransport { url: next });
+            }
+
+            url = next;
         }
+    }
 
-        // construct new client with default redirect handling, but keep all cookies
-        let client: Client = Client::builder()
-            .default_headers(self.headers)
-            .cookie_store(true)
-            .cookie_provider(jar)
-            .timeout(self.timeout)
-            .build()
-            .expect("Failed to initialize the network stack.");
-
-        Ok(Session { client })
+    /// This method sends an authentication request with the given form data to the OpenID
+    /// provider.
+    async fn submit(
+        &self,
+        client: &Client,
+        form: &HashMap<Cow<'_, str>, Cow<'_, str>>,
+    ) -> Result<AuthAttempt, OpenIDClientError> {
+        let response = client.post(self.auth_url.clone()).form(form).send().await?;
+
+        let url = response.url().clone();
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        let body = response.text().await?;
+
+        if let Ok(openid_auth) = serde_json::from_str::<OpenIDResponse>(&body) {
+            if openid_auth.success {
+                return Ok(AuthAttempt::Success(Box::new(openid_auth)));
+            }
+        }
+
+        // JSON error responses contain an error code and / or message
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&body) {
+            let error = provider_error(&value);
+            return Ok(AuthAttempt::Failure { body, error });
+        }
+
+        if !status.is_success() {
+            return Err(OpenIDClientError::HttpStatus {
+                url,
+                status,
+                body: excerpt(&body),
+            });
+        }
+
+        // the only indication that authenticating failed is the login form being returned again
+        match content_type.as_deref() {
+            Some(value) if value.starts_with("text/html") => Ok(AuthAttempt::Failure {
+                body,
+                error: OpenIDClientError::Login,
+            }),
+            _ => Err(OpenIDClientError::UnexpectedContentType { url, content_type }),
+        }
+    }
+}
+
+impl Authenticator for OpenIDAuthenticator {
+    fn login<'a>(&'a self, client: &'a Client) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move { Ok(self.start(client).await?) })
+    }
+
+    fn is_rejected(&self, response: &Response) -> bool {
+        // the URL of the request is checked in recover
+        response.status() == StatusCode::UNAUTHORIZED || self.is_login_page(response.url())
+    }
+
+    fn recover<'a>(
+        &'a self,
+        request: &'a mut Request,
+        response: &'a Response,
+    ) -> BoxFuture<'a, Result<bool, AuthError>> {
+        Box::pin(async move {
+            if !self.is_expired(response.status(), request.url(), response.url()) {
+                return Ok(false);
+            }
+
+            if !self.reauthenticate().await? {
+                return Ok(false);
+            }
+
+            if !self.may_replay(request.method()) {
+                log::info!(
+                    "Session was re-authenticated, but the {} request to {} is not sent again.",
+                    request.method(),
+                    request.url()
+                );
+                return Ok(false);
+            }
+
+            // cookies are added to the request again when it is sent
+            Ok(true)
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// login form that is returned again after a failed login, including an input for one-time
+    /// passwords
+    const LOGIN_FORM: &str = r#"<html>
+<body>
+  <div class="alert alert-danger">{error}</div>
+  <form method="post" action="/openid/">
+    <input type="text" name="username">
+    <input type="password" name="password">
+    <label for="otp">One-time password (OTP), if two-factor authentication is enabled</label>
+    <input type="text" name="otp" id="otp">
+    <p>Reduce your footprint: log out when you are done.</p>
+  </form>
+</body>
+</html>"#;
+
+    fn json_failure(body: &str) -> bool {
+        let value: serde_json::Value = serde_json::from_str(body).unwrap();
+        mentions_otp(&provider_error(&value), body)
+    }
+
+    fn html_failure(error: &str) -> bool {
+        mentions_otp(&OpenIDClientError::Login, &LOGIN_FORM.replace("{error}", error))
+    }
+
+    #[test]
+    fn wrong_password_is_not_an_otp_failure() {
+        assert!(!json_failure(
+            r#"{"success": false, "message": "Invalid username or password (footprint: 1a2b)"}"#
+        ));
+        assert!(!json_failure(
+            r#"{"success": false, "error": "Authentication failed", "otp_hint": "otp"}"#
+        ));
+        assert!(!html_failure("Invalid username or password."));
+    }
+
+    #[test]
+    fn missing_otp_is_an_otp_failure() {
+        assert!(json_failure(r#"{"success": false, "message": "OTP required"}"#));
+        assert!(json_failure(
+            r#"{"success": false, "error_description": "Two-factor authentication is required."}"#
+        ));
+        assert!(html_failure("A one-time password is required for this account."));
+        assert!(html_failure("Invalid OTP, please try again."));
+    }
+
+    fn authenticator() -> OpenIDAuthenticator {
+        OpenIDAuthenticator::new(
+            Url::parse("https://bodhi.fedoraproject.org/login").unwrap(),
+            OpenIDSessionKind::Default,
+            "janedoe",
+            "CorrectHorseBatteryStaple",
+        )
+    }
+
+    #[test]
+    fn only_unauthorized_responses_and_login_redirects_are_expired() {
+        let auth = authenticator();
+        let request = Url::parse("https://bodhi.fedoraproject.org/updates/").unwrap();
+        let login = Url::parse("https://bodhi.fedoraproject.org/login?came_from=%2Fupdates%2F").unwrap();
+        let provider = Url::parse("https://id.fedoraproject.org/openid/").unwrap();
+
+        assert!(auth.is_expired(StatusCode::UNAUTHORIZED, &request, &request));
+        assert!(auth.is_expired(StatusCode::OK, &request, &login));
+        assert!(auth.is_expired(StatusCode::OK, &request, &provider));
+
+        // permission errors do not mean that the session has expired
+        assert!(!auth.is_expired(StatusCode::FORBIDDEN, &request, &request));
+        assert!(!auth.is_expired(StatusCode::OK, &request, &request));
+
+        // requests that were sent to the login page directly were not redirected there
+        assert!(!auth.is_expired(StatusCode::OK, &login, &login));
+        assert!(!auth.is_expired(StatusCode::FORBIDDEN, &provider, &provider));
+    }
+
+    #[test]
+    fn non_idempotent_requests_are_only_replayed_on_request() {
+        let auth = authenticator();
+        assert!(auth.may_replay(&Method::GET));
+        assert!(auth.may_replay(&Method::PUT));
+        assert!(auth.may_replay(&Method::DELETE));
+        assert!(!auth.may_replay(&Method::POST));
+        assert!(!auth.may_replay(&Method::PATCH));
+
+        let auth = authenticator().replay_non_idempotent(true);
+        assert!(auth.may_replay(&Method::POST));
+        assert!(auth.may_replay(&Method::PATCH));
+    }
+
+    #[tokio::test]
+    async fn providers_are_called_where_blocking_is_allowed() {
+        let caller = std::thread::current().id();
+
+        let source = CredentialSource::Provider(Arc::new(move || {
+            // blocking the thread of the (single-threaded) test runtime would be a bug
+            assert_ne!(std::thread::current().id(), caller);
+            Ok(Credentials::new("janedoe", "CorrectHorseBatteryStaple"))
+        }));
+        assert_eq!(source.get().await.unwrap().username, "janedoe");
+
+        let error = run_provider(|| -> Result<Secret, AuthError> { Err("no terminal".into()) })
+            .await
+            .unwrap_err();
+        assert!(matches!(error, OpenIDClientError::Credentials { .. }), "{:?}", error);
+
+        let error = run_provider(|| -> Result<Secret, AuthError> { panic!("provider panicked") })
+            .await
+            .unwrap_err();
+        assert!(matches!(error, OpenIDClientError::Credentials { .. }), "{:?}", error);
     }
 }
@@ -1,54 +1,329 @@
 //! This module contains a simple cookie jar implementation based on the implementation from
-//! [`reqwest::cookie::Jar`], extended with functions to persist it as a file on disk.
+//! [`reqwest::cookie::Jar`], extended with functions to persist it with a [`SessionStore`].
 
-use std::convert::From;
-use std::fs::{create_dir, read_to_string};
-use std::path::PathBuf;
-use std::sync::RwLock;
+use std::collections::HashSet;
+use std::convert::{From, Infallible};
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bytes::Bytes;
+use cookie_store::CookieExpiration;
 use reqwest::cookie::CookieStore;
 use reqwest::header::HeaderValue;
 use reqwest::Url;
 
+use crate::store::{FileStore, PermissionCheck, SessionStore};
+
+#[cfg(feature = "encryption")]
+mod crypto;
+#[cfg(feature = "encryption")]
+pub use crypto::CacheKey;
+
 /// This error describes the types of error that can occur when loading cached session cookies from
-/// disk.
+/// disk, or when managing cookie cache profiles.
 #[derive(Debug, thiserror::Error)]
-pub(crate) enum CookieCacheError {
+pub enum CookieCacheError {
     /// No on-disk cookie cache exists at the existed path yet.
     #[error("No existing cookie cache found.")]
     DoesNotExist,
-    /// An error occurred while attempting to read on-disk cookie cache.
-    #[error("Failed to read cookie cache from disk.")]
-    FileSystemError,
+    /// An error occurred while attempting to read or write the on-disk cookie cache.
+    #[error("Failed to access cookie cache {}: {error}", path.display())]
+    FileSystemError {
+        /// path of the file or directory that could not be accessed
+        path: PathBuf,
+        /// The inner error contains the I/O error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+    /// The directory for the cookie cache could not be determined (for example, because the home
+    /// directory of the current user is unknown).
+    #[error("Failed to determine the location of the cookie cache.")]
+    NoCacheDir,
     /// An error occurred while (de)serializing the cookie cache to / from JSON.
     #[error("Failed to (de)serialize cookie cache: {error}")]
     SerializationError {
+        /// The inner error contains the error passed from [`serde_json`](https://docs.rs/serde_json).
         #[from]
         error: serde_json::Error,
     },
+    /// The name of a cookie cache profile is not valid.
+    #[error("Invalid cookie cache profile name: {name:?}")]
+    InvalidProfile {
+        /// name of the invalid profile
+        name: String,
+    },
+    /// The cookie cache file can be read by other users, and was not loaded.
+    #[error("Cookie cache {} is accessible by other users (mode {mode:o})", path.display())]
+    InsecurePermissions {
+        /// path of the cookie cache file
+        path: PathBuf,
+        /// permissions of the cookie cache file
+        mode: u32,
+    },
+    /// An error occurred in a custom [`SessionStore`] implementation.
+    #[error("Failed to access session store: {error}")]
+    Store {
+        /// The inner error contains the error that was returned by the [`SessionStore`].
+        error: Box<dyn Error + Send + Sync>,
+    },
+    /// An error occurred while encrypting or decrypting the cookie cache.
+    #[error("Failed to encrypt or decrypt cookie cache: {error}")]
+    Encryption {
+        /// The inner error contains an explanation what went wrong.
+        error: String,
+    },
 }
 
-impl From<std::io::Error> for CookieCacheError {
-    fn from(_: std::io::Error) -> Self {
-        Self::FileSystemError
+impl CookieCacheError {
+    /// This method constructs a [`CookieCacheError::FileSystemError`] for the given path.
+    pub(crate) fn io(path: &Path, error: std::io::Error) -> Self {
+        CookieCacheError::FileSystemError {
+            path: path.to_path_buf(),
+            error,
+        }
     }
 }
 
-fn get_cookie_cache_dir() -> Result<PathBuf, CookieCacheError> {
-    let home = dirs::home_dir().ok_or(CookieCacheError::FileSystemError)?;
-    Ok(home.join(".fedora"))
-}
-
 /// This helper function constructs the path to the default location for the on-disk cookie cache.
 fn get_cookie_cache_path() -> Result<PathBuf, CookieCacheError> {
-    let home = dirs::home_dir().ok_or(CookieCacheError::FileSystemError)?;
+    let home = dirs::home_dir().ok_or(CookieCacheError::NoCacheDir)?;
     Ok(home.join(".fedora/fedora-rs-cookie-jar.json"))
 }
 
+/// This helper function constructs the path to the directory that contains cookie cache profiles
+/// (`$XDG_CACHE_HOME/fedora-rs`, which is `~/.cache/fedora-rs` by default).
+fn get_profile_dir() -> Result<PathBuf, CookieCacheError> {
+    let cache = dirs::cache_dir().ok_or(CookieCacheError::NoCacheDir)?;
+    Ok(cache.join("fedora-rs"))
+}
+
+/// This helper function checks that a profile name can be safely used as a file name.
+pub(crate) fn check_profile_name(name: &str) -> Result<(), CookieCacheError> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(CookieCacheError::InvalidProfile { name: name.to_string() })
+    }
+}
+
+/// This enum represents the location of the on-disk cookie cache that is used for persisting
+/// session cookies between runs.
+///
+/// Using separate profiles (for example, one per identity provider and username) prevents logins
+/// for different accounts or for production and staging instances from overwriting each other:
+///
+/// ```
+/// use fedora::CookieCache;
+///
+/// let cache = CookieCache::profile("stg-janedoe").unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum CookieCache {
+    /// the legacy default location (`~/.fedora/fedora-rs-cookie-jar.json`)
+    #[default]
+    Default,
+    /// a named profile, stored as `$XDG_CACHE_HOME/fedora-rs/<name>.json`
+    Profile {
+        /// name of the profile
+        name: String,
+    },
+    /// a custom file path
+    Path {
+        /// path of the cookie cache file
+        path: PathBuf,
+    },
+}
+
+impl CookieCache {
+    /// This method constructs a [`CookieCache`] for the named profile. Profile names can only
+    /// contain ASCII letters, digits, and the characters `-`, `_`, `.`, and `@`, and must not start
+    /// with a `.`.
+    pub fn profile(name: &str) -> Result<Self, CookieCacheError> {
+        check_profile_name(name)?;
+        Ok(CookieCache::Profile { name: name.to_string() })
+    }
+
+    /// This method constructs a [`CookieCache`] for a custom file path.
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        CookieCache::Path { path: path.into() }
+    }
+
+    /// This method returns the path of the cookie cache file.
+    pub fn file_path(&self) -> Result<PathBuf, CookieCacheError> {
+        match self {
+            CookieCache::Default => get_cookie_cache_path(),
+            CookieCache::Profile { name } => {
+                check_profile_name(name)?;
+                Ok(get_profile_dir()?.join(format!("{}.json", name)))
+            },
+            CookieCache::Path { path } => Ok(path.clone()),
+        }
+    }
+
+    /// This method deletes the cookie cache file, if it exists.
+    pub fn purge(&self) -> Result<(), CookieCacheError> {
+        FileStore::file(self.file_path()?).delete("")
+    }
+
+    /// This method returns the names of all existing cookie cache profiles, in alphabetical order.
+    pub fn list_profiles() -> Result<Vec<String>, CookieCacheError> {
+        let directory = get_profile_dir()?;
+
+        let entries = match read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(CookieCacheError::io(&directory, error)),
+        };
+
+        let mut profiles = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|error| CookieCacheError::io(&directory, error))?.path();
+
+            if path.extension().map_or(true, |extension| extension != "json") {
+                continue;
+            }
+
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                if check_profile_name(name).is_ok() {
+                    profiles.push(name.to_string());
+                }
+            }
+        }
+
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    /// This method deletes the cookie cache files of all existing profiles.
+    pub fn purge_profiles() -> Result<(), CookieCacheError> {
+        for name in CookieCache::list_profiles()? {
+            CookieCache::Profile { name }.purge()?;
+        }
+        Ok(())
+    }
+}
+
+/// This type contains information about a cookie that is stored in the cookie jar of a
+/// [`Session`](crate::Session). The value of the cookie is not included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CookieInfo {
+    /// domain the cookie is sent to
+    pub domain: String,
+    /// path the cookie is sent to
+    pub path: String,
+    /// name of the cookie
+    pub name: String,
+    /// time when the cookie expires (or `None` for session cookies, which expire when the session
+    /// ends and are not persisted)
+    pub expires: Option<SystemTime>,
+    /// whether the cookie is only sent over HTTPS
+    pub secure: bool,
+}
+
+impl CookieInfo {
+    /// This method collects the information about the given cookie.
+    fn from_cookie(cookie: &cookie_store::Cookie<'_>) -> Self {
+        let expires = match &cookie.expires {
+            CookieExpiration::AtUtc(time) => {
+                Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(time.unix_timestamp()).unwrap_or_default()))
+            },
+            CookieExpiration::SessionEnd => None,
+        };
+
+        let (domain, path, name) = cookie_key(cookie);
+
+        CookieInfo {
+            domain: domain.trim_start_matches('.').to_string(),
+            path,
+            name,
+            expires,
+            secure: cookie.secure().unwrap_or(false),
+        }
+    }
+}
+
+/// This type collects the cookie cache settings of a session builder.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CacheConfig {
+    pub(crate) location: CookieCache,
+    pub(crate) permissions: PermissionCheck,
+    pub(crate) store: Option<(Arc<dyn SessionStore>, String)>,
+    #[cfg(feature = "encryption")]
+    pub(crate) key: Option<CacheKey>,
+}
+
+impl CacheConfig {
+    /// This method returns the [`SessionStore`] and profile name that are used for persisting
+    /// cookies: either a custom store, or a [`FileStore`] for the configured [`CookieCache`].
+    fn backend(&self) -> Result<(Arc<dyn SessionStore>, String), CookieCacheError> {
+        if let Some((store, profile)) = &self.store {
+            return Ok((store.clone(), profile.clone()));
+        }
+
+        let store = FileStore::file(self.location.file_path()?).permissions(self.permissions);
+        Ok((Arc::new(store), String::new()))
+    }
+
+    /// This method turns the serialized cookie cache into a [`cookie_store::CookieStore`], and
+    /// decrypts it first if necessary.
+    fn parse(&self, contents: String) -> Result<cookie_store::CookieStore, CookieCacheError> {
+        let contents = self.unseal(contents)?;
+
+        // deserialization implementation for CookieStore skips expired cookies internally
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// This method turns the serialized cookies into the contents of the cookie cache file, and
+    /// encrypts them if a key was configured.
+    fn seal(&self, contents: String) -> Result<String, CookieCacheError> {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.key {
+            return crypto::encrypt(key, contents.as_bytes());
+        }
+
+        Ok(contents)
+    }
+
+    /// This method turns the contents of the cookie cache file into serialized cookies, and
+    /// decrypts them if they are encrypted.
+    fn unseal(&self, contents: String) -> Result<String, CookieCacheError> {
+        // unencrypted cookie caches contain a JSON array, encrypted ones contain a JSON object
+        let encrypted = contents.trim_start().starts_with('{');
+
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.key {
+            if encrypted {
+                return crypto::decrypt(key, &contents);
+            } else {
+                log::info!("Cookie cache is not encrypted yet, it will be encrypted on the next write.");
+                return Ok(contents);
+            }
+        }
+
+        if encrypted {
+            Err(CookieCacheError::Encryption {
+                error: String::from("Cookie cache is encrypted, but no key was provided."),
+            })
+        } else {
+            Ok(contents)
+        }
+    }
+}
+
 /// This function is used to parse [`HeaderValue`]s into cookies. It is based on the private
 /// `parse` method from [`reqwest::cookie::Cookie`].
-fn parse_cookie(value: &HeaderValue) -> Result<cookie::Cookie, cookie::ParseError> {
+fn parse_cookie(value: &HeaderValue) -> Result<cookie::Cookie<'_>, cookie::ParseError> {
     std::str::from_utf8(value.as_bytes())
         .map_err(cookie::ParseError::from)
         .and_then(cookie::Cookie::parse)
@@ -56,10 +331,30 @@ fn parse_cookie(value: &HeaderValue) -> Result<cookie::Cookie, cookie::ParseErro
 
 /// A simple implementation of the [`CookieStore`](reqwest::cookie::CookieStore) trait, based on
 /// the default implementation in [`reqwest::cookie::Jar`], but with additional methods for using a
-/// simple on-disk cookie cache for persistent cookies.
-#[derive(Debug)]
+/// persistent cookie cache.
 pub(crate) struct CachingJar {
     pub(crate) store: RwLock<cookie_store::CookieStore>,
+    /// cookies that were removed locally, and which need to be removed from the cookie cache
+    removed: Mutex<HashSet<CookieKey>>,
+}
+
+/// This type alias is used for the (domain, path, name) triple that identifies a cookie.
+type CookieKey = (String, String, String);
+
+/// This helper function returns the (domain, path, name) triple that identifies a cookie.
+fn cookie_key(cookie: &cookie_store::Cookie<'_>) -> CookieKey {
+    (
+        String::from(&cookie.domain),
+        String::from(&cookie.path),
+        cookie.name().to_string(),
+    )
+}
+
+impl Debug for CachingJar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // do not leak session cookies into logs
+        f.debug_struct("CachingJar").finish_non_exhaustive()
+    }
 }
 
 impl CachingJar {
@@ -67,51 +362,94 @@ impl CachingJar {
     pub fn new(store: cookie_store::CookieStore) -> CachingJar {
         CachingJar {
             store: RwLock::new(store),
+            removed: Mutex::new(HashSet::new()),
         }
     }
 
     /// Creates an empty cookie jar.
     pub fn empty() -> CachingJar {
-        CachingJar {
-            store: RwLock::new(cookie_store::CookieStore::default()),
-        }
+        CachingJar::new(cookie_store::CookieStore::default())
     }
 
-    /// Attempt to read cached persistent cookies from the on-disk cookie cache. If successful, the
-    /// return value is a new [`CachingJar`] instance that contains non-expired cookies.
-    pub fn read_from_disk() -> Result<CachingJar, CookieCacheError> {
-        let path = get_cookie_cache_path()?;
+    /// Returns information about all non-expired cookies in this jar.
+    pub fn list(&self) -> Vec<CookieInfo> {
+        let store = self.store.read().expect("Poisoned lock!");
 
-        let contents = match read_to_string(path) {
-            Ok(string) => Ok(string),
-            Err(error) => {
-                if let std::io::ErrorKind::NotFound = error.kind() {
-                    Err(CookieCacheError::DoesNotExist)
-                } else {
-                    Err(error.into())
-                }
-            },
-        }?;
+        let mut cookies: Vec<CookieInfo> = store.iter_unexpired().map(CookieInfo::from_cookie).collect();
+        cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+        cookies
+    }
 
-        // deserialization implementation for CookieStore skips expired cookies internally
-        let store: cookie_store::CookieStore = serde_json::from_str(&contents)?;
+    /// Removes all cookies for the given domain (or all cookies, if no domain is given), and
+    /// returns the number of removed cookies. The cookies are also removed from the cookie cache
+    /// the next time it is written.
+    pub fn remove(&self, domain: Option<&str>) -> usize {
+        let mut store = self.store.write().expect("Poisoned lock!");
+
+        let keys: Vec<CookieKey> = store
+            .iter_any()
+            .map(cookie_key)
+            .filter(|(cookie_domain, _, _)| match domain {
+                Some(domain) => cookie_domain.trim_start_matches('.').eq_ignore_ascii_case(domain),
+                None => true,
+            })
+            .collect();
+
+        for (domain, path, name) in &keys {
+            store.remove(domain, path, name);
+        }
 
-        Ok(CachingJar::new(store))
+        let count = keys.len();
+        self.removed.lock().expect("Poisoned lock!").extend(keys);
+        count
     }
 
-    /// Attempt to write persistent cookies to the on-disk cookie cache.
-    pub fn write_to_disk(&self) -> Result<(), CookieCacheError> {
-        let cache_dir = get_cookie_cache_dir()?;
-        let cache_path = get_cookie_cache_path()?;
+    /// Attempt to read cached persistent cookies from the configured cookie cache. If successful,
+    /// the return value is a new [`CachingJar`] instance that contains non-expired cookies.
+    pub fn read_from_store(config: &CacheConfig) -> Result<CachingJar, CookieCacheError> {
+        let (store, profile) = config.backend()?;
 
-        if !cache_dir.exists() {
-            create_dir(cache_dir)?;
-        }
+        let contents = store.load(&profile)?.ok_or(CookieCacheError::DoesNotExist)?;
+        Ok(CachingJar::new(config.parse(contents)?))
+    }
+
+    /// Attempt to write persistent cookies to the configured cookie cache.
+    ///
+    /// Cookies that were written to the cache by other processes in the meantime are merged with
+    /// the cookies from this jar (which take precedence), instead of being overwritten.
+    pub fn write_to_store(&self, config: &CacheConfig) -> Result<(), CookieCacheError> {
+        let (store, profile) = config.backend()?;
+
+        store.update(&profile, &mut |current| {
+            let stored = match current.map(|contents| config.parse(contents)) {
+                None => cookie_store::CookieStore::default(),
+                Some(Ok(stored)) => stored,
+                Some(Err(error @ CookieCacheError::SerializationError { .. })) => {
+                    log::warn!("Discarding unreadable cookie cache: {}", error);
+                    cookie_store::CookieStore::default()
+                },
+                // do not overwrite cookie caches that can not be decrypted
+                Some(Err(error)) => return Err(error),
+            };
+
+            let merged = {
+                let store = &*self.store.read().expect("Poisoned lock!");
+                let removed = &*self.removed.lock().expect("Poisoned lock!");
+
+                let cookies = stored
+                    .iter_unexpired()
+                    .filter(|cookie| !removed.contains(&cookie_key(cookie)))
+                    .chain(store.iter_unexpired())
+                    .cloned();
+                cookie_store::CookieStore::from_cookies(cookies.map(Ok::<_, Infallible>), false)
+                    .expect("Merging cookies can not fail.")
+            };
 
-        let store = &*self.store.read().expect("Poisoned lock!");
-        let contents = serde_json::to_string_pretty(store)?;
+            config.seal(serde_json::to_string_pretty(&merged)?)
+        })?;
 
-        std::fs::write(cache_path, contents)?;
+        // removed cookies are no longer present in the cookie cache
+        self.removed.lock().expect("Poisoned lock!").clear();
         Ok(())
     }
 }
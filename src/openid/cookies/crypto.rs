@@ -0,0 +1,332 @@
+//! This module contains the implementation of optional encryption for the on-disk cookie cache.
+//!
+//! Encrypted cookie caches are stored as a small JSON document that contains the parameters that
+//! are required for decryption, and the cookies encrypted with ChaCha20-Poly1305.
+
+use std::fmt::{self, Debug};
+
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::CookieCacheError;
+use crate::secret::Secret;
+
+/// version of the format of encrypted cookie caches
+const FORMAT_VERSION: u32 = 1;
+
+/// length of the random salt that is used for deriving keys from passphrases
+const SALT_LENGTH: usize = 16;
+
+/// length of the random nonce that is used for encryption
+const NONCE_LENGTH: usize = 12;
+
+/// This type represents the key that is used for encrypting the on-disk cookie cache.
+///
+/// The key can either be a raw 256-bit key, or a passphrase, which is stretched with Argon2id. Raw
+/// keys can be stored in the Secret Service (see `CacheKey::from_secret_service`, if the
+/// `secret-service` feature is also enabled), or loaded from other key stores (for example, the
+/// kernel keyring) by the application and passed to [`CacheKey::from_bytes`].
+///
+/// This type is only available if the `encryption` feature is enabled.
+///
+/// ```
+/// use fedora::CacheKey;
+///
+/// let key = CacheKey::from_passphrase("CorrectHorseBatteryStaple");
+/// assert_eq!(format!("{:?}", key), "CacheKey([REDACTED])");
+/// ```
+#[derive(Clone)]
+pub struct CacheKey {
+    source: KeySource,
+}
+
+#[derive(Clone)]
+enum KeySource {
+    Raw(Zeroizing<[u8; 32]>),
+    Passphrase(Secret),
+}
+
+impl Debug for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CacheKey([REDACTED])")
+    }
+}
+
+impl CacheKey {
+    /// This method constructs a new [`CacheKey`] from a raw 256-bit key.
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        CacheKey {
+            source: KeySource::Raw(Zeroizing::new(key)),
+        }
+    }
+
+    /// This method constructs a new [`CacheKey`] from a passphrase.
+    pub fn from_passphrase(passphrase: impl Into<Secret>) -> Self {
+        CacheKey {
+            source: KeySource::Passphrase(passphrase.into()),
+        }
+    }
+
+    /// This method loads the raw key for the cookie cache of the given profile from the default
+    /// collection of the Secret Service (for example, GNOME Keyring or KWallet). If no key has
+    /// been stored for this profile yet, a new random key is generated and stored.
+    ///
+    /// This method is only available if the `secret-service` feature is enabled.
+    #[cfg(feature = "secret-service")]
+    pub fn from_secret_service(profile: &str) -> Result<Self, CookieCacheError> {
+        use std::collections::HashMap;
+
+        use secret_service::blocking::SecretService;
+        use secret_service::EncryptionType;
+
+        fn store_error(error: secret_service::Error) -> CookieCacheError {
+            CookieCacheError::Store { error: error.into() }
+        }
+
+        // these attributes do not match the items that contain session data
+        let attributes = HashMap::from([("application", "fedora-rs"), ("cache-key", profile)]);
+
+        let service = SecretService::connect(EncryptionType::Dh).map_err(store_error)?;
+
+        // look up the stored key, unlocking it if necessary
+        let find = || -> Result<Option<Vec<u8>>, CookieCacheError> {
+            let result = service.search_items(attributes.clone()).map_err(store_error)?;
+
+            if !result.locked.is_empty() {
+                let locked: Vec<_> = result.locked.iter().collect();
+                service.unlock_all(&locked).map_err(store_error)?;
+            }
+
+            match result.unlocked.into_iter().chain(result.locked).next() {
+                Some(item) => Ok(Some(item.get_secret().map_err(store_error)?)),
+                None => Ok(None),
+            }
+        };
+
+        if let Some(stored) = find()? {
+            return CacheKey::from_stored(stored);
+        }
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        rand::thread_rng().fill_bytes(key.as_mut());
+
+        // Another process might store a key for the same profile at the same time. Replacing
+        // items with the same attributes ensures that only one key is stored, and reading the key
+        // back ensures that the key that was actually stored is used.
+        let collection = service.get_default_collection().map_err(store_error)?;
+        collection.ensure_unlocked().map_err(store_error)?;
+        collection
+            .create_item(
+                &format!("fedora-rs cookie cache key ({})", profile),
+                attributes.clone(),
+                key.as_ref(),
+                true,
+                "application/octet-stream",
+            )
+            .map_err(store_error)?;
+
+        match find()? {
+            Some(stored) => CacheKey::from_stored(stored),
+            None => Err(encryption_error("Stored cache key could not be read back.")),
+        }
+    }
+
+    /// This method constructs a new [`CacheKey`] from a raw key that was loaded from a key store,
+    /// and wipes the loaded copy.
+    #[cfg_attr(not(feature = "secret-service"), allow(dead_code))]
+    fn from_stored(mut stored: Vec<u8>) -> Result<Self, CookieCacheError> {
+        let key = <[u8; 32]>::try_from(stored.as_slice()).map(Zeroizing::new);
+        stored.zeroize();
+
+        match key {
+            Ok(key) => Ok(CacheKey {
+                source: KeySource::Raw(key),
+            }),
+            Err(_) => Err(encryption_error("Stored cache key does not have a length of 256 bits.")),
+        }
+    }
+
+    /// This method returns the key derivation function that is used for this key.
+    fn kdf(&self) -> Kdf {
+        match self.source {
+            KeySource::Raw(_) => Kdf::None,
+            KeySource::Passphrase(_) => Kdf::Argon2id,
+        }
+    }
+
+    /// This method derives the encryption key, using the given salt for passphrases.
+    fn derive(&self, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, CookieCacheError> {
+        match &self.source {
+            KeySource::Raw(key) => Ok(key.clone()),
+            KeySource::Passphrase(passphrase) => {
+                let mut key = Zeroizing::new([0u8; 32]);
+                Argon2::default()
+                    .hash_password_into(passphrase.expose().as_bytes(), salt, key.as_mut())
+                    .map_err(|error| CookieCacheError::Encryption {
+                        error: error.to_string(),
+                    })?;
+                Ok(key)
+            },
+        }
+    }
+}
+
+/// This enum represents the supported key derivation functions.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kdf {
+    None,
+    Argon2id,
+}
+
+/// This type represents the on-disk format of encrypted cookie caches.
+#[derive(Debug, Deserialize, Serialize)]
+struct EncryptedCache {
+    version: u32,
+    kdf: Kdf,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// This helper function constructs an error for encryption / decryption failures.
+fn encryption_error(error: &str) -> CookieCacheError {
+    CookieCacheError::Encryption {
+        error: error.to_string(),
+    }
+}
+
+/// This function encrypts the serialized cookie cache with the given key.
+pub(crate) fn encrypt(key: &CacheKey, plaintext: &[u8]) -> Result<String, CookieCacheError> {
+    let mut salt = [0u8; SALT_LENGTH];
+    let mut nonce = [0u8; NONCE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key.derive(&salt)?.as_ref()));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| encryption_error("Failed to encrypt cookie cache."))?;
+
+    let cache = EncryptedCache {
+        version: FORMAT_VERSION,
+        kdf: key.kdf(),
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    };
+
+    Ok(serde_json::to_string_pretty(&cache)?)
+}
+
+/// This function decrypts an encrypted cookie cache with the given key, and returns the serialized
+/// cookies.
+pub(crate) fn decrypt(key: &CacheKey, contents: &str) -> Result<String, CookieCacheError> {
+    let cache: EncryptedCache = serde_json::from_str(contents)?;
+
+    if cache.version != FORMAT_VERSION {
+        return Err(encryption_error("Unsupported cookie cache format version."));
+    }
+
+    if cache.kdf != key.kdf() {
+        return Err(encryption_error(
+            "Cookie cache was encrypted with a different kind of key.",
+        ));
+    }
+
+    let salt = STANDARD
+        .decode(cache.salt)
+        .map_err(|_| encryption_error("Invalid salt."))?;
+    let nonce = STANDARD
+        .decode(cache.nonce)
+        .map_err(|_| encryption_error("Invalid nonce."))?;
+    let ciphertext = STANDARD
+        .decode(cache.ciphertext)
+        .map_err(|_| encryption_error("Invalid ciphertext."))?;
+
+    if nonce.len() != NONCE_LENGTH {
+        return Err(encryption_error("Invalid nonce."));
+    }
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key.derive(&salt)?.as_ref()));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| encryption_error("Failed to decrypt cookie cache (wrong key or corrupted file)."))?;
+
+    String::from_utf8(plaintext).map_err(|_| encryption_error("Decrypted cookie cache is not valid UTF-8."))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const COOKIES: &str = r#"[{"raw_cookie":"session=abc; Path=/","path":["/",true],"domain":{"HostOnly":"example.com"},"expires":"SessionEnd"}]"#;
+
+    #[test]
+    fn raw_keys_round_trip() {
+        let key = CacheKey::from_bytes([7; 32]);
+
+        let sealed = encrypt(&key, COOKIES.as_bytes()).unwrap();
+        assert!(!sealed.contains("session=abc"));
+        assert_eq!(decrypt(&key, &sealed).unwrap(), COOKIES);
+
+        // every encryption uses a new nonce
+        assert_ne!(encrypt(&key, COOKIES.as_bytes()).unwrap(), sealed);
+    }
+
+    #[test]
+    fn passphrases_round_trip() {
+        let key = CacheKey::from_passphrase("CorrectHorseBatteryStaple");
+
+        let sealed = encrypt(&key, COOKIES.as_bytes()).unwrap();
+        let cache: EncryptedCache = serde_json::from_str(&sealed).unwrap();
+        assert_eq!(cache.kdf, Kdf::Argon2id);
+
+        assert_eq!(decrypt(&key, &sealed).unwrap(), COOKIES);
+    }
+
+    #[test]
+    fn wrong_keys_are_rejected() {
+        let sealed = encrypt(&CacheKey::from_bytes([7; 32]), COOKIES.as_bytes()).unwrap();
+        let error = decrypt(&CacheKey::from_bytes([8; 32]), &sealed).unwrap_err();
+        assert!(error.to_string().contains("wrong key"), "{}", error);
+
+        let sealed = encrypt(&CacheKey::from_passphrase("correct"), COOKIES.as_bytes()).unwrap();
+        let error = decrypt(&CacheKey::from_passphrase("incorrect"), &sealed).unwrap_err();
+        assert!(error.to_string().contains("wrong key"), "{}", error);
+
+        // passphrases are not used as raw keys, and the other way around
+        let error = decrypt(&CacheKey::from_bytes([7; 32]), &sealed).unwrap_err();
+        assert!(error.to_string().contains("different kind of key"), "{}", error);
+    }
+
+    #[test]
+    fn tampered_caches_are_rejected() {
+        let key = CacheKey::from_bytes([7; 32]);
+        let mut cache: EncryptedCache = serde_json::from_str(&encrypt(&key, COOKIES.as_bytes()).unwrap()).unwrap();
+
+        let mut ciphertext = STANDARD.decode(&cache.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        cache.ciphertext = STANDARD.encode(ciphertext);
+
+        let error = decrypt(&key, &serde_json::to_string(&cache).unwrap()).unwrap_err();
+        assert!(matches!(error, CookieCacheError::Encryption { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn stored_keys_must_have_the_right_length() {
+        let key = CacheKey::from_stored(vec![7; 32]).unwrap();
+        let sealed = encrypt(&key, COOKIES.as_bytes()).unwrap();
+        assert_eq!(decrypt(&CacheKey::from_bytes([7; 32]), &sealed).unwrap(), COOKIES);
+
+        assert!(CacheKey::from_stored(vec![7; 16]).is_err());
+        assert!(CacheKey::from_stored(b"CorrectHorseBatteryStaple".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! This module contains a typed client for the REST API of [Pagure](https://pagure.io), which is
+//! also used for the package repositories on [src.fedoraproject.org](https://src.fedoraproject.org)
+//! ("dist-git"). It is built on top of a [`Session`].
+//!
+//! Read-only requests can be sent with anonymous sessions. Actions that modify data need a session
+//! that is authenticated with a Pagure API token (see [`Session::with_token`] and
+//! [`ApiToken::pagure`](crate::ApiToken::pagure)). API tokens are created in the settings of a
+//! user (for all projects) or of a project (for only this project), and they are restricted to
+//! the ACLs that were selected when they were created (for example, "pull_request_merge" for
+//! merging pull requests). [`PagureClient::whoami`] can be used to check which user a token
+//! belongs to.
+//!
+//! Listings (for example, [`PagureClient::pull_requests`]) are returned as streams, which request
+//! additional pages while they are consumed:
+//!
+//! ```ignore
+//! use futures::TryStreamExt;
+//!
+//! use fedora::pagure::{PagureClient, PullRequestQuery};
+//! use fedora::{ApiToken, Session};
+//! use url::Url;
+//!
+//! let url = Url::parse("https://src.fedoraproject.org/")?;
+//! let session = Session::with_token(ApiToken::pagure("TOKEN"), &url).build()?;
+//! let pagure = PagureClient::dist_git(session);
+//!
+//! let pull_requests: Vec<_> = pagure.pull_requests("rpms/rust-fedora", &PullRequestQuery::new()).try_collect().await?;
+//! ```
+
+use futures_util::stream::{self, Stream, TryStreamExt};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+use crate::client::{api_error, fixed_url, ApiClient, ApiError, ApiPath, PathError};
+use crate::openid::excerpt;
+use crate::session::Session;
+
+mod actions;
+pub use actions::{AclTarget, NewIssue, NewPullRequest};
+
+mod query;
+pub use query::{IssueQuery, ProjectQuery, PullRequestQuery};
+
+mod types;
+pub use types::{
+    AccessLevel,
+    AccessList,
+    Collaborator,
+    Comment,
+    Contributors,
+    Issue,
+    IssueStatus,
+    Project,
+    PullRequest,
+    PullRequestStatus,
+    User,
+};
+
+/// base URL of pagure.io
+const PAGURE_IO_URL: &str = "https://pagure.io/";
+/// base URL of the production instance of dist-git
+const FEDORA_DIST_GIT_URL: &str = "https://src.fedoraproject.org/";
+/// base URL of the staging instance of dist-git
+const FEDORA_DIST_GIT_STG_URL: &str = "https://src.stg.fedoraproject.org/";
+
+/// Pagure error code for invalid or expired API tokens
+const INVALID_TOKEN: &str = "EINVALIDTOK";
+/// Pagure error code for insufficient permissions
+const NOT_HIGH_ENOUGH: &str = "ENOTHIGHENOUGH";
+
+api_error! {
+    service: "Pagure",
+    /// This collection of errors is returned for failures when sending requests to Pagure.
+    pub enum PagureError {
+        Status {
+            /// error code from the response (for example, "EINVALIDTOK"), if any
+            code: Option<String>,
+        },
+        /// This error represents an issue with constructing the URL of an API endpoint.
+        #[error("Failed to construct URL: {error}")]
+        UrlParsing {
+            /// The inner error contains the error that occurred when parsing the URL.
+            #[from]
+            error: url::ParseError,
+        },
+        /// This error is returned when a project name or another name can not be used in the path
+        /// of an API endpoint (because it is empty, or because it contains "." or ".." components).
+        #[error("Invalid name in API path: {name:?}")]
+        InvalidName {
+            /// name (or component of a project name) that was rejected
+            name: String,
+        },
+    }
+}
+
+impl ApiError for PagureError {
+    fn from_response(status: StatusCode, body: &str) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorResponse {
+            error: String,
+            error_code: Option<String>,
+            errors: Option<serde_json::Value>,
+        }
+
+        match serde_json::from_str::<ErrorResponse>(body) {
+            Ok(response) => {
+                // validation errors of form fields are returned separately
+                let message = match response.errors {
+                    Some(errors) if !errors.is_null() => format!("{} ({})", response.error, errors),
+                    _ => response.error,
+                };
+
+                PagureError::Status {
+                    status,
+                    code: response.error_code,
+                    message,
+                }
+            },
+            Err(_) => PagureError::Status {
+                status,
+                code: None,
+                message: excerpt(body),
+            },
+        }
+    }
+
+    /// Pagure also rejects invalid API tokens and missing ACLs with error codes.
+    fn is_service_auth_failure(&self) -> bool {
+        matches!(
+            self,
+            PagureError::Status { code: Some(code), .. } if code == INVALID_TOKEN || code == NOT_HIGH_ENOUGH
+        )
+    }
+}
+
+impl From<PathError> for PagureError {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::Base(error) => PagureError::UrlParsing { error },
+            PathError::Segment(name) => PagureError::InvalidName { name },
+        }
+    }
+}
+
+/// This helper function returns the path segments of an API endpoint of the given project. The
+/// full name of the project (for example, "fork/janedoe/rpms/rust-fedora") is split at slashes,
+/// and every component is encoded separately.
+fn project_path<'a>(project: &'a str, segments: &[&'a str]) -> Vec<&'a str> {
+    project.split('/').chain(segments.iter().copied()).collect()
+}
+
+/// This type is a typed client for the Pagure REST API.
+///
+/// All requests are sent with [`Session::send`], so they are authenticated by the [`Session`] (if
+/// it is authenticated), and they are retried and rate-limited according to its settings.
+///
+/// Projects are identified by their full name, including namespace (for example,
+/// "rpms/rust-fedora") and, for forks, the owner of the fork (for example,
+/// "fork/janedoe/rpms/rust-fedora").
+#[derive(Debug)]
+pub struct PagureClient {
+    api: ApiClient<PagureError>,
+}
+
+impl PagureClient {
+    /// This method constructs a new [`PagureClient`] for [pagure.io](https://pagure.io).
+    pub fn pagure_io(session: Session) -> Self {
+        PagureClient::with_url(session, fixed_url(PAGURE_IO_URL))
+    }
+
+    /// This method constructs a new [`PagureClient`] for the production instance of dist-git.
+    pub fn dist_git(session: Session) -> Self {
+        PagureClient::with_url(session, fixed_url(FEDORA_DIST_GIT_URL))
+    }
+
+    /// This method constructs a new [`PagureClient`] for the staging instance of dist-git.
+    pub fn dist_git_staging(session: Session) -> Self {
+        PagureClient::with_url(session, fixed_url(FEDORA_DIST_GIT_STG_URL))
+    }
+
+    /// This method constructs a new [`PagureClient`] for the instance of Pagure at the given URL.
+    pub fn with_url(session: Session, url: Url) -> Self {
+        PagureClient {
+            api: ApiClient::rest(session, url, &["api", "0"]),
+        }
+    }
+
+    /// This method returns a reference to the wrapped [`Session`].
+    pub fn session(&self) -> &Session {
+        self.api.session()
+    }
+
+    /// This method returns the base URL of the Pagure instance.
+    pub fn url(&self) -> &Url {
+        self.api.url()
+    }
+
+    /// This method returns a stream of the results of a paginated listing, where the results are
+    /// stored under the given key. Pages are requested while the stream is consumed.
+    fn paginate<'a, T>(
+        &'a self,
+        path: Vec<String>,
+        params: Vec<(&'static str, String)>,
+        key: &'static str,
+    ) -> impl Stream<Item = Result<T, PagureError>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        #[derive(Deserialize)]
+        struct Pagination {
+            page: u32,
+            pages: u32,
+        }
+
+        let pages = stream::try_unfold(Some(1), move |page| {
+            let path = path.clone();
+            let mut params = params.clone();
+
+            async move {
+                let page: u32 = match page {
+                    Some(page) => page,
+                    None => return Ok::<_, PagureError>(None),
+                };
+                params.push(("page", page.to_string()));
+
+                let segments: Vec<&str> = path.iter().map(String::as_str).collect();
+                let mut value: serde_json::Value = self.api.get(&ApiPath::file(&segments), &params).await?;
+
+                let items: Vec<T> = match value.get_mut(key) {
+                    Some(items) => serde_json::from_value(items.take())?,
+                    None => Vec::new(),
+                };
+                let pagination: Option<Pagination> = match value.get_mut("pagination") {
+                    Some(pagination) => serde_json::from_value(pagination.take())?,
+                    None => None,
+                };
+
+                log::debug!("Received page {} of Pagure listing {}.", page, path.join("/"));
+
+                let next = match pagination {
+                    Some(pagination) if pagination.page < pagination.pages && !items.is_empty() => {
+                        Some(pagination.page + 1)
+                    },
+                    _ => None,
+                };
+
+                Ok(Some((items, next)))
+            }
+        });
+
+        pages
+            .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// This method returns the project with the given full name (or `None` if it does not exist).
+    pub async fn project(&self, project: &str) -> Result<Option<Project>, PagureError> {
+        self.api
+            .get_optional(&ApiPath::file(&project_path(project, &[])), &[])
+            .await
+    }
+
+    /// This method returns a stream of the projects that match the query.
+    pub fn projects<'a>(&'a self, query: &ProjectQuery<'_>) -> impl Stream<Item = Result<Project, PagureError>> + 'a {
+        self.paginate(vec![String::from("projects")], query.params(), "projects")
+    }
+
+    /// This method returns the pull request with the given ID in the given project (or `None` if
+    /// it does not exist).
+    pub async fn pull_request(&self, project: &str, id: u64) -> Result<Option<PullRequest>, PagureError> {
+        let id = id.to_string();
+        self.api
+            .get_optional(&ApiPath::file(&project_path(project, &["pull-request", &id])), &[])
+            .await
+    }
+
+    /// This method returns a stream of the pull requests of the given project that match the query.
+    pub fn pull_requests<'a>(
+        &'a self,
+        project: &str,
+        query: &PullRequestQuery<'_>,
+    ) -> impl Stream<Item = Result<PullRequest, PagureError>> + 'a {
+        let path = project_path(project, &["pull-requests"])
+            .into_iter()
+            .map(String::from)
+            .collect();
+        self.paginate(path, query.params(), "requests")
+    }
+
+    /// This method returns the issue with the given ID in the given project (or `None` if it does
+    /// not exist).
+    pub async fn issue(&self, project: &str, id: u64) -> Result<Option<Issue>, PagureError> {
+        let id = id.to_string();
+        self.api
+            .get_optional(&ApiPath::file(&project_path(project, &["issue", &id])), &[])
+            .await
+    }
+
+    /// This method returns a stream of the issues of the given project that match the query.
+    pub fn issues<'a>(
+        &'a self,
+        project: &str,
+        query: &IssueQuery<'_>,
+    ) -> impl Stream<Item = Result<Issue, PagureError>> + 'a {
+        let path = project_path(project, &["issues"])
+            .into_iter()
+            .map(String::from)
+            .collect();
+        self.paginate(path, query.params(), "issues")
+    }
+
+    /// This method returns the users and groups with access to the given project, by access
+    /// level.
+    pub async fn contributors(&self, project: &str) -> Result<Contributors, PagureError> {
+        self.api
+            .get(&ApiPath::file(&project_path(project, &["contributors"])), &[])
+            .await
+    }
+}
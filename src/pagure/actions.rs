@@ -0,0 +1,327 @@
+//! This module contains the authenticated actions of the Pagure REST API (opening, commenting on,
+//! and merging pull requests, creating and commenting on issues, and modifying ACLs).
+//!
+//! All actions need a session that is authenticated with an API token that grants the
+//! corresponding ACL (for example, "pull_request_create", "pull_request_comment",
+//! "pull_request_merge", "issue_create", "issue_comment", or "modify_project").
+
+use serde::Deserialize;
+
+use super::types::{AccessLevel, Issue, PullRequest};
+use super::{project_path, PagureClient, PagureError};
+use crate::client::{ApiPath, Body};
+
+/// This enum represents the user or group whose access to a project is modified.
+#[derive(Clone, Copy, Debug)]
+pub enum AclTarget<'a> {
+    /// user with the given name
+    User(&'a str),
+    /// group with the given name
+    Group(&'a str),
+}
+
+/// This type contains the data for opening a new pull request.
+///
+/// ```
+/// use fedora::pagure::NewPullRequest;
+///
+/// let pull_request = NewPullRequest::new("Update to version 2.2.0", "rawhide", "update")
+///     .fork("janedoe")
+///     .initial_comment("This also fixes the build on i686.");
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct NewPullRequest<'a> {
+    title: &'a str,
+    branch_to: &'a str,
+    branch_from: &'a str,
+    initial_comment: Option<&'a str>,
+    fork_owner: Option<&'a str>,
+}
+
+impl<'a> NewPullRequest<'a> {
+    /// This method constructs a new [`NewPullRequest`] with the given title, for merging the
+    /// source branch into the target branch.
+    pub fn new(title: &'a str, branch_to: &'a str, branch_from: &'a str) -> Self {
+        NewPullRequest {
+            title,
+            branch_to,
+            branch_from,
+            initial_comment: None,
+            fork_owner: None,
+        }
+    }
+
+    /// Set the description of the pull request.
+    pub fn initial_comment(mut self, initial_comment: &'a str) -> Self {
+        self.initial_comment = Some(initial_comment);
+        self
+    }
+
+    /// Open the pull request from the fork of the project that is owned by the given user (instead
+    /// of from a branch of the project itself).
+    pub fn fork(mut self, owner: &'a str) -> Self {
+        self.fork_owner = Some(owner);
+        self
+    }
+}
+
+/// This type contains the data for creating a new issue.
+///
+/// ```
+/// use fedora::pagure::NewIssue;
+///
+/// let issue =
+///     NewIssue::new("Package fails to build", "The build fails with Rust 1.80.").tag("ftbfs");
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct NewIssue<'a> {
+    title: &'a str,
+    content: &'a str,
+    tags: Vec<&'a str>,
+    assignee: Option<&'a str>,
+    milestone: Option<&'a str>,
+    private: bool,
+}
+
+impl<'a> NewIssue<'a> {
+    /// This method constructs a new [`NewIssue`] with the given title and description.
+    pub fn new(title: &'a str, content: &'a str) -> Self {
+        NewIssue {
+            title,
+            content,
+            tags: Vec::new(),
+            assignee: None,
+            milestone: None,
+            private: false,
+        }
+    }
+
+    /// Add the given tag to the issue.
+    pub fn tag(mut self, tag: &'a str) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Assign the issue to the given user.
+    pub fn assignee(mut self, assignee: &'a str) -> Self {
+        self.assignee = Some(assignee);
+        self
+    }
+
+    /// Add the issue to the given milestone.
+    pub fn milestone(mut self, milestone: &'a str) -> Self {
+        self.milestone = Some(milestone);
+        self
+    }
+
+    /// Only make the issue visible to the project maintainers.
+    pub fn private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+}
+
+/// This type represents the response to actions that only return a message.
+#[derive(Deserialize)]
+struct MessageResponse {
+    message: String,
+}
+
+impl PagureClient {
+    /// This method returns the name of the user that the API token of the session belongs to.
+    pub async fn whoami(&self) -> Result<String, PagureError> {
+        #[derive(Deserialize)]
+        struct WhoamiResponse {
+            username: String,
+        }
+
+        let response: WhoamiResponse = self.api.post(&ApiPath::file(&["-", "whoami"]), Body::Empty).await?;
+        Ok(response.username)
+    }
+
+    /// This method opens a new pull request against the given project.
+    pub async fn open_pull_request(
+        &self,
+        project: &str,
+        pull_request: &NewPullRequest<'_>,
+    ) -> Result<PullRequest, PagureError> {
+        let mut form = vec![
+            ("title", pull_request.title.to_string()),
+            ("branch_to", pull_request.branch_to.to_string()),
+            ("branch_from", pull_request.branch_from.to_string()),
+        ];
+
+        if let Some(initial_comment) = pull_request.initial_comment {
+            form.push(("initial_comment", initial_comment.to_string()));
+        }
+
+        if let Some(owner) = pull_request.fork_owner {
+            // the fork has the same name (and namespace) as the target project
+            let (namespace, name) = match project.rsplit_once('/') {
+                Some((namespace, name)) => (Some(namespace), name),
+                None => (None, project),
+            };
+
+            form.push(("repo_from", name.to_string()));
+            form.push(("repo_from_username", owner.to_string()));
+            if let Some(namespace) = namespace {
+                form.push(("repo_from_namespace", namespace.to_string()));
+            }
+        }
+
+        self.api
+            .post(
+                &ApiPath::file(&project_path(project, &["pull-request", "new"])),
+                Body::Form(&form),
+            )
+            .await
+    }
+
+    /// This method posts a comment on the pull request with the given ID, and returns the
+    /// confirmation message.
+    pub async fn comment_on_pull_request(&self, project: &str, id: u64, comment: &str) -> Result<String, PagureError> {
+        let form = [("comment", comment.to_string())];
+
+        let id = id.to_string();
+
+        let response: MessageResponse = self
+            .api
+            .post(
+                &ApiPath::file(&project_path(project, &["pull-request", &id, "comment"])),
+                Body::Form(&form),
+            )
+            .await?;
+        Ok(response.message)
+    }
+
+    /// This method merges the pull request with the given ID, and returns the confirmation
+    /// message.
+    pub async fn merge_pull_request(&self, project: &str, id: u64) -> Result<String, PagureError> {
+        let id = id.to_string();
+
+        let response: MessageResponse = self
+            .api
+            .post(
+                &ApiPath::file(&project_path(project, &["pull-request", &id, "merge"])),
+                Body::Form(&[]),
+            )
+            .await?;
+        Ok(response.message)
+    }
+
+    /// This method creates a new issue in the given project.
+    pub async fn create_issue(&self, project: &str, issue: &NewIssue<'_>) -> Result<Issue, PagureError> {
+        #[derive(Deserialize)]
+        struct IssueResponse {
+            issue: Issue,
+        }
+
+        let mut form = vec![
+            ("title", issue.title.to_string()),
+            ("issue_content", issue.content.to_string()),
+            ("private", issue.private.to_string()),
+        ];
+
+        if !issue.tags.is_empty() {
+            form.push(("tag", issue.tags.join(",")));
+        }
+        if let Some(assignee) = issue.assignee {
+            form.push(("assignee", assignee.to_string()));
+        }
+        if let Some(milestone) = issue.milestone {
+            form.push(("milestone", milestone.to_string()));
+        }
+
+        let response: IssueResponse = self
+            .api
+            .post(
+                &ApiPath::file(&project_path(project, &["new_issue"])),
+                Body::Form(&form),
+            )
+            .await?;
+        Ok(response.issue)
+    }
+
+    /// This method posts a comment on the issue with the given ID, and returns the confirmation
+    /// message.
+    pub async fn comment_on_issue(&self, project: &str, id: u64, comment: &str) -> Result<String, PagureError> {
+        let form = [("comment", comment.to_string())];
+
+        let id = id.to_string();
+
+        let response: MessageResponse = self
+            .api
+            .post(
+                &ApiPath::file(&project_path(project, &["issue", &id, "comment"])),
+                Body::Form(&form),
+            )
+            .await?;
+        Ok(response.message)
+    }
+
+    /// This method grants the given access level on the given project to a user or group.
+    ///
+    /// Collaborators only have commit access to some branches, so they need to be added with
+    /// [`PagureClient::set_collaborator`] instead.
+    pub async fn set_acl(&self, project: &str, target: AclTarget<'_>, level: AccessLevel) -> Result<(), PagureError> {
+        let acl = match level {
+            AccessLevel::Ticket => "ticket",
+            AccessLevel::Collaborator => "collaborator",
+            AccessLevel::Commit => "commit",
+            AccessLevel::Admin => "admin",
+        };
+
+        self.modify_acl(project, target, acl, None).await
+    }
+
+    /// This method grants commit access to the branches that match the given patterns (separated
+    /// by commas, for example, "epel*,f4*") on the given project to a user or group.
+    pub async fn set_collaborator(
+        &self,
+        project: &str,
+        target: AclTarget<'_>,
+        branches: &str,
+    ) -> Result<(), PagureError> {
+        self.modify_acl(project, target, "collaborator", Some(branches)).await
+    }
+
+    /// This method removes all access of a user or group to the given project.
+    pub async fn remove_acl(&self, project: &str, target: AclTarget<'_>) -> Result<(), PagureError> {
+        self.modify_acl(project, target, "", None).await
+    }
+
+    /// This method modifies the access of a user or group to the given project (an empty ACL
+    /// removes access).
+    async fn modify_acl(
+        &self,
+        project: &str,
+        target: AclTarget<'_>,
+        acl: &str,
+        branches: Option<&str>,
+    ) -> Result<(), PagureError> {
+        let (user_type, name) = match target {
+            AclTarget::User(name) => ("user", name),
+            AclTarget::Group(name) => ("group", name),
+        };
+
+        let mut form = vec![
+            ("user_type", user_type.to_string()),
+            ("name", name.to_string()),
+            ("acl", acl.to_string()),
+        ];
+        if let Some(branches) = branches {
+            form.push(("branches", branches.to_string()));
+        }
+
+        let _: serde_json::Value = self
+            .api
+            .post(
+                &ApiPath::file(&project_path(project, &["git", "modifyacls"])),
+                Body::Form(&form),
+            )
+            .await?;
+        Ok(())
+    }
+}
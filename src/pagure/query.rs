@@ -0,0 +1,313 @@
+//! This module contains the query builders for listing projects, pull requests, and issues.
+//!
+//! Results are returned as streams that request additional pages while they are consumed (see
+//! [`PagureClient::projects`](super::PagureClient::projects)).
+
+use super::types::{IssueStatus, PullRequestStatus};
+
+/// This helper function returns the string representation of a pull request status that is used
+/// in queries.
+fn pull_request_status(status: PullRequestStatus) -> &'static str {
+    match status {
+        PullRequestStatus::Open => "Open",
+        PullRequestStatus::Closed => "Closed",
+        PullRequestStatus::Merged => "Merged",
+        PullRequestStatus::Unknown => "All",
+    }
+}
+
+/// This type represents a query for projects.
+///
+/// ```
+/// use fedora::pagure::ProjectQuery;
+///
+/// let query = ProjectQuery::new().namespace("rpms").pattern("rust-*");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct ProjectQuery<'a> {
+    namespace: Option<&'a str>,
+    pattern: Option<&'a str>,
+    owner: Option<&'a str>,
+    username: Option<&'a str>,
+    tags: Vec<&'a str>,
+    fork: Option<bool>,
+    per_page: Option<u32>,
+}
+
+impl<'a> ProjectQuery<'a> {
+    /// This method constructs a new [`ProjectQuery`] without any filters.
+    pub fn new() -> Self {
+        ProjectQuery::default()
+    }
+
+    /// Only return projects in the given namespace (for example, "rpms").
+    pub fn namespace(mut self, namespace: &'a str) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Only return projects whose name matches the given pattern (with `*` as wildcard).
+    pub fn pattern(mut self, pattern: &'a str) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Only return projects that are owned by the given user.
+    pub fn owner(mut self, owner: &'a str) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Only return projects that the given user has access to.
+    pub fn username(mut self, username: &'a str) -> Self {
+        self.username = Some(username);
+        self
+    }
+
+    /// Only return projects with the given tag.
+    pub fn tag(mut self, tag: &'a str) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Only return forks (or only return projects that are not forks).
+    pub fn fork(mut self, fork: bool) -> Self {
+        self.fork = Some(fork);
+        self
+    }
+
+    /// Override the number of results per page (at most 100).
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    /// This method returns the query parameters of the query.
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+
+        if let Some(namespace) = self.namespace {
+            params.push(("namespace", namespace.to_string()));
+        }
+        if let Some(pattern) = self.pattern {
+            params.push(("pattern", pattern.to_string()));
+        }
+        if let Some(owner) = self.owner {
+            params.push(("owner", owner.to_string()));
+        }
+        if let Some(username) = self.username {
+            params.push(("username", username.to_string()));
+        }
+        params.extend(self.tags.iter().map(|tag| ("tags", tag.to_string())));
+        if let Some(fork) = self.fork {
+            params.push(("fork", fork.to_string()));
+        }
+        if let Some(per_page) = self.per_page {
+            params.push(("per_page", per_page.to_string()));
+        }
+
+        params
+    }
+}
+
+/// This type represents a query for the pull requests of a project.
+///
+/// ```
+/// use fedora::pagure::{PullRequestQuery, PullRequestStatus};
+///
+/// let query = PullRequestQuery::new()
+///     .status(PullRequestStatus::Merged)
+///     .author("janedoe");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct PullRequestQuery<'a> {
+    status: Option<PullRequestStatus>,
+    all: bool,
+    assignee: Option<&'a str>,
+    author: Option<&'a str>,
+    tags: Vec<&'a str>,
+    per_page: Option<u32>,
+}
+
+impl<'a> PullRequestQuery<'a> {
+    /// This method constructs a new [`PullRequestQuery`], which returns open pull requests.
+    pub fn new() -> Self {
+        PullRequestQuery::default()
+    }
+
+    /// Only return pull requests with the given status (default: open).
+    pub fn status(mut self, status: PullRequestStatus) -> Self {
+        self.status = Some(status);
+        self.all = false;
+        self
+    }
+
+    /// Return pull requests regardless of their status.
+    pub fn all(mut self) -> Self {
+        self.status = None;
+        self.all = true;
+        self
+    }
+
+    /// Only return pull requests that are assigned to the given user.
+    pub fn assignee(mut self, assignee: &'a str) -> Self {
+        self.assignee = Some(assignee);
+        self
+    }
+
+    /// Only return pull requests that were opened by the given user.
+    pub fn author(mut self, author: &'a str) -> Self {
+        self.author = Some(author);
+        self
+    }
+
+    /// Only return pull requests with the given tag.
+    pub fn tag(mut self, tag: &'a str) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Override the number of results per page (at most 100).
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    /// This method returns the query parameters of the query.
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+
+        if self.all {
+            params.push(("status", String::from("All")));
+        } else if let Some(status) = self.status {
+            params.push(("status", pull_request_status(status).to_string()));
+        }
+        if let Some(assignee) = self.assignee {
+            params.push(("assignee", assignee.to_string()));
+        }
+        if let Some(author) = self.author {
+            params.push(("author", author.to_string()));
+        }
+        params.extend(self.tags.iter().map(|tag| ("tags", tag.to_string())));
+        if let Some(per_page) = self.per_page {
+            params.push(("per_page", per_page.to_string()));
+        }
+
+        params
+    }
+}
+
+/// This type represents a query for the issues of a project.
+///
+/// ```
+/// use fedora::pagure::{IssueQuery, IssueStatus};
+///
+/// let query = IssueQuery::new().status(IssueStatus::Closed).tag("easyfix");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct IssueQuery<'a> {
+    status: Option<IssueStatus>,
+    all: bool,
+    assignee: Option<&'a str>,
+    author: Option<&'a str>,
+    tags: Vec<&'a str>,
+    milestones: Vec<&'a str>,
+    since: Option<&'a str>,
+    per_page: Option<u32>,
+}
+
+impl<'a> IssueQuery<'a> {
+    /// This method constructs a new [`IssueQuery`], which returns open issues.
+    pub fn new() -> Self {
+        IssueQuery::default()
+    }
+
+    /// Only return issues with the given status (default: open).
+    pub fn status(mut self, status: IssueStatus) -> Self {
+        self.status = Some(status);
+        self.all = false;
+        self
+    }
+
+    /// Return issues regardless of their status.
+    pub fn all(mut self) -> Self {
+        self.status = None;
+        self.all = true;
+        self
+    }
+
+    /// Only return issues that are assigned to the given user.
+    pub fn assignee(mut self, assignee: &'a str) -> Self {
+        self.assignee = Some(assignee);
+        self
+    }
+
+    /// Only return issues that were opened by the given user.
+    pub fn author(mut self, author: &'a str) -> Self {
+        self.author = Some(author);
+        self
+    }
+
+    /// Only return issues with the given tag.
+    pub fn tag(mut self, tag: &'a str) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Only return issues in the given milestone.
+    pub fn milestone(mut self, milestone: &'a str) -> Self {
+        self.milestones.push(milestone);
+        self
+    }
+
+    /// Only return issues that were updated after the given time (a UNIX timestamp or a date in
+    /// the `YYYY-MM-DD` format).
+    pub fn since(mut self, since: &'a str) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Override the number of results per page (at most 100).
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    /// This method returns the query parameters of the query.
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+
+        if self.all {
+            params.push(("status", String::from("all")));
+        } else if let Some(status) = self.status {
+            let status = match status {
+                IssueStatus::Closed => "Closed",
+                _ => "Open",
+            };
+            params.push(("status", status.to_string()));
+        }
+        if let Some(assignee) = self.assignee {
+            params.push(("assignee", assignee.to_string()));
+        }
+        if let Some(author) = self.author {
+            params.push(("author", author.to_string()));
+        }
+        params.extend(self.tags.iter().map(|tag| ("tags", tag.to_string())));
+        params.extend(
+            self.milestones
+                .iter()
+                .map(|milestone| ("milestones", milestone.to_string())),
+        );
+        if let Some(since) = self.since {
+            params.push(("since", since.to_string()));
+        }
+        if let Some(per_page) = self.per_page {
+            params.push(("per_page", per_page.to_string()));
+        }
+
+        params
+    }
+}
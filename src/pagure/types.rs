@@ -0,0 +1,258 @@
+//! This module contains the data types that are returned by the Pagure REST API.
+//!
+//! All types keep attributes that are not captured by known fields in a catch-all map, so no data
+//! is lost if Pagure adds new attributes. Timestamps are kept as returned by Pagure (seconds since
+//! the UNIX epoch, as strings).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum represents the status of a pull request.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub enum PullRequestStatus {
+    /// pull request is open
+    Open,
+    /// pull request was closed without merging
+    Closed,
+    /// pull request was merged
+    Merged,
+    /// status that is not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
+/// This enum represents the status of an issue.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub enum IssueStatus {
+    /// issue is open
+    Open,
+    /// issue is closed
+    Closed,
+    /// status that is not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
+/// This enum represents the access levels of users and groups on a project.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLevel {
+    /// access to the issue tracker
+    Ticket,
+    /// commit access to some branches
+    Collaborator,
+    /// commit access to all branches
+    Commit,
+    /// full access, including project settings
+    Admin,
+}
+
+/// This type represents a user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct User {
+    /// name of the user
+    pub name: String,
+    /// full name of the user
+    pub fullname: Option<String>,
+    /// URL of the user page
+    pub full_url: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a project (for example, a dist-git repository like "rpms/rust-fedora").
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Project {
+    /// ID of the project
+    pub id: u64,
+    /// name of the project
+    pub name: String,
+    /// namespace of the project (for example, "rpms"), if any
+    pub namespace: Option<String>,
+    /// full name of the project, including namespace and fork owner
+    pub fullname: String,
+    /// description of the project
+    #[serde(default)]
+    pub description: String,
+    /// URL of the project page
+    pub full_url: Option<String>,
+    /// owner of the project
+    pub user: Option<User>,
+    /// users with access to the project, keyed by access level ("owner", "admin", "commit",
+    /// "collaborator", and "ticket")
+    #[serde(default)]
+    pub access_users: HashMap<String, Vec<String>>,
+    /// groups with access to the project, keyed by access level
+    #[serde(default)]
+    pub access_groups: HashMap<String, Vec<String>>,
+    /// tags of the project
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// time when the project was created
+    pub date_created: Option<String>,
+    /// time when the project was last modified
+    pub date_modified: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a comment on a pull request or issue.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Comment {
+    /// ID of the comment
+    pub id: u64,
+    /// text of the comment
+    pub comment: String,
+    /// author of the comment
+    pub user: Option<User>,
+    /// time when the comment was posted
+    pub date_created: Option<String>,
+    /// time when the comment was last edited, if it was edited
+    pub edited_on: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a pull request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct PullRequest {
+    /// ID of the pull request (unique within the project)
+    pub id: u64,
+    /// globally unique ID of the pull request
+    pub uid: Option<String>,
+    /// title of the pull request
+    pub title: String,
+    /// description of the pull request
+    pub initial_comment: Option<String>,
+    /// status of the pull request
+    pub status: PullRequestStatus,
+    /// target branch
+    pub branch: String,
+    /// source branch
+    pub branch_from: String,
+    /// user who opened the pull request
+    pub user: Option<User>,
+    /// user who is assigned to the pull request, if any
+    pub assignee: Option<User>,
+    /// target project
+    pub project: Option<Project>,
+    /// source project (if the pull request was opened from a fork)
+    pub repo_from: Option<Project>,
+    /// first commit of the pull request
+    pub commit_start: Option<String>,
+    /// last commit of the pull request
+    pub commit_stop: Option<String>,
+    /// comments on the pull request
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    /// tags of the pull request
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// time when the pull request was opened
+    pub date_created: Option<String>,
+    /// time when the pull request was last updated
+    pub last_updated: Option<String>,
+    /// time when the pull request was closed or merged, if any
+    pub closed_at: Option<String>,
+    /// URL of the pull request page
+    pub full_url: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents an issue.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Issue {
+    /// ID of the issue (unique within the project)
+    pub id: u64,
+    /// title of the issue
+    pub title: String,
+    /// description of the issue
+    #[serde(default)]
+    pub content: String,
+    /// status of the issue
+    pub status: IssueStatus,
+    /// reason why the issue was closed, if it is closed
+    pub close_status: Option<String>,
+    /// user who opened the issue
+    pub user: Option<User>,
+    /// user who is assigned to the issue, if any
+    pub assignee: Option<User>,
+    /// whether the issue is private
+    #[serde(default)]
+    pub private: bool,
+    /// comments on the issue
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    /// tags of the issue
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// time when the issue was opened
+    pub date_created: Option<String>,
+    /// time when the issue was last updated
+    pub last_updated: Option<String>,
+    /// URL of the issue page
+    pub full_url: Option<String>,
+
+    /// This catch-all map contains all attributes that are not captured by the known fields.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// This type represents a user or group with commit access to some branches of a project.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Collaborator {
+    /// name of the user or group
+    #[serde(alias = "user", alias = "group")]
+    pub name: String,
+    /// branches the user or group has commit access to (glob patterns, separated by commas)
+    pub branches: String,
+}
+
+/// This type contains the users or groups with access to a project, by access level.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct AccessList {
+    /// users or groups with admin access
+    #[serde(default)]
+    pub admin: Vec<String>,
+    /// users or groups with commit access to all branches
+    #[serde(default)]
+    pub commit: Vec<String>,
+    /// users or groups with commit access to some branches
+    #[serde(default)]
+    pub collaborators: Vec<Collaborator>,
+    /// users or groups with access to the issue tracker
+    #[serde(default)]
+    pub ticket: Vec<String>,
+}
+
+/// This type represents the contributors of a project (as returned by the `contributors`
+/// endpoint).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Contributors {
+    /// users with access to the project
+    #[serde(default)]
+    pub users: AccessList,
+    /// groups with access to the project
+    #[serde(default)]
+    pub groups: AccessList,
+}
@@ -0,0 +1,280 @@
+//! This module contains the definition of the [`RateLimiter`] type, which limits the rate of
+//! requests that are sent to each host.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// default request rates for known Fedora services: (host, requests per second, burst size)
+const FEDORA_RATES: &[(&str, f64, u32)] = &[
+    ("bodhi.fedoraproject.org", 5.0, 10),
+    ("koji.fedoraproject.org", 10.0, 20),
+    ("mdapi.fedoraproject.org", 10.0, 20),
+    ("src.fedoraproject.org", 5.0, 10),
+    ("pagure.io", 5.0, 10),
+    ("copr.fedorainfracloud.org", 5.0, 10),
+    ("accounts.fedoraproject.org", 2.0, 5),
+    ("fasjson.fedoraproject.org", 5.0, 10),
+];
+
+/// This error is returned when constructing a [`Rate`] with a request rate that is not a positive,
+/// finite number.
+#[derive(Debug, thiserror::Error)]
+#[error("Request rate must be a positive, finite number, got {per_second}.")]
+pub struct InvalidRate {
+    /// the rejected number of requests per second
+    pub per_second: f64,
+}
+
+/// This type describes the maximum rate of requests to a host.
+///
+/// Requests are limited with a token bucket: Up to `burst` requests can be sent at once, and the
+/// bucket is refilled at `per_second` requests per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rate {
+    per_second: f64,
+    burst: u32,
+}
+
+impl Rate {
+    /// This method constructs a new [`Rate`] that allows the given number of requests per second,
+    /// with bursts of up to `burst` requests (at least one).
+    ///
+    /// It returns an error if `per_second` is zero, negative, infinite, or NaN.
+    ///
+    /// ```
+    /// use fedora::Rate;
+    ///
+    /// assert!(Rate::new(0.5, 1).is_ok());
+    /// assert!(Rate::new(0.0, 1).is_err());
+    /// assert!(Rate::new(f64::NAN, 1).is_err());
+    /// ```
+    pub fn new(per_second: f64, burst: u32) -> Result<Self, InvalidRate> {
+        if !(per_second.is_finite() && per_second > 0.0) {
+            return Err(InvalidRate { per_second });
+        }
+
+        Ok(Rate {
+            per_second,
+            burst: burst.max(1),
+        })
+    }
+}
+
+/// This type represents the state of the token bucket for one host.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// This type contains the configured limits of a [`RateLimiter`].
+#[derive(Clone, Debug, Default)]
+struct Limits {
+    default: Option<Rate>,
+    rates: HashMap<String, Rate>,
+}
+
+/// This type limits the rate of requests that are sent to each host, with a separate token bucket
+/// per host.
+///
+/// [`RateLimiter::new`] contains default limits for known Fedora services (for example, Bodhi,
+/// Koji, and mdapi), and does not limit requests to other hosts. Clones of a [`RateLimiter`] share
+/// their token buckets, so one limiter can be passed to multiple sessions (for example, with
+/// [`AnonymousSessionBuilder::rate_limiter`](crate::AnonymousSessionBuilder::rate_limiter)) to
+/// limit the total rate of requests of a process.
+///
+/// The limits can not be changed after a limiter was cloned: [`RateLimiter::host`] and
+/// [`RateLimiter::default_rate`] return a new limiter with its own token buckets, and do not
+/// affect existing clones.
+///
+/// ```
+/// use fedora::{Rate, RateLimiter, Session};
+///
+/// let limiter = RateLimiter::new()
+///     .host("bodhi.fedoraproject.org", Rate::new(2.0, 5).unwrap())
+///     .default_rate(Rate::new(20.0, 20).unwrap());
+///
+/// let anonymous = Session::anonymous().rate_limiter(limiter.clone()).build();
+/// let other = Session::anonymous().rate_limiter(limiter).build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    limits: Arc<Limits>,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        let rates = FEDORA_RATES
+            .iter()
+            .map(|(host, per_second, burst)| {
+                let rate = Rate::new(*per_second, *burst).expect("Invalid hardcoded request rate.");
+                (host.to_string(), rate)
+            })
+            .collect();
+
+        RateLimiter::with_limits(Limits { default: None, rates })
+    }
+}
+
+impl RateLimiter {
+    /// This method constructs a new [`RateLimiter`] with default limits for known Fedora services.
+    pub fn new() -> Self {
+        RateLimiter::default()
+    }
+
+    /// This method constructs a new [`RateLimiter`] without any limits (limits can be added with
+    /// [`RateLimiter::host`] and [`RateLimiter::default_rate`]).
+    pub fn empty() -> Self {
+        RateLimiter::with_limits(Limits::default())
+    }
+
+    fn with_limits(limits: Limits) -> Self {
+        RateLimiter {
+            limits: Arc::new(limits),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Override the limit for requests to the given host.
+    #[must_use]
+    pub fn host(self, host: &str, rate: Rate) -> Self {
+        let mut limits = Limits::clone(&self.limits);
+        limits.rates.insert(host.to_string(), rate);
+        RateLimiter::with_limits(limits)
+    }
+
+    /// Limit requests to hosts without a specific limit to the given rate.
+    #[must_use]
+    pub fn default_rate(self, rate: Rate) -> Self {
+        let mut limits = Limits::clone(&self.limits);
+        limits.default = Some(rate);
+        RateLimiter::with_limits(limits)
+    }
+
+    /// This method waits until a request to the given host can be sent.
+    pub(crate) async fn acquire(&self, host: &str) {
+        loop {
+            let wait = match self.try_acquire(host) {
+                Some(wait) => wait,
+                None => return,
+            };
+
+            log::debug!("Rate limit for {} reached, waiting for {:?}.", host, wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// This method takes a token from the bucket of the given host, if possible. Otherwise, it
+    /// returns the duration until the next token is available.
+    fn try_acquire(&self, host: &str) -> Option<Duration> {
+        let rate = match self.limits.rates.get(host).or(self.limits.default.as_ref()) {
+            Some(rate) => *rate,
+            None => return None,
+        };
+
+        let mut buckets = self.buckets.lock().expect("Poisoned lock!");
+
+        let now = Instant::now();
+        let bucket = buckets.entry(host.to_string()).or_insert(Bucket {
+            tokens: f64::from(rate.burst),
+            updated: now,
+        });
+
+        // refill the bucket according to the time that has passed since the last request
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate.per_second).min(f64::from(rate.burst));
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate.per_second))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// This helper function acquires a token for the given host, and returns how long that took.
+    async fn timed_acquire(limiter: &RateLimiter, host: &str) -> Duration {
+        let start = Instant::now();
+        limiter.acquire(host).await;
+        start.elapsed()
+    }
+
+    #[test]
+    fn invalid_rates_are_rejected() {
+        for per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(Rate::new(per_second, 1).is_err(), "{}", per_second);
+        }
+        assert_eq!(Rate::new(1.0, 0).unwrap().burst, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bursts_are_allowed() {
+        let limiter = RateLimiter::empty().host("example.com", Rate::new(1.0, 3).unwrap());
+
+        for _ in 0..3 {
+            assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::ZERO);
+        }
+
+        // the bucket is empty, and refilled with one token per second
+        assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::from_secs(1));
+        assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn buckets_are_refilled() {
+        let limiter = RateLimiter::empty().host("example.com", Rate::new(2.0, 2).unwrap());
+
+        limiter.acquire("example.com").await;
+        limiter.acquire("example.com").await;
+
+        // the bucket is never filled above the burst size
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        for _ in 0..2 {
+            assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::ZERO);
+        }
+        assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hosts_are_limited_separately() {
+        let limiter = RateLimiter::empty().default_rate(Rate::new(1.0, 1).unwrap());
+
+        assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::ZERO);
+        assert_eq!(timed_acquire(&limiter, "example.org").await, Duration::ZERO);
+        assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hosts_without_limits_are_not_limited() {
+        let limiter = RateLimiter::new();
+
+        for _ in 0..100 {
+            assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::ZERO);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clones_share_buckets() {
+        let limiter = RateLimiter::empty().host("example.com", Rate::new(1.0, 1).unwrap());
+        let clone = limiter.clone();
+
+        assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::ZERO);
+        assert_eq!(timed_acquire(&clone, "example.com").await, Duration::from_secs(1));
+
+        // changing the limits does not affect existing clones
+        let changed = clone.host("example.com", Rate::new(10.0, 10).unwrap());
+        assert_eq!(timed_acquire(&changed, "example.com").await, Duration::ZERO);
+        assert_eq!(timed_acquire(&limiter, "example.com").await, Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,152 @@
+//! This module contains the definition of the [`RetryPolicy`] type, which controls how requests
+//! that failed due to temporary errors are retried.
+
+use std::time::{Duration, SystemTime};
+
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Method, Response};
+
+/// default maximum number of attempts (including the first one)
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// default delay before the first retry
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// default upper limit for delays between attempts
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// This type describes how requests that are sent with [`Session::send`](crate::Session::send) or
+/// [`Session::execute`](crate::Session::execute) are retried if they failed due to temporary errors
+/// (timeouts, connection failures, and "408 Request Timeout", "429 Too Many Requests", and
+/// "5xx" responses).
+///
+/// The delay between attempts grows exponentially (with random jitter), starting at the initial
+/// backoff and capped at the maximum backoff. If the server sends a `Retry-After` header, the
+/// requested delay is used instead, unless it exceeds the maximum backoff, in which case the
+/// response is returned without retrying.
+///
+/// By default, only requests with idempotent methods (`GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT`,
+/// and `DELETE`) are retried.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use fedora::{RetryPolicy, Session};
+///
+/// let session = Session::anonymous()
+///     .retry(
+///         RetryPolicy::new()
+///             .max_attempts(5)
+///             .max_backoff(Duration::from_secs(60)),
+///     )
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    all_methods: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            all_methods: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// This method constructs a new [`RetryPolicy`] with default settings (3 attempts, with
+    /// delays starting at 500 milliseconds, and at most 30 seconds).
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Override the maximum number of attempts (including the first one).
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Override the delay before the first retry.
+    #[must_use]
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Override the upper limit for delays between attempts.
+    #[must_use]
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Also retry requests with non-idempotent methods (for example, `POST`). This is only safe if
+    /// the server does not act on requests that fail.
+    #[must_use]
+    pub fn all_methods(mut self, all_methods: bool) -> Self {
+        self.all_methods = all_methods;
+        self
+    }
+
+    /// This method returns the maximum number of attempts.
+    pub(crate) fn attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// This method checks whether requests with the given method can be retried.
+    pub(crate) fn allows(&self, method: &Method) -> bool {
+        self.all_methods || is_idempotent(method)
+    }
+
+    /// This method returns the delay before the next attempt, given the number of attempts so far
+    /// and the failed response (if any). It returns `None` if the server requested a delay that is
+    /// longer than the maximum backoff.
+    pub(crate) fn delay(&self, attempt: u32, response: Option<&Response>) -> Option<Duration> {
+        if let Some(retry_after) = response.and_then(retry_after) {
+            return if retry_after <= self.max_backoff {
+                Some(retry_after)
+            } else {
+                None
+            };
+        }
+
+        // exponential backoff: initial * 2^(attempt - 1), capped at the maximum
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self.initial_backoff.saturating_mul(1 << exponent).min(self.max_backoff);
+
+        // "equal jitter": wait at least half of the backoff, and a random fraction of the rest
+        let half = backoff / 2;
+        let jitter = rand::thread_rng().gen_range(0.0..=1.0);
+        Some(half + half.mul_f64(jitter))
+    }
+}
+
+/// This helper function checks whether requests with the given method are idempotent.
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+/// This helper function parses the `Retry-After` header of a response (either a number of seconds,
+/// or an HTTP date).
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+}
@@ -0,0 +1,59 @@
+//! This module contains a simple wrapper type for secrets (passwords, tokens), which prevents them
+//! from being accidentally leaked via [`Debug`] output or logs, and clears them from memory when
+//! they are no longer used.
+
+use std::fmt::{self, Debug};
+
+use serde::Deserialize;
+use zeroize::Zeroize;
+
+/// This type wraps a secret string (for example, an API token or a password). Its [`Debug`]
+/// implementation does not print the wrapped value, and the value is overwritten with zeroes when
+/// it is dropped.
+///
+/// ```
+/// use fedora::Secret;
+///
+/// let secret = Secret::from("CorrectHorseBatteryStaple");
+/// assert_eq!(format!("{:?}", secret), "Secret([REDACTED])");
+/// assert_eq!(secret.expose(), "CorrectHorseBatteryStaple");
+/// ```
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// This method constructs a new [`Secret`] from the given string.
+    pub fn new(secret: String) -> Self {
+        Secret(secret)
+    }
+
+    /// This method returns the wrapped secret value.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret::new(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Secret::new(secret.to_string())
+    }
+}
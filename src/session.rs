@@ -1,21 +1,153 @@
 //! This module contains the definition of the [`Session`] type, and associated methods for building
 //! anonymous or authenticated sessions.
 
-use reqwest::Client;
+use std::sync::Arc;
+
+use reqwest::{Client, Request, RequestBuilder, Response, StatusCode};
 use url::Url;
 
 use crate::anonymous::AnonymousSessionBuilder;
-use crate::openid::{OpenIDSessionBuilder, OpenIDSessionKind};
+use crate::auth::{AuthError, Authenticator, SessionBuilder};
+use crate::oidc::{OIDCClientError, OIDCSessionBuilder, OIDCSessionKind};
+use crate::openid::{
+    CacheConfig,
+    CachingJar,
+    CookieCacheError,
+    CookieInfo,
+    OpenIDClientError,
+    OpenIDSessionBuilder,
+    OpenIDSessionKind,
+};
+use crate::ratelimit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::tls::TlsError;
+use crate::token::{ApiToken, TokenSessionBuilder};
+
+#[cfg(feature = "gssapi")]
+use crate::kerberos::KerberosSessionBuilder;
+
+/// This collection of errors is returned for failures when sending requests with a [`Session`].
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    /// This error represents a network-related issue that occurred within [`reqwest`].
+    #[error("Failed to send request: {error}")]
+    Request {
+        /// The inner error contains the error passed from [`reqwest`](https://docs.rs/reqwest).
+        #[from]
+        error: reqwest::Error,
+    },
+    /// This error is returned when authenticating a request failed (for example, because an
+    /// expired access token could not be refreshed).
+    #[error("Failed to authenticate: {error}")]
+    Authentication {
+        /// The inner error contains the error that was returned by the [`Authenticator`].
+        error: AuthError,
+    },
+    /// This error is returned when session cookies could not be written to the cookie cache.
+    #[error("Failed to persist session cookies: {error}")]
+    CookieCache {
+        /// The inner error contains the error that occurred when writing to the cookie cache.
+        #[from]
+        error: CookieCacheError,
+    },
+    /// This error is returned when the TLS backend rejected the configured client certificate or
+    /// CA certificates.
+    #[error("Failed to initialize TLS: {error}")]
+    Tls {
+        /// The inner error contains the error that was returned by the TLS backend.
+        #[from]
+        error: TlsError,
+    },
+}
+
+impl SessionError {
+    /// This method returns `true` if the error is likely temporary (for example, a timeout or a
+    /// connection failure), and the request can be retried later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SessionError::Request { error } => is_transient_error(error),
+            SessionError::Authentication { error } => {
+                if let Some(error) = error.downcast_ref::<OpenIDClientError>() {
+                    error.is_retryable()
+                } else if let Some(error) = error.downcast_ref::<OIDCClientError>() {
+                    error.is_retryable()
+                } else if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+                    is_transient_error(error)
+                } else {
+                    false
+                }
+            },
+            SessionError::CookieCache { .. } | SessionError::Tls { .. } => false,
+        }
+    }
+
+    /// This method returns `true` if the error indicates that the credentials of the session were
+    /// rejected, or could not be refreshed.
+    pub fn is_auth_failure(&self) -> bool {
+        match self {
+            SessionError::Request { .. } | SessionError::CookieCache { .. } | SessionError::Tls { .. } => false,
+            SessionError::Authentication { error } => {
+                if let Some(error) = error.downcast_ref::<OpenIDClientError>() {
+                    error.is_auth_failure()
+                } else if let Some(error) = error.downcast_ref::<OIDCClientError>() {
+                    error.is_auth_failure()
+                } else {
+                    !self.is_retryable()
+                }
+            },
+        }
+    }
+}
+
+/// This helper function checks whether an HTTP status code indicates a temporary failure.
+pub(crate) fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// This helper function checks whether an error from [`reqwest`] indicates a temporary failure.
+pub(crate) fn is_transient_error(error: &reqwest::Error) -> bool {
+    if error.is_timeout() || error.is_connect() {
+        return true;
+    }
 
-#[derive(Debug)]
-/// This type is a thin newtype wrapper around [`reqwest::Client`] with implementations for
-/// constructing both a generic / unauthenticated session, and a session pre-authenticated via
-/// an OpenID provider.
+    match error.status() {
+        Some(status) => is_transient_status(status),
+        None => false,
+    }
+}
+
+#[derive(Clone, Debug)]
+/// This type is a thin wrapper around [`reqwest::Client`] with implementations for constructing
+/// both a generic / unauthenticated session, and a session pre-authenticated via an OpenID or
+/// OpenID Connect provider, or with any other [`Authenticator`].
+///
+/// Sessions are cheap to clone: clones share the connection pool, cookies, credentials, and rate
+/// limiter of the original session, so one session can be passed to multiple API clients.
 pub struct Session {
     pub(crate) client: Client,
+    pub(crate) auth: Option<Arc<dyn Authenticator>>,
+    pub(crate) cookies: Option<Arc<CachingJar>>,
+    pub(crate) cookie_cache: Option<CacheConfig>,
+    pub(crate) logout_url: Option<Url>,
+    pub(crate) retry: Option<RetryPolicy>,
+    pub(crate) rate_limiter: Option<RateLimiter>,
 }
 
 impl Session {
+    /// This method constructs a new [`Session`] that wraps the given client without any additional
+    /// authentication.
+    pub(crate) fn from_client(client: Client) -> Self {
+        Session {
+            client,
+            auth: None,
+            cookies: None,
+            cookie_cache: None,
+            logout_url: None,
+            retry: None,
+            rate_limiter: None,
+        }
+    }
+
     /// This method returns a reference to the wrapped [`reqwest::Client`]:
     ///
     /// ```
@@ -23,10 +155,229 @@ impl Session {
     /// let session = Session::anonymous().build();
     /// let client: &reqwest::Client = session.session();
     /// ```
+    ///
+    /// Note that requests which are sent directly with the wrapped client bypass the
+    /// [`Authenticator`] of the session. For sessions that are authenticated via OpenID Connect,
+    /// they carry the initial access token, which is never refreshed. For sessions that are
+    /// authenticated with an [`ApiToken`], they carry no token at all. Use [`Session::send`] or
+    /// [`Session::execute`] for authenticated requests.
     pub fn session(&self) -> &Client {
         &self.client
     }
 
+    /// This method builds the request from the given [`RequestBuilder`] and sends it with
+    /// [`Session::execute`].
+    ///
+    /// ```ignore
+    /// # use fedora::Session;
+    /// let session = Session::anonymous().build();
+    /// let response = session.send(session.session().get("https://bodhi.fedoraproject.org/releases/")).await?;
+    /// ```
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, SessionError> {
+        self.execute(request.build()?).await
+    }
+
+    /// This method sends a request with the wrapped [`reqwest::Client`].
+    ///
+    /// For authenticated sessions, the [`Authenticator`] of the session adds credentials to the
+    /// request (for example, the current OIDC access token, which is refreshed ahead of time if it
+    /// is about to expire). If the server rejects the request (for example, with
+    /// "401 Unauthorized"), the [`Authenticator`] is given the chance to update the credentials
+    /// (for example, by refreshing the access token, by answering a `Negotiate` challenge, or by
+    /// logging in again), and the request is retried once.
+    ///
+    /// If the session was built with a [`RetryPolicy`], requests that failed due to temporary
+    /// errors (for example, "503 Service Unavailable" responses during deployments) are retried
+    /// with exponential backoff. If the session was built with a [`RateLimiter`], every attempt
+    /// waits until the rate limit for the host allows sending another request.
+    pub async fn execute(&self, request: Request) -> Result<Response, SessionError> {
+        let policy = match &self.retry {
+            Some(policy) if policy.allows(request.method()) => policy,
+            _ => return self.execute_once(request).await,
+        };
+
+        let mut attempt = 1;
+        loop {
+            // requests with streaming bodies can not be retried
+            let copy = match request.try_clone() {
+                Some(copy) if attempt < policy.attempts() => copy,
+                _ => return self.execute_once(request).await,
+            };
+
+            let result = self.execute_once(copy).await;
+
+            let delay = match &result {
+                Ok(response) if is_transient_status(response.status()) => policy.delay(attempt, Some(response)),
+                Err(error) if error.is_retryable() => policy.delay(attempt, None),
+                _ => return result,
+            };
+
+            let delay = match delay {
+                Some(delay) => delay,
+                None => return result,
+            };
+
+            match &result {
+                Ok(response) => log::info!(
+                    "Request to {} failed with {}, retrying in {:?}.",
+                    request.url(),
+                    response.status(),
+                    delay
+                ),
+                Err(error) => log::info!(
+                    "Request to {} failed ({}), retrying in {:?}.",
+                    request.url(),
+                    error,
+                    delay
+                ),
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// This method sends a request once (or twice, if the credentials were rejected and could be
+    /// updated by the [`Authenticator`]).
+    async fn execute_once(&self, mut request: Request) -> Result<Response, SessionError> {
+        let auth = match &self.auth {
+            Some(auth) => auth,
+            None => return Ok(self.send_raw(request).await?),
+        };
+
+        auth.authorize(&mut request)
+            .await
+            .map_err(|error| SessionError::Authentication { error })?;
+
+        // requests with streaming bodies can not be retried
+        let retry = request.try_clone();
+        let response = self.send_raw(request).await?;
+
+        let mut retry = match retry {
+            Some(retry) if auth.is_rejected(&response) => retry,
+            _ => return Ok(response),
+        };
+
+        let recovered = auth
+            .recover(&mut retry, &response)
+            .await
+            .map_err(|error| SessionError::Authentication { error })?;
+
+        if recovered {
+            // keep cookies from re-authenticating
+            if let Err(error) = self.persist() {
+                log::error!("Failed to write cookie jar to disk: {}", error);
+            }
+
+            log::debug!("Retrying request with updated credentials.");
+            Ok(self.send_raw(retry).await?)
+        } else {
+            Ok(response)
+        }
+    }
+
+    /// This method sends a request with the wrapped [`reqwest::Client`], after waiting for the
+    /// rate limiter of the session (if any).
+    async fn send_raw(&self, request: Request) -> Result<Response, reqwest::Error> {
+        if let (Some(rate_limiter), Some(host)) = (&self.rate_limiter, request.url().host_str()) {
+            rate_limiter.acquire(host).await;
+        }
+
+        self.client.execute(request).await
+    }
+
+    /// This method returns information about all cookies of this session, sorted by domain, path,
+    /// and name.
+    ///
+    /// Note: Only cookies of sessions that were built with [`Session::openid_auth`] or
+    /// [`Session::builder`] can be inspected. For other sessions, this method returns an empty
+    /// list.
+    ///
+    /// ```
+    /// # use fedora::Session;
+    /// let session = Session::anonymous().build();
+    /// assert!(session.cookies().is_empty());
+    /// ```
+    pub fn cookies(&self) -> Vec<CookieInfo> {
+        match &self.cookies {
+            Some(jar) => jar.list(),
+            None => Vec::new(),
+        }
+    }
+
+    /// This method removes all cookies for the given domain from this session, and returns the
+    /// number of removed cookies. The cookies are removed from the cookie cache when it is written
+    /// the next time (for example, with [`Session::persist`]).
+    pub fn remove_cookies(&self, domain: &str) -> usize {
+        match &self.cookies {
+            Some(jar) => jar.remove(Some(domain)),
+            None => 0,
+        }
+    }
+
+    /// This method writes the persistent cookies of this session to its cookie cache (or
+    /// [`SessionStore`](crate::SessionStore)). It does nothing for sessions without a cookie cache.
+    pub fn persist(&self) -> Result<(), SessionError> {
+        if let (Some(jar), Some(cookie_cache)) = (&self.cookies, &self.cookie_cache) {
+            jar.write_to_store(cookie_cache)?;
+        }
+        Ok(())
+    }
+
+    /// This method logs out of this session:
+    ///
+    /// - the [`Authenticator`] of the session is asked to invalidate its credentials (for example,
+    ///   OIDC tokens are revoked if the provider supports it),
+    /// - the logout URL is visited, if one was configured (for example, with
+    ///   [`OpenIDSessionBuilder::logout_url`]),
+    /// - all cookies of this session are removed, both from the session and from its cookie cache.
+    ///
+    /// Local state is cleared even if logging out on the server failed.
+    ///
+    /// ```ignore
+    /// # use fedora::Session;
+    /// session.logout().await?;
+    /// ```
+    pub async fn logout(&self) -> Result<(), SessionError> {
+        let remote = self.logout_remote().await;
+
+        if let Some(jar) = &self.cookies {
+            let removed = jar.remove(None);
+            log::debug!("Removed {} cookie(s).", removed);
+        }
+
+        self.persist()?;
+        remote
+    }
+
+    /// This method invalidates the credentials of this session on the server.
+    async fn logout_remote(&self) -> Result<(), SessionError> {
+        if let Some(auth) = &self.auth {
+            auth.logout(&self.client)
+                .await
+                .map_err(|error| SessionError::Authentication { error })?;
+        }
+
+        if let Some(logout_url) = &self.logout_url {
+            self.client.get(logout_url.clone()).send().await?.error_for_status()?;
+        }
+
+        Ok(())
+    }
+
+    /// This method returns a new builder for a session that is authenticated with the given
+    /// [`Authenticator`].
+    ///
+    /// ```
+    /// # use fedora::Session;
+    /// use fedora::{Anonymous, SessionBuilder};
+    ///
+    /// let builder: SessionBuilder = Session::builder(Anonymous);
+    /// ```
+    pub fn builder<'a, A: Authenticator + 'static>(authenticator: A) -> SessionBuilder<'a> {
+        SessionBuilder::new(authenticator)
+    }
+
     /// This method returns a new builder for an anonymous session.
     ///
     /// ```
@@ -52,4 +403,56 @@ impl Session {
     pub fn openid_auth<'a>(login_url: Url, kind: OpenIDSessionKind) -> OpenIDSessionBuilder<'a> {
         OpenIDSessionBuilder::new(login_url, kind)
     }
+
+    /// This method returns a new builder for a session that will need to be authenticated via an
+    /// OpenID Connect provider.
+    ///
+    /// The access token is only sent to the service at the given URL.
+    ///
+    /// ```
+    /// # use fedora::Session;
+    /// use fedora::{OIDCSessionBuilder, OIDCSessionKind};
+    /// use url::Url;
+    ///
+    /// let builder: OIDCSessionBuilder = Session::oidc_auth(
+    ///     OIDCSessionKind::Default,
+    ///     "my-client-id",
+    ///     &Url::parse("https://bodhi.fedoraproject.org/").unwrap(),
+    /// );
+    /// ```
+    pub fn oidc_auth<'a>(kind: OIDCSessionKind, client_id: &'a str, service: &Url) -> OIDCSessionBuilder<'a> {
+        OIDCSessionBuilder::new(kind, client_id, service)
+    }
+
+    /// This method returns a new builder for a session that is authenticated with a static API
+    /// token, which is only sent to the service at the given URL.
+    ///
+    /// ```
+    /// # use fedora::Session;
+    /// use fedora::ApiToken;
+    /// use url::Url;
+    ///
+    /// let url = Url::parse("https://pagure.io/").unwrap();
+    /// let session: Session = Session::with_token(ApiToken::pagure("MY-PAGURE-TOKEN"), &url)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn with_token<'a>(token: ApiToken, url: &Url) -> TokenSessionBuilder<'a> {
+        TokenSessionBuilder::new(token, url)
+    }
+
+    /// This method returns a new builder for a session that is authenticated with Kerberos tickets
+    /// (for example, obtained with `fkinit`).
+    ///
+    /// This method is only available if the `gssapi` feature is enabled.
+    ///
+    /// ```ignore
+    /// use fedora::Session;
+    ///
+    /// let session: Session = Session::kerberos().build().unwrap();
+    /// ```
+    #[cfg(feature = "gssapi")]
+    pub fn kerberos<'a>() -> KerberosSessionBuilder<'a> {
+        KerberosSessionBuilder::new()
+    }
 }
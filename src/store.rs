@@ -0,0 +1,609 @@
+//! This module contains the definition of the [`SessionStore`] trait, which is used for persisting
+//! session cookies between runs, and implementations that store them in files, in memory, or in
+//! the Secret Service.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::{self, Debug};
+use std::fs::{read_to_string, remove_file, rename, DirBuilder, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use fs2::FileExt;
+
+use crate::openid::CookieCacheError;
+
+#[cfg(feature = "secret-service")]
+mod secret_service;
+#[cfg(feature = "secret-service")]
+pub use self::secret_service::SecretServiceStore;
+
+/// This trait is implemented by all storage backends for session cookies. Session data is stored
+/// as an opaque string per profile (for example, one profile per user of a web service).
+///
+/// Implementations for storing session data in files ([`FileStore`]), in memory ([`MemoryStore`]),
+/// and in the Secret Service (`SecretServiceStore`, if the `secret-service` feature is enabled)
+/// are provided, but the trait can also be implemented by other crates (for example, to store
+/// session data in a database):
+///
+/// ```
+/// use fedora::{CookieCacheError, SessionStore};
+///
+/// #[derive(Debug)]
+/// struct Database;
+///
+/// impl SessionStore for Database {
+///     fn load(&self, profile: &str) -> Result<Option<String>, CookieCacheError> {
+///         // SELECT contents FROM sessions WHERE profile = ?
+///         Ok(None)
+///     }
+///
+///     fn save(&self, profile: &str, contents: &str) -> Result<(), CookieCacheError> {
+///         // INSERT OR REPLACE INTO sessions VALUES (?, ?)
+///         Ok(())
+///     }
+///
+///     fn delete(&self, profile: &str) -> Result<(), CookieCacheError> {
+///         // DELETE FROM sessions WHERE profile = ?
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait SessionStore: Debug + Send + Sync {
+    /// This method returns the stored session data for the given profile, or `None` if there is
+    /// no stored session data for this profile yet.
+    fn load(&self, profile: &str) -> Result<Option<String>, CookieCacheError>;
+
+    /// This method stores the session data for the given profile, replacing any existing data.
+    fn save(&self, profile: &str, contents: &str) -> Result<(), CookieCacheError>;
+
+    /// This method deletes the stored session data for the given profile, if there is any.
+    fn delete(&self, profile: &str) -> Result<(), CookieCacheError>;
+
+    /// This method replaces the stored session data for the given profile with the result of the
+    /// given function, which is called with the currently stored session data.
+    ///
+    /// The default implementation calls [`SessionStore::load`] and [`SessionStore::save`].
+    /// Implementations should override it if they can guarantee that no concurrent updates are
+    /// lost between these two steps.
+    fn update(
+        &self,
+        profile: &str,
+        update: &mut dyn FnMut(Option<String>) -> Result<String, CookieCacheError>,
+    ) -> Result<(), CookieCacheError> {
+        let contents = update(self.load(profile)?)?;
+        self.save(profile, &contents)
+    }
+}
+
+impl<T: SessionStore + ?Sized> SessionStore for Arc<T> {
+    fn load(&self, profile: &str) -> Result<Option<String>, CookieCacheError> {
+        (**self).load(profile)
+    }
+
+    fn save(&self, profile: &str, contents: &str) -> Result<(), CookieCacheError> {
+        (**self).save(profile, contents)
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), CookieCacheError> {
+        (**self).delete(profile)
+    }
+
+    fn update(
+        &self,
+        profile: &str,
+        update: &mut dyn FnMut(Option<String>) -> Result<String, CookieCacheError>,
+    ) -> Result<(), CookieCacheError> {
+        (**self).update(profile, update)
+    }
+}
+
+/// This type implements [`SessionStore`] by keeping session data in memory. It can be shared
+/// between sessions by wrapping it in an [`Arc`].
+#[derive(Default)]
+pub struct MemoryStore {
+    profiles: Mutex<HashMap<String, String>>,
+}
+
+impl MemoryStore {
+    /// This method constructs a new, empty [`MemoryStore`].
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+}
+
+impl Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // do not leak session cookies into logs
+        let profiles = self.profiles.lock().expect("Poisoned lock!");
+        f.debug_struct("MemoryStore")
+            .field("profiles", &profiles.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, profile: &str) -> Result<Option<String>, CookieCacheError> {
+        Ok(self.profiles.lock().expect("Poisoned lock!").get(profile).cloned())
+    }
+
+    fn save(&self, profile: &str, contents: &str) -> Result<(), CookieCacheError> {
+        self.profiles
+            .lock()
+            .expect("Poisoned lock!")
+            .insert(profile.to_string(), contents.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), CookieCacheError> {
+        self.profiles.lock().expect("Poisoned lock!").remove(profile);
+        Ok(())
+    }
+
+    fn update(
+        &self,
+        profile: &str,
+        update: &mut dyn FnMut(Option<String>) -> Result<String, CookieCacheError>,
+    ) -> Result<(), CookieCacheError> {
+        let mut profiles = self.profiles.lock().expect("Poisoned lock!");
+        let contents = update(profiles.get(profile).cloned())?;
+        profiles.insert(profile.to_string(), contents);
+        Ok(())
+    }
+}
+
+/// This enum determines how existing cookie cache files that are accessible by other users are
+/// handled (permissions are only checked on Unix-like systems).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PermissionCheck {
+    /// refuse to load the cookie cache (it is replaced with a private file on the next write,
+    /// without merging its contents)
+    #[default]
+    Refuse,
+    /// load the cookie cache anyway, but log a warning
+    Warn,
+}
+
+/// This enum represents where a [`FileStore`] stores session data.
+#[derive(Clone, Debug)]
+enum FileLocation {
+    /// one file per profile in this directory
+    Directory(PathBuf),
+    /// one file for all profiles (used for [`CookieCache`](crate::CookieCache) locations)
+    File(PathBuf),
+}
+
+/// This type implements [`SessionStore`] by storing session data for every profile in a separate
+/// JSON file in a directory.
+///
+/// Files are protected with advisory file locks against concurrent access from multiple processes,
+/// they are replaced atomically, and they are created so that they are only accessible by the
+/// current user.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    location: FileLocation,
+    permissions: PermissionCheck,
+}
+
+impl FileStore {
+    /// This method constructs a new [`FileStore`] that stores session data for every profile as
+    /// `<directory>/<profile>.json`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        FileStore {
+            location: FileLocation::Directory(directory.into()),
+            permissions: PermissionCheck::default(),
+        }
+    }
+
+    /// This method constructs a new [`FileStore`] that stores session data in a single file,
+    /// regardless of the profile.
+    pub(crate) fn file(path: PathBuf) -> Self {
+        FileStore {
+            location: FileLocation::File(path),
+            permissions: PermissionCheck::default(),
+        }
+    }
+
+    /// Override how existing files that are accessible by other users are handled (by default,
+    /// they are not loaded).
+    #[must_use]
+    pub fn permissions(mut self, check: PermissionCheck) -> Self {
+        self.permissions = check;
+        self
+    }
+
+    /// This method returns the path of the file that contains the session data for the given
+    /// profile.
+    fn path(&self, profile: &str) -> Result<PathBuf, CookieCacheError> {
+        match &self.location {
+            FileLocation::Directory(directory) => {
+                crate::openid::check_profile_name(profile)?;
+                Ok(directory.join(format!("{}.json", profile)))
+            },
+            FileLocation::File(path) => Ok(path.clone()),
+        }
+    }
+
+    /// This method returns the path of the file for the given profile, and creates its parent
+    /// directory if it does not exist yet.
+    fn prepare(&self, profile: &str) -> Result<PathBuf, CookieCacheError> {
+        let path = self.path(profile)?;
+
+        if let Some(directory) = path.parent() {
+            if !directory.exists() {
+                create_private_dir(directory).map_err(|error| CookieCacheError::io(directory, error))?;
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+impl SessionStore for FileStore {
+    fn load(&self, profile: &str) -> Result<Option<String>, CookieCacheError> {
+        let path = self.path(profile)?;
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        // hold a shared lock while reading, so concurrent writers can not interfere
+        let lock = open_lock_file(&path)?;
+        FileExt::lock_shared(&lock).map_err(|error| CookieCacheError::io(&get_lock_path(&path), error))?;
+
+        check_permissions(&path, self.permissions)?;
+        read_file(&path)
+    }
+
+    fn save(&self, profile: &str, contents: &str) -> Result<(), CookieCacheError> {
+        let path = self.prepare(profile)?;
+
+        let lock = open_lock_file(&path)?;
+        FileExt::lock_exclusive(&lock).map_err(|error| CookieCacheError::io(&get_lock_path(&path), error))?;
+
+        write_atomic(&path, contents.as_bytes())
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), CookieCacheError> {
+        let path = self.path(profile)?;
+
+        if !path.exists() {
+            return Ok(());
+        }
+
+        // the lock file is left in place: other processes might be waiting for the lock, and
+        // removing the file would let the next process lock a different file
+        let lock = open_lock_file(&path)?;
+        FileExt::lock_exclusive(&lock).map_err(|error| CookieCacheError::io(&get_lock_path(&path), error))?;
+
+        match remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(CookieCacheError::io(&path, error)),
+        }
+    }
+
+    fn update(
+        &self,
+        profile: &str,
+        update: &mut dyn FnMut(Option<String>) -> Result<String, CookieCacheError>,
+    ) -> Result<(), CookieCacheError> {
+        let path = self.prepare(profile)?;
+
+        // hold an exclusive lock for the whole read / modify / write cycle
+        let lock = open_lock_file(&path)?;
+        FileExt::lock_exclusive(&lock).map_err(|error| CookieCacheError::io(&get_lock_path(&path), error))?;
+
+        // contents of files that are accessible by other users must not be merged into the new
+        // contents, the file is replaced with a private file instead
+        let current = match check_permissions(&path, self.permissions) {
+            Ok(()) => read_file(&path)?,
+            Err(error @ CookieCacheError::InsecurePermissions { .. }) => {
+                log::warn!("Replacing cookie cache without merging: {}", error);
+                None
+            },
+            Err(CookieCacheError::FileSystemError { error, .. }) if error.kind() == std::io::ErrorKind::NotFound => {
+                None
+            },
+            Err(error) => return Err(error),
+        };
+
+        let contents = update(current)?;
+        write_atomic(&path, contents.as_bytes())
+
+        // the lock is released when the lock file is closed
+    }
+}
+
+/// This helper function reads the file at the given path, if it exists.
+fn read_file(path: &Path) -> Result<Option<String>, CookieCacheError> {
+    match read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(CookieCacheError::io(path, error)),
+    }
+}
+
+/// This helper function checks that the cookie cache file is not accessible by other users.
+#[cfg(unix)]
+fn check_permissions(path: &Path, check: PermissionCheck) -> Result<(), CookieCacheError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = std::fs::metadata(path)
+        .map_err(|error| CookieCacheError::io(path, error))?
+        .permissions()
+        .mode()
+        & 0o777;
+    if mode & 0o077 == 0 {
+        return Ok(());
+    }
+
+    match check {
+        PermissionCheck::Refuse => Err(CookieCacheError::InsecurePermissions {
+            path: path.to_path_buf(),
+            mode,
+        }),
+        PermissionCheck::Warn => {
+            log::warn!(
+                "Cookie cache {} is accessible by other users (mode {:o}).",
+                path.display(),
+                mode
+            );
+            Ok(())
+        },
+    }
+}
+
+/// This helper function checks that the cookie cache file is not accessible by other users.
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path, _check: PermissionCheck) -> Result<(), CookieCacheError> {
+    Ok(())
+}
+
+/// This helper function creates a directory (and its parents) that is only accessible by the
+/// current user.
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder.create(path)
+}
+
+/// This helper function returns [`OpenOptions`] that create files which are only accessible by the
+/// current user.
+fn private_file_options() -> OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = OpenOptions::new();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options
+}
+
+/// This helper function returns the path of the lock file that guards the given cookie cache.
+fn get_lock_path(path: &Path) -> PathBuf {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
+
+/// This helper function opens (and creates, if necessary) the lock file for the given cookie cache.
+fn open_lock_file(path: &Path) -> Result<File, CookieCacheError> {
+    let lock_path = get_lock_path(path);
+
+    private_file_options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|error| CookieCacheError::io(&lock_path, error))
+}
+
+/// This helper function writes data to a temporary file next to the given path first, and then
+/// moves it into place, so readers never see partially written files. The file is only accessible
+/// by the current user.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), CookieCacheError> {
+    let file_name = path.file_name().ok_or_else(|| {
+        CookieCacheError::io(
+            path,
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "path does not name a file"),
+        )
+    })?;
+
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    // remove leftovers from previous attempts, so the file is created with the correct permissions
+    let _ = remove_file(&temp_path);
+
+    let result = private_file_options()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| rename(&temp_path, path));
+
+    if let Err(error) = result {
+        let _ = remove_file(&temp_path);
+        return Err(CookieCacheError::io(path, error));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+
+    use reqwest::cookie::CookieStore;
+    use reqwest::header::HeaderValue;
+    use url::Url;
+
+    use super::*;
+    use crate::openid::{CacheConfig, CachingJar};
+    use crate::CookieCache;
+
+    /// environment variable that tells a child process which cookie cache to write to
+    const HAMMER_PATH: &str = "FEDORA_RS_TEST_HAMMER_PATH";
+    /// environment variable that tells a child process which cookies to write
+    const HAMMER_WORKER: &str = "FEDORA_RS_TEST_HAMMER_WORKER";
+
+    const WORKERS: usize = 8;
+    const WRITES: usize = 20;
+
+    /// This helper function returns an empty directory for a test.
+    fn test_dir(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("fedora-rs-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        create_private_dir(&directory).unwrap();
+        directory
+    }
+
+    /// This helper function returns the settings for a cookie cache at the given path.
+    fn cache_config(path: PathBuf) -> CacheConfig {
+        CacheConfig {
+            location: CookieCache::path(path),
+            ..CacheConfig::default()
+        }
+    }
+
+    /// This test is run in child processes by [`concurrent_writes_from_multiple_processes`]. It
+    /// does nothing when it is run directly.
+    #[test]
+    fn hammer_worker() {
+        let (path, worker) = match (std::env::var(HAMMER_PATH), std::env::var(HAMMER_WORKER)) {
+            (Ok(path), Ok(worker)) => (PathBuf::from(path), worker),
+            _ => return,
+        };
+
+        let config = cache_config(path);
+        let url = Url::parse("https://example.com/").unwrap();
+        let jar = CachingJar::empty();
+
+        for write in 0..WRITES {
+            let header = HeaderValue::from_str(&format!("worker-{}-{}=value; Max-Age=3600", worker, write)).unwrap();
+            jar.set_cookies(&mut std::iter::once(&header), &url);
+            jar.write_to_store(&config).unwrap();
+        }
+    }
+
+    #[test]
+    fn concurrent_writes_from_multiple_processes() {
+        let directory = test_dir("hammer");
+        let path = directory.join("cookies.json");
+        let exe = std::env::current_exe().unwrap();
+
+        let children: Vec<_> = (0..WORKERS)
+            .map(|worker| {
+                Command::new(&exe)
+                    .args(["--exact", "store::tests::hammer_worker", "--test-threads=1"])
+                    .env(HAMMER_PATH, &path)
+                    .env(HAMMER_WORKER, worker.to_string())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+
+        for child in children {
+            let output = child.wait_with_output().unwrap();
+            assert!(
+                output.status.success(),
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        let jar = CachingJar::read_from_store(&cache_config(path)).unwrap();
+        let mut names: Vec<String> = jar.list().into_iter().map(|cookie| cookie.name).collect();
+        names.sort();
+
+        let mut expected: Vec<String> = (0..WORKERS)
+            .flat_map(|worker| (0..WRITES).map(move |write| format!("worker-{}-{}", worker, write)))
+            .collect();
+        expected.sort();
+
+        assert_eq!(names, expected);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn update_checks_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = test_dir("permissions");
+        let path = directory.join("janedoe.json");
+        std::fs::write(&path, "[]").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        // with the default settings, the contents are not passed on, and the file is replaced
+        let store = FileStore::new(&directory);
+        assert!(matches!(
+            store.load("janedoe"),
+            Err(CookieCacheError::InsecurePermissions { mode: 0o644, .. })
+        ));
+        store
+            .update("janedoe", &mut |current| {
+                assert_eq!(current, None);
+                Ok(String::from("[1]"))
+            })
+            .unwrap();
+
+        // the replaced file is only accessible by the current user
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        assert_eq!(store.load("janedoe").unwrap().as_deref(), Some("[1]"));
+
+        // with PermissionCheck::Warn, the contents are passed on
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let store = store.permissions(PermissionCheck::Warn);
+        store
+            .update("janedoe", &mut |current| {
+                assert_eq!(current.as_deref(), Some("[1]"));
+                Ok(String::from("[2]"))
+            })
+            .unwrap();
+        assert_eq!(store.load("janedoe").unwrap().as_deref(), Some("[2]"));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn delete_keeps_lock_file() {
+        let directory = test_dir("delete");
+        let store = FileStore::new(&directory);
+
+        store.save("janedoe", "[]").unwrap();
+        store.delete("janedoe").unwrap();
+
+        assert_eq!(store.load("janedoe").unwrap(), None);
+        assert!(directory.join("janedoe.json.lock").exists());
+
+        // deleting profiles that do not exist is not an error
+        store.delete("janedoe").unwrap();
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+//! This module contains an implementation of [`SessionStore`] that stores session data in the
+//! Secret Service (for example, GNOME Keyring or KWallet) via D-Bus.
+
+use std::collections::HashMap;
+
+use secret_service::blocking::{Item, SecretService};
+use secret_service::EncryptionType;
+
+use super::SessionStore;
+use crate::openid::CookieCacheError;
+
+/// value of the "application" attribute of all items that are created by this crate
+const APPLICATION: &str = "fedora-rs";
+
+/// This helper function converts errors from the Secret Service into [`CookieCacheError`]s.
+fn store_error(error: secret_service::Error) -> CookieCacheError {
+    CookieCacheError::Store { error: error.into() }
+}
+
+/// This type implements [`SessionStore`] by storing session data as items in the default
+/// collection of the Secret Service, using the profile name as an item attribute.
+///
+/// This type is only available if the `secret-service` feature is enabled.
+#[derive(Debug, Default)]
+pub struct SecretServiceStore {
+    _private: (),
+}
+
+impl SecretServiceStore {
+    /// This method constructs a new [`SecretServiceStore`].
+    ///
+    /// Note: The connection to the Secret Service is only established when session data is
+    /// loaded, saved, or deleted.
+    pub fn new() -> Self {
+        SecretServiceStore::default()
+    }
+
+    /// This method returns the attributes that are used to look up the item for a profile.
+    fn attributes(profile: &str) -> HashMap<&str, &str> {
+        HashMap::from([("application", APPLICATION), ("profile", profile)])
+    }
+
+    /// This method connects to the Secret Service.
+    fn connect<'a>() -> Result<SecretService<'a>, CookieCacheError> {
+        SecretService::connect(EncryptionType::Dh).map_err(store_error)
+    }
+
+    /// This method returns all items that belong to the given profile, and unlocks them if
+    /// necessary.
+    fn items<'a>(service: &'a SecretService<'a>, profile: &str) -> Result<Vec<Item<'a>>, CookieCacheError> {
+        let result = service
+            .search_items(SecretServiceStore::attributes(profile))
+            .map_err(store_error)?;
+
+        if !result.locked.is_empty() {
+            let locked: Vec<&Item> = result.locked.iter().collect();
+            service.unlock_all(&locked).map_err(store_error)?;
+        }
+
+        Ok(result.unlocked.into_iter().chain(result.locked).collect())
+    }
+}
+
+impl SessionStore for SecretServiceStore {
+    fn load(&self, profile: &str) -> Result<Option<String>, CookieCacheError> {
+        let service = SecretServiceStore::connect()?;
+
+        let item = match SecretServiceStore::items(&service, profile)?.into_iter().next() {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let secret = item.get_secret().map_err(store_error)?;
+        let contents = String::from_utf8(secret).map_err(|error| CookieCacheError::Store { error: error.into() })?;
+
+        Ok(Some(contents))
+    }
+
+    fn save(&self, profile: &str, contents: &str) -> Result<(), CookieCacheError> {
+        let service = SecretServiceStore::connect()?;
+
+        let collection = service.get_default_collection().map_err(store_error)?;
+        collection.ensure_unlocked().map_err(store_error)?;
+
+        let label = format!("fedora-rs session cookies ({})", profile);
+
+        // existing items with the same attributes are replaced
+        collection
+            .create_item(
+                &label,
+                SecretServiceStore::attributes(profile),
+                contents.as_bytes(),
+                true,
+                "application/json",
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), CookieCacheError> {
+        let service = SecretServiceStore::connect()?;
+
+        for item in SecretServiceStore::items(&service, profile)? {
+            item.delete().map_err(store_error)?;
+        }
+
+        Ok(())
+    }
+}
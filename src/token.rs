@@ -0,0 +1,251 @@
+//! This module contains an implementation of sessions that are authenticated with static API tokens
+//! (for example, for Pagure, Copr, or Bodhi).
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::Request;
+use url::{Origin, Url};
+
+use crate::auth::{AuthError, Authenticator, BoxFuture};
+use crate::config::{client_config_methods, ClientConfig};
+use crate::secret::Secret;
+use crate::session::Session;
+use crate::tls::TlsError;
+
+/// This collection of errors is returned when reading API tokens from a configuration file, or when
+/// building a session with an API token failed.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiTokenError {
+    /// The configuration file could not be read.
+    #[error("Failed to read configuration file {}: {error}", path.display())]
+    FileSystemError {
+        /// path of the configuration file
+        path: PathBuf,
+        /// The inner error contains the I/O error that occurred when reading the file.
+        error: std::io::Error,
+    },
+    /// The configuration file does not contain a required value.
+    #[error("Configuration file {} does not contain a value for {key:?}", path.display())]
+    MissingValue {
+        /// path of the configuration file
+        path: PathBuf,
+        /// name of the missing configuration key
+        key: &'static str,
+    },
+    /// The home directory of the current user could not be determined.
+    #[error("Failed to determine the location of the configuration file.")]
+    NoConfigDir,
+    /// The API token can not be used as the value of an HTTP header.
+    #[error("API token contains invalid characters.")]
+    InvalidToken,
+    /// The TLS backend rejected the configured client certificate or CA certificates.
+    #[error("Failed to initialize TLS: {error}")]
+    Tls {
+        /// The inner error contains the error that was returned by the TLS backend.
+        #[from]
+        error: TlsError,
+    },
+}
+
+/// This enum represents static API tokens for Fedora services, and determines how they are sent.
+///
+/// Tokens are wrapped in a [`Secret`], so they do not show up in [`Debug`] output.
+#[derive(Clone, Debug)]
+pub enum ApiToken {
+    /// Pagure API token, sent as `Authorization: token <token>`
+    Pagure {
+        /// API token
+        token: Secret,
+    },
+    /// Copr API login and token, sent as HTTP Basic authentication
+    Copr {
+        /// API login (this is not the FAS username)
+        login: String,
+        /// API token
+        token: Secret,
+    },
+    /// generic bearer token (for example, for Bodhi), sent as `Authorization: Bearer <token>`
+    Bearer {
+        /// bearer token
+        token: Secret,
+    },
+}
+
+impl ApiToken {
+    /// This method constructs a new Pagure API token.
+    pub fn pagure(token: impl Into<Secret>) -> Self {
+        ApiToken::Pagure { token: token.into() }
+    }
+
+    /// This method constructs a new Copr API login / token pair.
+    pub fn copr(login: &str, token: impl Into<Secret>) -> Self {
+        ApiToken::Copr {
+            login: login.to_string(),
+            token: token.into(),
+        }
+    }
+
+    /// This method constructs a new generic bearer token.
+    pub fn bearer(token: impl Into<Secret>) -> Self {
+        ApiToken::Bearer { token: token.into() }
+    }
+
+    /// This method reads the Copr API login / token pair from the Copr configuration file, which
+    /// is located at `~/.config/copr` by default.
+    pub fn from_copr_config(path: Option<&Path>) -> Result<Self, ApiTokenError> {
+        let (path, contents) = read_copr_config(path)?;
+
+        let login = config_value(&contents, "copr-cli", "login");
+        let token = config_value(&contents, "copr-cli", "token");
+
+        match (login, token) {
+            (Some(login), Some(token)) => Ok(ApiToken::copr(login, token)),
+            (None, _) => Err(ApiTokenError::MissingValue { path, key: "login" }),
+            (_, None) => Err(ApiTokenError::MissingValue { path, key: "token" }),
+        }
+    }
+
+    /// This method returns the value of the `Authorization` header for this token.
+    fn header_value(&self) -> Result<HeaderValue, ApiTokenError> {
+        let string = match self {
+            ApiToken::Pagure { token } => format!("token {}", token.expose()),
+            ApiToken::Copr { login, token } => {
+                format!("Basic {}", STANDARD.encode(format!("{}:{}", login, token.expose())))
+            },
+            ApiToken::Bearer { token } => format!("Bearer {}", token.expose()),
+        };
+
+        let mut value = HeaderValue::from_str(&string).map_err(|_| ApiTokenError::InvalidToken)?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+/// This helper function reads the Copr configuration file from the given path, or from the
+/// default location (`~/.config/copr`), and returns its path and contents.
+pub(crate) fn read_copr_config(path: Option<&Path>) -> Result<(PathBuf, String), ApiTokenError> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => dirs::config_dir().ok_or(ApiTokenError::NoConfigDir)?.join("copr"),
+    };
+
+    let contents = std::fs::read_to_string(&path).map_err(|error| ApiTokenError::FileSystemError {
+        path: path.clone(),
+        error,
+    })?;
+
+    Ok((path, contents))
+}
+
+/// This helper function looks up a value in a simple INI-style configuration file.
+pub(crate) fn config_value<'a>(contents: &'a str, section: &str, key: &str) -> Option<&'a str> {
+    let mut in_section = false;
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            in_section = line[1..line.len() - 1].trim() == section;
+            continue;
+        }
+
+        if !in_section {
+            continue;
+        }
+
+        if let Some((k, v)) = line.split_once('=') {
+            if k.trim() == key {
+                return Some(v.trim());
+            }
+        }
+    }
+
+    None
+}
+
+// API tokens are sent with every request (use `Session::with_token` to restrict them to one host)
+impl Authenticator for ApiToken {
+    fn authorize<'a>(&'a self, request: &'a mut Request) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move {
+            request.headers_mut().insert(AUTHORIZATION, self.header_value()?);
+            Ok(())
+        })
+    }
+}
+
+/// This type restricts an [`ApiToken`] to requests to the origin (scheme, host, and port) of the
+/// service that it was issued for, so it is not leaked to other hosts (for example, when following
+/// links to external sites, or when the session is shared between clients for different services).
+#[derive(Debug)]
+struct ScopedToken {
+    token: ApiToken,
+    origin: Origin,
+}
+
+impl Authenticator for ScopedToken {
+    fn authorize<'a>(&'a self, request: &'a mut Request) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move {
+            if request.url().origin() == self.origin {
+                self.token.authorize(request).await
+            } else {
+                log::debug!("Not sending API token to {}.", request.url());
+                Ok(())
+            }
+        })
+    }
+}
+
+/// This type encapsulates the arguments that are required for building a session that is
+/// authenticated with a static API token.
+#[derive(Debug)]
+pub struct TokenSessionBuilder<'a> {
+    token: ApiToken,
+    origin: Origin,
+    config: ClientConfig<'a>,
+}
+
+impl<'a> TokenSessionBuilder<'a> {
+    /// This method constructs a new [`TokenSessionBuilder`] instance for the given API token, which
+    /// is only sent with requests to the scheme, host, and port of the given service URL.
+    pub fn new(token: ApiToken, url: &Url) -> Self {
+        TokenSessionBuilder {
+            token,
+            origin: url.origin(),
+            config: ClientConfig::default(),
+        }
+    }
+
+    client_config_methods!('a);
+
+    /// This method consumes the [`TokenSessionBuilder`] and returns a [`Session`] that sends the
+    /// API token with every request to the configured service.
+    ///
+    /// The token is added to requests that are sent with [`Session::send`] or
+    /// [`Session::execute`], but not to requests that are sent with the wrapped client directly.
+    ///
+    /// If the TLS backend rejects the configured client certificate or CA certificates, an error
+    /// is returned. Otherwise, this method will panic if the network stack cannot be initialized.
+    pub fn build(self) -> Result<Session, ApiTokenError> {
+        // reject tokens that can not be sent before handing out a session
+        self.token.header_value()?;
+
+        let template = self.config.resolve();
+        let client = template.build(template.client().cookie_store(true))?;
+
+        let token = ScopedToken {
+            token: self.token,
+            origin: self.origin,
+        };
+
+        Ok(Session {
+            auth: Some(Arc::new(token)),
+            ..template.session(client)
+        })
+    }
+}
@@ -0,0 +1,175 @@
+//! tests for sessions that are authenticated with custom implementations of the Authenticator
+//! trait, against a local stand-in server
+
+#![allow(deprecated)]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{MockServer, Request, Response};
+use fedora::{Anonymous, AuthError, Authenticator, BoxFuture, Session, SessionError};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::Client;
+use url::Url;
+
+/// This type logs in by requesting a session cookie, sends the number of the current ticket with
+/// every request, and requests a new ticket when the server rejects the current one.
+#[derive(Debug)]
+struct Tickets {
+    server: Url,
+    ticket: AtomicUsize,
+}
+
+impl Tickets {
+    fn new(server: &MockServer) -> Self {
+        Tickets {
+            server: server.url(),
+            ticket: AtomicUsize::new(1),
+        }
+    }
+}
+
+impl Authenticator for Tickets {
+    fn login<'a>(&'a self, client: &'a Client) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move {
+            let response = client.post(self.server.join("login")?).send().await?;
+            if response.status().is_server_error() {
+                return Err(format!("Login failed with {}", response.status()).into());
+            }
+            Ok(())
+        })
+    }
+
+    fn authorize<'a>(&'a self, request: &'a mut reqwest::Request) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move {
+            let value = format!("Ticket {}", self.ticket.load(Ordering::SeqCst));
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, HeaderValue::from_str(&value)?);
+            Ok(())
+        })
+    }
+
+    fn recover<'a>(
+        &'a self,
+        request: &'a mut reqwest::Request,
+        _response: &'a reqwest::Response,
+    ) -> BoxFuture<'a, Result<bool, AuthError>> {
+        Box::pin(async move {
+            let ticket = self.ticket.fetch_add(1, Ordering::SeqCst) + 1;
+            let value = format!("Ticket {}", ticket);
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, HeaderValue::from_str(&value)?);
+            Ok(true)
+        })
+    }
+
+    fn logout<'a>(&'a self, client: &'a Client) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move {
+            client.post(self.server.join("logout")?).send().await?;
+            Ok(())
+        })
+    }
+}
+
+/// This helper function answers login requests with a session cookie (and a redirect that is not
+/// followed), and API requests only if they are sent with the given ticket.
+fn serve(request: &Request, ticket: &str) -> Response {
+    match request.path.as_str() {
+        "/login" => Response::status(303)
+            .with_header("Set-Cookie", "session=SESSION-1; Path=/")
+            .with_header("Location", "/welcome"),
+        "/api" if request.header("authorization") == Some(ticket) => Response::json(r#"{"status": "ok"}"#),
+        "/api" => Response::status(401),
+        _ => Response::status(200),
+    }
+}
+
+fn paths(server: &MockServer) -> Vec<String> {
+    server.requests().into_iter().map(|request| request.path).collect()
+}
+
+#[tokio::test]
+async fn login_shares_cookies_with_the_session() {
+    let server = MockServer::start(|request| serve(request, "Ticket 1")).await;
+
+    let session = Session::builder(Tickets::new(&server)).build().await.unwrap();
+    let response = session
+        .send(session.session().get(server.url_for("api")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // the login client does not follow redirects
+    assert_eq!(paths(&server), vec!["/login", "/api"]);
+
+    let requests = server.requests();
+    assert_eq!(requests[1].header("cookie"), Some("session=SESSION-1"));
+    assert_eq!(requests[1].header("authorization"), Some("Ticket 1"));
+    assert_eq!(session.cookies().len(), 1);
+}
+
+#[tokio::test]
+async fn login_errors_are_returned() {
+    let server = MockServer::start(|_| Response::status(500)).await;
+
+    let error = Session::builder(Tickets::new(&server)).build().await.unwrap_err();
+    assert!(matches!(error, SessionError::Authentication { .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn rejected_requests_are_retried_once() {
+    let server = MockServer::start(|request| serve(request, "Ticket 2")).await;
+
+    let session = Session::builder(Tickets::new(&server)).build().await.unwrap();
+    let response = session
+        .send(session.session().get(server.url_for("api")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let requests = server.requests();
+    assert_eq!(paths(&server), vec!["/login", "/api", "/api"]);
+    assert_eq!(requests[1].header("authorization"), Some("Ticket 1"));
+    assert_eq!(requests[2].header("authorization"), Some("Ticket 2"));
+
+    // the second rejection is returned instead of asking for yet another ticket
+    let server = MockServer::start(|request| serve(request, "Ticket 3")).await;
+
+    let session = Session::builder(Tickets::new(&server)).build().await.unwrap();
+    let response = session
+        .send(session.session().get(server.url_for("api")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(paths(&server), vec!["/login", "/api", "/api"]);
+}
+
+#[tokio::test]
+async fn logout_is_delegated_to_the_authenticator() {
+    let server = MockServer::start(|request| serve(request, "Ticket 1")).await;
+
+    let session = Session::builder(Tickets::new(&server)).build().await.unwrap();
+    session.logout().await.unwrap();
+
+    assert_eq!(paths(&server), vec!["/login", "/logout"]);
+    assert!(session.cookies().is_empty());
+}
+
+#[tokio::test]
+async fn default_methods_do_nothing() {
+    let server = MockServer::start(|request| serve(request, "Ticket 1")).await;
+
+    let session = Session::builder(Anonymous).build().await.unwrap();
+    let response = session
+        .send(session.session().get(server.url_for("api")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    session.logout().await.unwrap();
+
+    assert_eq!(paths(&server), vec!["/api"]);
+    assert_eq!(server.requests()[0].header("authorization"), None);
+}
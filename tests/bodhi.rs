@@ -0,0 +1,208 @@
+//! tests for the Bodhi client, against recorded responses served by a local stand-in server
+
+#![cfg(feature = "bodhi")]
+#![allow(deprecated)]
+
+mod common;
+
+use common::{MockServer, Response};
+use fedora::bodhi::{
+    BodhiClient,
+    BodhiError,
+    Karma,
+    NewUpdate,
+    UpdateQuery,
+    UpdateRequest,
+    UpdateSeverity,
+    UpdateStatus,
+    UpdateType,
+};
+
+#[tokio::test]
+async fn update() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/updates/FEDORA-2024-0123456789" => Response::fixture("bodhi/update.json"),
+        _ => Response::fixture("bodhi/not-found.json").with_status(404),
+    })
+    .await;
+    let bodhi = server.client(BodhiClient::with_url);
+
+    let update = bodhi.update("FEDORA-2024-0123456789").await.unwrap().unwrap();
+    assert_eq!(update.alias, "FEDORA-2024-0123456789");
+    assert_eq!(update.status, UpdateStatus::Testing);
+    assert_eq!(update.builds[0].nvr, "rust-fedora-2.1.2-1.fc40");
+    assert_eq!(update.bugs[0].bug_id, 2270000);
+    assert_eq!(update.release.unwrap().name, "F40");
+    assert_eq!(update.user.unwrap().name, "janedoe");
+    assert!(update.extra.contains_key("critpath"));
+
+    assert!(bodhi.update("FEDORA-2024-0000000000").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn queries_are_paginated() {
+    let server = MockServer::start(|request| match request.param("page").as_deref() {
+        Some("1") => Response::fixture("bodhi/updates-page-1.json"),
+        Some("2") => Response::fixture("bodhi/updates-page-2.json"),
+        _ => Response::status(400),
+    })
+    .await;
+
+    let query = UpdateQuery::new().package("rust-fedora").rows_per_page(2);
+    let updates = server.client(BodhiClient::with_url).all(&query).await.unwrap();
+
+    let aliases: Vec<&str> = updates.iter().map(|update| update.alias.as_str()).collect();
+    assert_eq!(
+        aliases,
+        vec![
+            "FEDORA-2024-0123456789",
+            "FEDORA-2024-1111111111",
+            "FEDORA-2024-2222222222"
+        ]
+    );
+    assert_eq!(updates[2].status, UpdateStatus::Obsolete);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(request.path, "/updates/");
+        assert_eq!(request.param("packages").as_deref(), Some("rust-fedora"));
+        assert_eq!(request.param("rows_per_page").as_deref(), Some("2"));
+    }
+}
+
+#[tokio::test]
+async fn enum_filters_use_bodhi_names() {
+    let server = MockServer::start(|_| Response::fixture("bodhi/updates-page-2.json")).await;
+
+    let query = UpdateQuery::new()
+        .status(UpdateStatus::SideTagActive)
+        .request(UpdateRequest::Unpush)
+        .update_type(UpdateType::Newpackage)
+        .severity(UpdateSeverity::Urgent);
+    server.client(BodhiClient::with_url).page(&query, 1).await.unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request.param("status").as_deref(), Some("side_tag_active"));
+    assert_eq!(request.param("request").as_deref(), Some("unpush"));
+    assert_eq!(request.param("type").as_deref(), Some("newpackage"));
+    assert_eq!(request.param("severity").as_deref(), Some("urgent"));
+}
+
+#[tokio::test]
+async fn builds_releases_overrides_and_users() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/builds/rust-fedora-2.1.2-1.fc40" => Response::fixture("bodhi/build.json"),
+        "/releases/F40" => Response::fixture("bodhi/release.json"),
+        "/overrides/rust-fedora-2.1.2-1.fc40" => Response::fixture("bodhi/override.json"),
+        "/users/janedoe" => Response::fixture("bodhi/user.json"),
+        _ => Response::fixture("bodhi/not-found.json").with_status(404),
+    })
+    .await;
+    let bodhi = server.client(BodhiClient::with_url);
+
+    let build = bodhi.build("rust-fedora-2.1.2-1.fc40").await.unwrap().unwrap();
+    assert!(build.signed);
+    assert_eq!(build.build_type.as_deref(), Some("rpm"));
+
+    let release = bodhi.release("F40").await.unwrap().unwrap();
+    assert_eq!(release.branch.as_deref(), Some("f40"));
+    assert!(release.composed_by_bodhi);
+
+    let build_override = bodhi.build_override("rust-fedora-2.1.2-1.fc40").await.unwrap().unwrap();
+    assert_eq!(build_override.notes, "Needed for rebuilds.");
+    assert_eq!(build_override.submitter.unwrap().name, "janedoe");
+
+    let user = bodhi.user("janedoe").await.unwrap().unwrap();
+    assert_eq!(user.groups[0].name, "packager");
+}
+
+#[tokio::test]
+async fn names_can_not_change_the_requested_path() {
+    let server = MockServer::start(|_| Response::fixture("bodhi/not-found.json").with_status(404)).await;
+    let bodhi = server.client(BodhiClient::with_url);
+
+    assert!(bodhi.user("../updates/?x=1#y").await.unwrap().is_none());
+    assert!(bodhi.release("F40/../F39").await.unwrap().is_none());
+
+    let paths: Vec<String> = server.requests().into_iter().map(|request| request.path).collect();
+    assert_eq!(
+        paths,
+        vec!["/users/..%2Fupdates%2F%3Fx=1%23y", "/releases/F40%2F..%2FF39"]
+    );
+
+    let error = bodhi.update("..").await.unwrap_err();
+    assert!(matches!(error, BodhiError::InvalidName { .. }), "{:?}", error);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn comment() {
+    let server = MockServer::start(|request| match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/csrf") => Response::fixture("bodhi/csrf.json"),
+        ("POST", "/comments/") => Response::fixture("bodhi/comment.json"),
+        _ => Response::fixture("bodhi/not-found.json").with_status(404),
+    })
+    .await;
+
+    let comment = server
+        .client(BodhiClient::with_url)
+        .comment("FEDORA-2024-0123456789", "Works for me.", Karma::Positive)
+        .await
+        .unwrap();
+    assert_eq!(comment.karma, 1);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+
+    let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+    assert_eq!(body["update"], "FEDORA-2024-0123456789");
+    assert_eq!(body["karma"], 1);
+    assert_eq!(body["csrf_token"], "d2Fyb2NrIGNzcmYgdG9rZW4");
+    assert_eq!(requests[1].header("content-type"), Some("application/json"));
+}
+
+#[tokio::test]
+async fn request() {
+    let server = MockServer::start(|request| match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/csrf") => Response::fixture("bodhi/csrf.json"),
+        ("POST", "/updates/FEDORA-2024-0123456789/request") => Response::fixture("bodhi/request.json"),
+        _ => Response::fixture("bodhi/not-found.json").with_status(404),
+    })
+    .await;
+
+    let update = server
+        .client(BodhiClient::with_url)
+        .request("FEDORA-2024-0123456789", UpdateRequest::Stable)
+        .await
+        .unwrap();
+    assert_eq!(update.request, Some(UpdateRequest::Stable));
+
+    let body: serde_json::Value = serde_json::from_slice(&server.requests()[1].body).unwrap();
+    assert_eq!(body["request"], "stable");
+}
+
+#[tokio::test]
+async fn errors_are_reported() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/csrf" => Response::fixture("bodhi/csrf.json"),
+        _ => Response::fixture("bodhi/error.json").with_status(400),
+    })
+    .await;
+
+    let update = NewUpdate::new(&["rust-fedora-2.1.2-1.fc40"], "Update to version 2.1.2.");
+    let error = server
+        .client(BodhiClient::with_url)
+        .create_update(&update)
+        .await
+        .unwrap_err();
+
+    match &error {
+        BodhiError::Status { status, message } => {
+            assert_eq!(status.as_u16(), 400);
+            assert_eq!(message, "csrf_token: CSRF tokens do not match");
+        },
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert!(!error.is_retryable());
+}
@@ -0,0 +1,322 @@
+//! This module contains a minimal HTTP/1.1 server that is used as a local stand-in for Fedora web
+//! services in integration tests.
+//!
+//! Every connection serves exactly one request and is closed afterwards. All requests are recorded,
+//! so tests can check which paths were requested, and which headers and bodies were sent.
+
+#![allow(dead_code)]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use fedora::Session;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use url::Url;
+
+/// This type represents a request that was received by the [`MockServer`].
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    /// raw (still percent-encoded) path of the request, without the query string
+    pub path: String,
+    /// raw query string of the request (if any)
+    pub query: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// This method returns the value of the header with the given name (if present).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// This method returns the decoded value of the query parameter with the given name.
+    pub fn param(&self, name: &str) -> Option<String> {
+        let query = self.query.as_deref()?;
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// This method returns the decoded value of the form field with the given name from a request
+    /// with an `application/x-www-form-urlencoded` body.
+    pub fn form(&self, name: &str) -> Option<String> {
+        url::form_urlencoded::parse(&self.body)
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// This method returns the body of the request as a string.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// This type represents the response that the [`MockServer`] sends for a request.
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// This method constructs a response with the given status code and an empty body.
+    pub fn status(status: u16) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// This method constructs a "200 OK" response with a JSON body.
+    pub fn json(body: &str) -> Self {
+        Response::status(200).with_body("application/json", body)
+    }
+
+    /// This method constructs a "200 OK" response with a JSON body that is read from the recorded
+    /// fixture at the given path (see [`fixture`]).
+    pub fn fixture(path: &str) -> Self {
+        Response::json(&fixture(path))
+    }
+
+    /// This method replaces the status code of the response.
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// This method replaces the body of the response.
+    pub fn with_body(mut self, content_type: &str, body: &str) -> Self {
+        self.headers
+            .push((String::from("Content-Type"), content_type.to_owned()));
+        self.body = body.as_bytes().to_vec();
+        self
+    }
+
+    /// This method adds a header to the response.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+/// function that wraps accepted connections (for example, with TLS)
+pub type Accept = Box<dyn Fn(TcpStream) -> Pin<Box<dyn Future<Output = Option<Stream>> + Send>> + Send + Sync>;
+
+/// This trait is implemented for plain TCP streams and for TLS streams.
+pub trait Io: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin {}
+impl<T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin> Io for T {}
+
+/// boxed stream for an accepted connection
+pub type Stream = Box<dyn Io>;
+
+/// This type is a local HTTP server that answers requests with the given handler.
+pub struct MockServer {
+    url: Url,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    /// This method starts a new HTTP server on a random local port.
+    pub async fn start<F>(handler: F) -> MockServer
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        let accept: Accept = Box::new(|stream| Box::pin(async move { Some(Box::new(stream) as Stream) }));
+        MockServer::start_with(handler, "http", accept).await
+    }
+
+    /// This method starts a new server on a random local port, which wraps accepted connections
+    /// with the given function (for example, for TLS).
+    pub async fn start_with<F>(handler: F, scheme: &str, accept: Accept) -> MockServer
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind local port.");
+        let port = listener.local_addr().expect("Failed to get local address.").port();
+        let url = Url::parse(&format!("{}://127.0.0.1:{}/", scheme, port)).expect("Failed to parse URL.");
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let accept = Arc::new(accept);
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let recorded = recorded.clone();
+                let accept = accept.clone();
+
+                tokio::spawn(async move {
+                    if let Some(stream) = accept(stream).await {
+                        serve(stream, handler.as_ref(), &recorded).await;
+                    }
+                });
+            }
+        });
+
+        MockServer { url, requests }
+    }
+
+    /// This method starts a new HTTPS server on a random local port, which uses a certificate that
+    /// was signed by the test CA (`tests/fixtures/tls/ca.pem`).
+    pub async fn start_tls<F>(handler: F) -> MockServer
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        use tokio_native_tls::native_tls;
+
+        let identity = native_tls::Identity::from_pkcs8(
+            fixture("tls/server.pem").as_bytes(),
+            fixture("tls/server.key").as_bytes(),
+        )
+        .expect("Failed to load server certificate.");
+        let acceptor = Arc::new(tokio_native_tls::TlsAcceptor::from(
+            native_tls::TlsAcceptor::new(identity).expect("Failed to initialize TLS."),
+        ));
+
+        let accept: Accept = Box::new(move |stream| {
+            let acceptor = acceptor.clone();
+            Box::pin(async move {
+                let stream = acceptor.accept(stream).await.ok()?;
+                Some(Box::new(stream) as Stream)
+            })
+        });
+
+        MockServer::start_with(handler, "https", accept).await
+    }
+
+    /// This method returns the base URL of the server (ending with a slash).
+    pub fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    /// This method returns the absolute URL for the given path on this server.
+    pub fn url_for(&self, path: &str) -> Url {
+        self.url.join(path).expect("Failed to construct URL.")
+    }
+
+    /// This method constructs an API client for this server with an anonymous session, for
+    /// example, `server.client(BodhiClient::with_url)`.
+    pub fn client<C>(&self, with_url: impl FnOnce(Session, Url) -> C) -> C {
+        self.client_for("", with_url)
+    }
+
+    /// This method constructs an API client for the given path on this server with an anonymous
+    /// session, for services that are not located at the root (for example, a Koji hub).
+    pub fn client_for<C>(&self, path: &str, with_url: impl FnOnce(Session, Url) -> C) -> C {
+        with_url(Session::anonymous().build(), self.url_for(path))
+    }
+
+    /// This method returns all requests that were received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().expect("Poisoned lock.").clone()
+    }
+}
+
+/// This helper function reads a single request from the stream, and writes the response.
+async fn serve(stream: Stream, handler: &Handler, recorded: &Mutex<Vec<Request>>) {
+    let mut reader = BufReader::new(stream);
+
+    let request = match read_request(&mut reader).await {
+        Some(request) => request,
+        None => return,
+    };
+
+    let response = handler(&request);
+    recorded.lock().expect("Poisoned lock.").push(request);
+
+    let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+    head.push_str("Connection: close\r\n\r\n");
+
+    let stream = reader.get_mut();
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(&response.body).await;
+    let _ = stream.shutdown().await;
+}
+
+/// This helper function parses an HTTP/1.1 request, including bodies with chunked encoding.
+async fn read_request(reader: &mut BufReader<Stream>) -> Option<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_owned();
+    let target = parts.next()?.to_owned();
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_owned(), Some(query.to_owned())),
+        None => (target, None),
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':')?;
+        headers.push((name.trim().to_owned(), value.trim().to_owned()));
+    }
+
+    let mut request = Request {
+        method,
+        path,
+        query,
+        headers,
+        body: Vec::new(),
+    };
+
+    if let Some(length) = request.header("content-length") {
+        let length: usize = length.parse().ok()?;
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).await.ok()?;
+        request.body = body;
+    } else if request
+        .header("transfer-encoding")
+        .map_or(false, |value| value.eq_ignore_ascii_case("chunked"))
+    {
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size).await.ok()?;
+            let size = usize::from_str_radix(size.trim().split(';').next()?, 16).ok()?;
+
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).await.ok()?;
+            chunk.truncate(size);
+
+            if size == 0 {
+                break;
+            }
+            request.body.extend(chunk);
+        }
+    }
+
+    Some(request)
+}
+
+/// This helper function reads a recorded fixture from the `tests/fixtures` directory.
+pub fn fixture(path: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(path);
+    std::fs::read_to_string(&path).unwrap_or_else(|error| panic!("Failed to read {}: {}", path.display(), error))
+}
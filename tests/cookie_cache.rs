@@ -0,0 +1,102 @@
+//! tests for the locations of cookie cache profiles, and for listing and purging them
+//!
+//! Profiles are stored in `$XDG_CACHE_HOME`, which is only honored on Linux. Since environment
+//! variables are shared by all tests in this process, tests that use them hold a lock.
+
+#![allow(deprecated)]
+#![cfg(target_os = "linux")]
+
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use fedora::{CookieCache, CookieCacheError};
+
+static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+/// This helper function points `$XDG_CACHE_HOME` to a new empty directory, and returns it together
+/// with the lock that must be held while the environment variable is in use.
+fn cache_home(name: &str) -> (PathBuf, MutexGuard<'static, ()>) {
+    let guard = ENVIRONMENT.lock().unwrap_or_else(|error| error.into_inner());
+
+    let home = std::env::temp_dir().join(format!("fedora-rs-test-{}-xdg-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&home);
+    std::fs::create_dir_all(&home).unwrap();
+    std::env::set_var("XDG_CACHE_HOME", &home);
+
+    (home, guard)
+}
+
+fn touch(path: &PathBuf) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, "[]").unwrap();
+}
+
+#[test]
+fn profiles_are_stored_in_the_cache_directory() {
+    let (home, _guard) = cache_home("paths");
+
+    let path = CookieCache::profile("stg-janedoe").unwrap().file_path().unwrap();
+    assert_eq!(path, home.join("fedora-rs").join("stg-janedoe.json"));
+
+    // custom paths are not affected
+    let path = CookieCache::path("/tmp/cookies.json").file_path().unwrap();
+    assert_eq!(path, PathBuf::from("/tmp/cookies.json"));
+}
+
+#[test]
+fn invalid_profile_names_are_rejected() {
+    let (_home, _guard) = cache_home("names");
+
+    for name in ["", ".hidden", "../escape", "a/b", "spaces are invalid"] {
+        let error = CookieCache::profile(name).unwrap_err();
+        assert!(matches!(error, CookieCacheError::InvalidProfile { .. }), "{:?}", error);
+
+        // profiles that were constructed directly are checked, too
+        let error = CookieCache::Profile { name: name.to_string() }.file_path().unwrap_err();
+        assert!(matches!(error, CookieCacheError::InvalidProfile { .. }), "{:?}", error);
+    }
+}
+
+#[test]
+fn profiles_are_listed() {
+    let (home, _guard) = cache_home("list");
+
+    // the directory does not exist yet
+    assert!(CookieCache::list_profiles().unwrap().is_empty());
+
+    for name in ["stg-janedoe", "janedoe@example.com", "prod"] {
+        touch(&CookieCache::profile(name).unwrap().file_path().unwrap());
+    }
+
+    // files that are not profiles are ignored
+    touch(&home.join("fedora-rs").join("notes.txt"));
+    touch(&home.join("fedora-rs").join(".hidden.json"));
+
+    assert_eq!(
+        CookieCache::list_profiles().unwrap(),
+        vec!["janedoe@example.com", "prod", "stg-janedoe"]
+    );
+}
+
+#[test]
+fn profiles_are_purged() {
+    let (home, _guard) = cache_home("purge");
+
+    let profile = CookieCache::profile("prod").unwrap();
+    touch(&profile.file_path().unwrap());
+    touch(&CookieCache::profile("stg-janedoe").unwrap().file_path().unwrap());
+    touch(&home.join("fedora-rs").join("notes.txt"));
+
+    profile.purge().unwrap();
+    assert!(!profile.file_path().unwrap().exists());
+    assert_eq!(CookieCache::list_profiles().unwrap(), vec!["stg-janedoe"]);
+
+    // purging a profile that does not exist is not an error
+    profile.purge().unwrap();
+
+    CookieCache::purge_profiles().unwrap();
+    assert!(CookieCache::list_profiles().unwrap().is_empty());
+
+    // other files are kept
+    assert!(home.join("fedora-rs").join("notes.txt").exists());
+}
@@ -0,0 +1,208 @@
+//! tests for the Copr client, against recorded responses served by a local stand-in server
+
+#![cfg(feature = "copr")]
+#![allow(deprecated)]
+
+mod common;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use common::{MockServer, Response};
+use fedora::copr::{BuildOptions, BuildState, CoprClient, CoprConfig, CoprError, NewProject};
+
+/// This helper function writes a file with the given contents to a temporary directory that is
+/// unique to this test process.
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fedora-rs-copr-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn config_is_parsed() {
+    let path = temp_file(
+        "config",
+        b"# written by copr-cli\n\
+          [copr-cli]\n\
+          login = ABCDEFGHIJKLMNOPQRST\n\
+          username = janedoe\n\
+          token = abcdefghijklmnopqrstuvwxyzabcd\n\
+          copr_url = https://copr.stg.fedoraproject.org\n",
+    );
+
+    let config = CoprConfig::from_file(Some(&path)).unwrap();
+    assert_eq!(config.login, "ABCDEFGHIJKLMNOPQRST");
+    assert_eq!(config.username, "janedoe");
+    assert_eq!(config.copr_url.as_str(), "https://copr.stg.fedoraproject.org/");
+
+    // the token must not be leaked in debug output
+    assert!(!format!("{:?}", config).contains("abcdefghijklmnopqrstuvwxyzabcd"));
+}
+
+#[test]
+fn config_defaults_to_fedora_copr() {
+    let path = temp_file(
+        "config-default",
+        b"[other]\ncopr_url = https://example.com\n\n[copr-cli]\nlogin = LOGIN\nusername = janedoe\ntoken = TOKEN\n",
+    );
+
+    let config = CoprConfig::from_file(Some(&path)).unwrap();
+    assert_eq!(config.copr_url.as_str(), "https://copr.fedorainfracloud.org/");
+}
+
+#[test]
+fn config_without_token_is_rejected() {
+    let path = temp_file("config-incomplete", b"[copr-cli]\nlogin = LOGIN\nusername = janedoe\n");
+
+    let error = CoprConfig::from_file(Some(&path)).unwrap_err();
+    assert!(matches!(error, CoprError::Config { .. }), "{:?}", error);
+    assert!(error.to_string().contains("token"), "{}", error);
+
+    let error = CoprConfig::from_file(Some(&path.with_file_name("missing"))).unwrap_err();
+    assert!(matches!(error, CoprError::Config { .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn builds_are_polled_until_they_finish() {
+    let polls = AtomicUsize::new(0);
+    let server = MockServer::start(move |request| {
+        if request.path != "/api_3/build/7400001" {
+            return Response::status(404);
+        }
+        match polls.fetch_add(1, Ordering::SeqCst) {
+            0 => Response::fixture("copr/build-importing.json"),
+            1 => Response::fixture("copr/build-running.json"),
+            _ => Response::fixture("copr/build-succeeded.json"),
+        }
+    })
+    .await;
+
+    let build = server
+        .client(CoprClient::with_url)
+        .wait_for_build(7400001, Duration::from_secs(1), Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(build.state, BuildState::Succeeded);
+    assert_eq!(build.ended_on, Some(1712000700));
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn polling_stops_at_the_deadline() {
+    let server = MockServer::start(|_| Response::fixture("copr/build-running.json")).await;
+
+    let error = server
+        .client(CoprClient::with_url)
+        .wait_for_build(7400001, Duration::ZERO, Duration::from_millis(50))
+        .await
+        .unwrap_err();
+
+    match error {
+        CoprError::Timeout { id, state } => {
+            assert_eq!(id, 7400001);
+            assert_eq!(state, BuildState::Running);
+        },
+        _ => panic!("unexpected error: {:?}", error),
+    }
+
+    // the interval is extended to the minimum, so the build is only checked at the start and at
+    // the deadline (never after it)
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn source_packages_are_uploaded_as_multipart_form() {
+    let server = MockServer::start(|request| match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/api_3/build/create/upload") => Response::fixture("copr/build-importing.json"),
+        _ => Response::status(404),
+    })
+    .await;
+
+    // large enough to be sent in multiple chunks
+    let contents: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let path = temp_file("rust-fedora-2.1.2-1.fc40.src.rpm", &contents);
+
+    let options = BuildOptions::new().chroot("fedora-rawhide-x86_64");
+    let build = server
+        .client(CoprClient::with_url)
+        .build_from_file("janedoe", "rust-fedora", &path, &options)
+        .await
+        .unwrap();
+    assert_eq!(build.state, BuildState::Importing);
+
+    let request = &server.requests()[0];
+    assert!(request
+        .header("content-type")
+        .unwrap()
+        .starts_with("multipart/form-data; boundary="));
+    assert_eq!(request.header("transfer-encoding"), None);
+    assert_eq!(
+        request.header("content-length"),
+        Some(request.body.len().to_string().as_str())
+    );
+
+    let body = request.body.as_slice();
+    let text = String::from_utf8_lossy(body);
+    assert!(text.contains(r#"Content-Disposition: form-data; name="json""#));
+    assert!(text.contains(r#"{"ownername":"janedoe","projectname":"rust-fedora","chroots":["fedora-rawhide-x86_64"]}"#));
+    assert!(
+        text.contains(r#"Content-Disposition: form-data; name="pkgs"; filename="rust-fedora-2.1.2-1.fc40.src.rpm""#)
+    );
+    assert!(text.contains("Content-Type: application/x-rpm"));
+    assert!(body.windows(contents.len()).any(|window| window == contents.as_slice()));
+}
+
+#[tokio::test]
+async fn missing_source_packages_are_reported() {
+    let server = MockServer::start(|_| Response::status(500)).await;
+
+    let path = temp_file("placeholder", b"").with_file_name("missing.src.rpm");
+    let error = server
+        .client(CoprClient::with_url)
+        .build_from_file("janedoe", "rust-fedora", &path, &BuildOptions::new())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, CoprError::FileSystemError { .. }), "{:?}", error);
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn group_projects_are_created() {
+    let server = MockServer::start(|request| match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/api_3/project/add/@rust") => Response::fixture("copr/project.json"),
+        _ => Response::fixture("copr/error.json").with_status(400),
+    })
+    .await;
+    let copr = server.client(CoprClient::with_url);
+
+    let project = NewProject::new("rust-fedora", &["fedora-rawhide-x86_64"]).enable_net(true);
+    let created = copr.create_project("@rust", &project).await.unwrap();
+    assert_eq!(created.full_name, "@rust/rust-fedora");
+    assert!(created.enable_net);
+
+    let error = copr.create_project("@rust/../../build", &project).await.unwrap_err();
+    match &error {
+        CoprError::Status { status, message } => {
+            assert_eq!(status.as_u16(), 400);
+            assert_eq!(message, "Project @rust/rust-fedora already exists.");
+        },
+        _ => panic!("unexpected error: {:?}", error),
+    }
+
+    let error = copr.create_project("..", &project).await.unwrap_err();
+    assert!(matches!(error, CoprError::InvalidName { .. }), "{:?}", error);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].path, "/api_3/project/add/@rust%2F..%2F..%2Fbuild");
+
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["projectname"], "rust-fedora");
+    assert_eq!(body["enable_net"], true);
+}
@@ -0,0 +1,96 @@
+//! tests for the FASJSON client, against recorded responses served by a local stand-in server
+
+#![cfg(feature = "fas")]
+#![allow(deprecated)]
+
+mod common;
+
+use common::{MockServer, Response};
+use fedora::fas::{FasClient, FasError};
+
+#[tokio::test]
+async fn user() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/v1/users/janedoe/" => Response::fixture("fas/user.json"),
+        _ => Response::fixture("fas/not-found.json").with_status(404),
+    })
+    .await;
+    let fas = server.client(FasClient::with_url);
+
+    let user = fas.user("janedoe").await.unwrap().unwrap();
+    assert_eq!(user.username, "janedoe");
+    assert_eq!(user.human_name.as_deref(), Some("Jane Doe"));
+    assert_eq!(user.pronouns, vec!["she/her"]);
+    assert!(user.extra.contains_key("rssurl"));
+
+    assert!(fas.user("foobar").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn group_members_are_paginated() {
+    let server = MockServer::start(|request| match request.param("page_number").as_deref() {
+        Some("1") => Response::fixture("fas/members-page-1.json"),
+        Some("2") => Response::fixture("fas/members-page-2.json"),
+        _ => Response::status(400),
+    })
+    .await;
+
+    let members = server
+        .client(FasClient::with_url)
+        .group_members("packager")
+        .await
+        .unwrap();
+    let names: Vec<&str> = members.iter().map(|user| user.username.as_str()).collect();
+    assert_eq!(names, vec!["alice", "bob", "carol"]);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests
+        .iter()
+        .all(|request| request.path == "/v1/groups/packager/members/"));
+}
+
+#[tokio::test]
+async fn names_can_not_change_the_requested_path() {
+    let server = MockServer::start(|_| Response::json(r#"{"result": false}"#)).await;
+    let fas = server.client(FasClient::with_url);
+
+    let member = fas
+        .is_member("packager", "../../provenpackager/is-member/alice?x=1#y")
+        .await
+        .unwrap();
+    assert!(!member);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].path,
+        "/v1/groups/packager/is-member/..%2F..%2Fprovenpackager%2Fis-member%2Falice%3Fx=1%23y"
+    );
+    assert_eq!(requests[0].query, None);
+}
+
+#[tokio::test]
+async fn dot_segments_are_rejected() {
+    let server = MockServer::start(|_| Response::json(r#"{"result": true}"#)).await;
+    let fas = server.client(FasClient::with_url);
+
+    for name in ["..", ".", ""] {
+        let error = fas.is_member(name, "alice").await.unwrap_err();
+        assert!(matches!(error, FasError::InvalidName { .. }), "{:?}", error);
+    }
+
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn errors_are_reported() {
+    let server =
+        MockServer::start(|_| Response::status(403).with_body("application/json", r#"{"message": "Access denied"}"#))
+            .await;
+
+    let error = server.client(FasClient::with_url).groups().await.unwrap_err();
+    assert!(error.is_auth_failure());
+    assert!(!error.is_retryable());
+    assert!(error.to_string().contains("Access denied"));
+}
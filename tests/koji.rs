@@ -0,0 +1,206 @@
+//! tests for the Koji client, against recorded XML-RPC responses served by a local stand-in server
+
+#![cfg(feature = "koji")]
+#![allow(deprecated)]
+
+mod common;
+
+use common::{fixture, MockServer, Request, Response};
+use fedora::koji::{BuildState, KojiClient, KojiError, Value};
+
+fn xml(name: &str) -> Response {
+    Response::status(200).with_body("text/xml", &fixture(&format!("koji/{}.xml", name)))
+}
+
+/// This helper function extracts the name of the called method from an XML-RPC request.
+fn method(request: &Request) -> String {
+    let body = request.text();
+    let start = body.find("<methodName>").expect("missing method name") + "<methodName>".len();
+    let end = body.find("</methodName>").expect("missing method name");
+    body[start..end].to_string()
+}
+
+#[tokio::test]
+async fn get_build() {
+    let server = MockServer::start(|request| {
+        if request.text().contains("<string>rust-fedora-2.1.2-1.fc40</string>") {
+            xml("getBuild")
+        } else {
+            xml("getBuild-missing")
+        }
+    })
+    .await;
+    let koji = server.client_for("kojihub", KojiClient::with_url);
+
+    let build = koji.get_build("rust-fedora-2.1.2-1.fc40").await.unwrap().unwrap();
+    assert_eq!(build.build_id, 2400001);
+    assert_eq!(build.state, BuildState::Complete);
+    assert_eq!(build.task_id, Some(115000000000));
+    assert_eq!(build.epoch, None);
+    assert_eq!(build.owner_name.as_deref(), Some("janedoe"));
+    assert!(build.extra.contains_key("completion_ts"));
+
+    assert!(koji.get_build("rust-fedora-0-0.fc40").await.unwrap().is_none());
+
+    let requests = server.requests();
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, "/kojihub");
+    assert_eq!(requests[0].header("content-type"), Some("text/xml"));
+    assert_eq!(method(&requests[0]), "getBuild");
+}
+
+#[tokio::test]
+async fn multicall() {
+    let server = MockServer::start(|_| xml("multicall")).await;
+
+    let results = server
+        .client_for("kojihub", KojiClient::with_url)
+        .multicall(vec![
+            ("getKojiVersion", vec![]),
+            (
+                "getTag",
+                vec!["f99-updates".into(), Value::kwargs([("strict", true.into())])],
+            ),
+            ("getLastEvent", vec![]),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), &Value::from("1.34.0"));
+    match &results[1] {
+        Err(KojiError::Fault { code, message }) => {
+            assert_eq!(*code, 1000);
+            assert_eq!(message, "No such tagInfo: 'f99-updates'");
+        },
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert_eq!(results[2].as_ref().unwrap(), &Value::Int(12));
+
+    let request = &server.requests()[0];
+    assert_eq!(method(request), "system.multicall");
+    assert!(request
+        .text()
+        .contains("<member><name>methodName</name><value><string>getTag</string></value></member>"));
+}
+
+#[tokio::test]
+async fn multicall_with_wrong_number_of_results() {
+    let server = MockServer::start(|_| xml("multicall")).await;
+
+    let error = server
+        .client_for("kojihub", KojiClient::with_url)
+        .multicall(vec![("getKojiVersion", vec![])])
+        .await
+        .unwrap_err();
+    assert!(matches!(error, KojiError::Parsing { .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn login_and_authenticated_calls() {
+    let server = MockServer::start(|request| match (request.path.as_str(), method(request).as_str()) {
+        ("/kojihub/ssllogin", "sslLogin") => xml("sslLogin"),
+        ("/kojihub", "getLoggedInUser") => xml("getLoggedInUser"),
+        ("/kojihub", "logout") => xml("getBuild-missing"),
+        _ => Response::status(404),
+    })
+    .await;
+    let koji = server.client_for("kojihub", KojiClient::with_url);
+
+    koji.login().await.unwrap();
+    assert!(koji.is_logged_in());
+
+    let user = koji.call("getLoggedInUser", vec![]).await.unwrap();
+    assert_eq!(user.get("name"), Some(&Value::from("janedoe")));
+    koji.call("getLoggedInUser", vec![]).await.unwrap();
+
+    koji.logout().await.unwrap();
+    assert!(!koji.is_logged_in());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 4);
+
+    // the login call itself is not authenticated with a hub session
+    assert_eq!(requests[0].header("koji-session-id"), None);
+
+    // every authenticated call has an increasing call number
+    for (request, callnum) in requests[1..].iter().zip(["1", "2", "3"]) {
+        assert_eq!(request.header("koji-session-id"), Some("987654"));
+        assert_eq!(request.header("koji-session-key"), Some("987654-AbCdEfGhIjKlMnOp"));
+        assert_eq!(request.header("koji-session-callnum"), Some(callnum));
+        assert_eq!(request.query, None);
+    }
+}
+
+#[tokio::test]
+async fn faults_are_reported() {
+    let server = MockServer::start(|_| xml("fault-expired")).await;
+
+    let error = server
+        .client_for("kojihub", KojiClient::with_url)
+        .call("getLoggedInUser", vec![])
+        .await
+        .unwrap_err();
+    match &error {
+        KojiError::Fault { code, message } => {
+            assert_eq!(*code, 1007);
+            assert_eq!(message, "session \"987654\" has expired");
+        },
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert!(error.is_auth_failure());
+    assert!(!error.is_retryable());
+}
+
+#[test]
+fn faults_are_classified() {
+    let fault = |code| KojiError::Fault {
+        code,
+        message: String::new(),
+    };
+
+    // AuthError, AuthLockError, AuthExpired, GSSAPIAuthError
+    for code in [1002, 1006, 1007, 1019] {
+        assert!(fault(code).is_auth_failure(), "{}", code);
+        assert!(!fault(code).is_retryable(), "{}", code);
+    }
+
+    // SequenceError, RetryError, ServerOffline
+    for code in [1008, 1009, 1014] {
+        assert!(fault(code).is_retryable(), "{}", code);
+        assert!(!fault(code).is_auth_failure(), "{}", code);
+    }
+
+    // GenericError, ImportError, and other faults
+    for code in [1000, 1023, 1999] {
+        assert!(!fault(code).is_retryable(), "{}", code);
+        assert!(!fault(code).is_auth_failure(), "{}", code);
+    }
+}
+
+#[tokio::test]
+async fn http_errors_are_reported() {
+    let server =
+        MockServer::start(|_| Response::status(503).with_body("text/html", "<h1>Service Unavailable</h1>")).await;
+
+    let error = server
+        .client_for("kojihub", KojiClient::with_url)
+        .call("getKojiVersion", vec![])
+        .await
+        .unwrap_err();
+    assert!(matches!(error, KojiError::Status { .. }), "{:?}", error);
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn non_finite_parameters_are_not_sent() {
+    let server = MockServer::start(|_| xml("getBuild-missing")).await;
+
+    let error = server
+        .client_for("kojihub", KojiClient::with_url)
+        .call("echo", vec![Value::Double(f64::NAN)])
+        .await
+        .unwrap_err();
+    assert!(matches!(error, KojiError::Encoding { .. }), "{:?}", error);
+    assert!(server.requests().is_empty());
+}
@@ -0,0 +1,456 @@
+//! tests for sessions that are authenticated via OpenID Connect, against a local stand-in issuer
+
+#![allow(deprecated)]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use common::{fixture, MockServer, Request, Response};
+use fedora::{OIDCClientError, OIDCSessionKind, OIDCSessionLogin, Session, SessionError};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// This helper function serves the discovery document, with the issuer of the local server.
+fn serve_discovery(request: &Request) -> Response {
+    let issuer = format!("http://{}/openidc/", request.header("host").unwrap());
+    Response::json(&fixture("oidc/openid-configuration.json").replace("{issuer}", &issuer))
+}
+
+/// This helper function answers requests to the token endpoint with the given fixtures.
+fn token(request: &Request, code: &str, refresh: &str) -> Response {
+    match request.form("grant_type").as_deref() {
+        Some("authorization_code") if request.form("code").as_deref() == Some("CODE") => Response::json(&fixture(code)),
+        Some("refresh_token") => Response::json(&fixture(refresh)),
+        _ => Response::fixture("oidc/invalid-grant.json").with_status(400),
+    }
+}
+
+/// This helper function answers API requests only if they are sent with the given access token.
+fn api(request: &Request, access_token: &str) -> Response {
+    match request.header("authorization") {
+        Some(value) if value == format!("Bearer {}", access_token) => Response::json(r#"{"username": "janedoe"}"#),
+        _ => Response::status(401).with_header("WWW-Authenticate", r#"Bearer error="invalid_token""#),
+    }
+}
+
+async fn start_login(server: &MockServer) -> OIDCSessionLogin {
+    let kind = OIDCSessionKind::Custom {
+        issuer: server.url_for("openidc/"),
+    };
+    Session::oidc_auth(kind, "fedora-rs", &server.url_for("api/"))
+        .build()
+        .await
+        .unwrap()
+}
+
+/// This helper function returns the redirect back from the provider for the given login.
+fn redirect_for(login: &OIDCSessionLogin, code: &str) -> Url {
+    let state = login
+        .authorization_url()
+        .query_pairs()
+        .find(|(key, _)| key == "state")
+        .map(|(_, value)| value.into_owned())
+        .unwrap();
+
+    let mut redirect = Url::parse("http://localhost/callback").unwrap();
+    redirect
+        .query_pairs_mut()
+        .append_pair("code", code)
+        .append_pair("state", &state);
+    redirect
+}
+
+#[tokio::test]
+async fn discovery() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/openidc/.well-known/openid-configuration" => serve_discovery(request),
+        _ => Response::status(404),
+    })
+    .await;
+
+    let login = start_login(&server).await;
+    let url = login.authorization_url();
+    assert_eq!(url.path(), "/openidc/Authorization");
+
+    let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(param("response_type"), Some("code"));
+    assert_eq!(param("client_id"), Some("fedora-rs"));
+    assert_eq!(param("redirect_uri"), Some("urn:ietf:wg:oauth:2.0:oob"));
+    assert_eq!(param("scope"), Some("openid"));
+    assert_eq!(param("code_challenge_method"), Some("S256"));
+
+    // the state and the code verifier are not printed
+    let debug = format!("{:?}", login);
+    assert!(!debug.contains(param("state").unwrap()), "{}", debug);
+    assert!(debug.contains("REDACTED"), "{}", debug);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].header("accept"), Some("application/json"));
+}
+
+#[tokio::test]
+async fn discovery_rejects_other_issuers() {
+    let server = MockServer::start(|_| {
+        Response::json(&fixture("oidc/openid-configuration.json").replace("{issuer}", "https://id.example.com/"))
+    })
+    .await;
+
+    let kind = OIDCSessionKind::Custom {
+        issuer: server.url_for("openidc/"),
+    };
+    let error = Session::oidc_auth(kind, "fedora-rs", &server.url())
+        .build()
+        .await
+        .unwrap_err();
+    assert!(matches!(error, OIDCClientError::Discovery { .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn code_exchange() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/openidc/.well-known/openid-configuration" => serve_discovery(request),
+        "/openidc/Token" => token(request, "oidc/token.json", "oidc/invalid-grant.json"),
+        "/api/whoami" => api(request, "ACCESS-TOKEN-1"),
+        _ => Response::status(404),
+    })
+    .await;
+
+    let login = start_login(&server).await;
+    let challenge = login
+        .authorization_url()
+        .query_pairs()
+        .find(|(key, _)| key == "code_challenge")
+        .map(|(_, value)| value.into_owned())
+        .unwrap();
+
+    let redirect = redirect_for(&login, "CODE");
+    let session = login.login_with_redirect(&redirect).await.unwrap();
+
+    let response = session
+        .send(session.session().get(server.url_for("api/whoami")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // the wrapped client sends the initial access token on its own
+    let response = session
+        .session()
+        .get(server.url_for("api/whoami"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 4);
+
+    let exchange = &requests[1];
+    assert_eq!(exchange.method, "POST");
+    assert_eq!(exchange.header("authorization"), None);
+    assert_eq!(exchange.form("client_id").as_deref(), Some("fedora-rs"));
+    assert_eq!(
+        exchange.form("redirect_uri").as_deref(),
+        Some("urn:ietf:wg:oauth:2.0:oob")
+    );
+
+    // the code verifier must match the code challenge from the authorization URL
+    let verifier = exchange.form("code_verifier").unwrap();
+    assert_eq!(URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())), challenge);
+
+    assert_eq!(requests[2].header("authorization"), Some("Bearer ACCESS-TOKEN-1"));
+    assert_eq!(requests[3].header("authorization"), Some("Bearer ACCESS-TOKEN-1"));
+}
+
+#[tokio::test]
+async fn code_exchange_errors() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/openidc/.well-known/openid-configuration" => serve_discovery(request),
+        "/openidc/Token" => token(request, "oidc/token.json", "oidc/token.json"),
+        _ => Response::status(404),
+    })
+    .await;
+
+    let login = start_login(&server).await;
+    let redirect = redirect_for(&login, "EXPIRED");
+    let error = login.login_with_redirect(&redirect).await.unwrap_err();
+    match &error {
+        OIDCClientError::Provider {
+            error: code,
+            description,
+        } => {
+            assert_eq!(code, "invalid_grant");
+            assert!(description.as_deref().unwrap().contains("expired"));
+        },
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert!(error.is_auth_failure());
+
+    // redirects with a different state are never exchanged
+    let login = start_login(&server).await;
+    let forged = Url::parse("http://localhost/callback?code=CODE&state=forged").unwrap();
+    let error = login.login_with_redirect(&forged).await.unwrap_err();
+    assert!(matches!(error, OIDCClientError::Redirection { .. }), "{:?}", error);
+
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn rejected_tokens_are_refreshed() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/openidc/.well-known/openid-configuration" => serve_discovery(request),
+        "/openidc/Token" => token(request, "oidc/token.json", "oidc/token-refreshed.json"),
+        "/api/whoami" => api(request, "ACCESS-TOKEN-2"),
+        _ => Response::status(404),
+    })
+    .await;
+
+    let login = start_login(&server).await;
+    let redirect = redirect_for(&login, "CODE");
+    let session = login.login_with_redirect(&redirect).await.unwrap();
+
+    let response = session
+        .send(session.session().get(server.url_for("api/whoami")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let requests = server.requests();
+    let paths: Vec<&str> = requests.iter().map(|request| request.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/openidc/.well-known/openid-configuration",
+            "/openidc/Token",
+            "/api/whoami",
+            "/openidc/Token",
+            "/api/whoami",
+        ]
+    );
+
+    assert_eq!(requests[2].header("authorization"), Some("Bearer ACCESS-TOKEN-1"));
+    assert_eq!(requests[3].form("grant_type").as_deref(), Some("refresh_token"));
+    assert_eq!(requests[3].form("refresh_token").as_deref(), Some("REFRESH-TOKEN-1"));
+    assert_eq!(requests[4].header("authorization"), Some("Bearer ACCESS-TOKEN-2"));
+}
+
+#[tokio::test]
+async fn expiring_tokens_are_refreshed_ahead_of_time() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/openidc/.well-known/openid-configuration" => serve_discovery(request),
+        "/openidc/Token" => token(request, "oidc/token-expiring.json", "oidc/token-refreshed.json"),
+        "/api/whoami" => api(request, "ACCESS-TOKEN-2"),
+        _ => Response::status(404),
+    })
+    .await;
+
+    let login = start_login(&server).await;
+    let redirect = redirect_for(&login, "CODE");
+    let session = login.login_with_redirect(&redirect).await.unwrap();
+
+    for _ in 0..2 {
+        let response = session
+            .send(session.session().get(server.url_for("api/whoami")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    // the expiring access token is never sent, and the new one is used for later requests
+    let requests = server.requests();
+    let paths: Vec<&str> = requests.iter().map(|request| request.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/openidc/.well-known/openid-configuration",
+            "/openidc/Token",
+            "/openidc/Token",
+            "/api/whoami",
+            "/api/whoami",
+        ]
+    );
+    assert_eq!(requests[2].form("refresh_token").as_deref(), Some("REFRESH-TOKEN-1"));
+}
+
+#[tokio::test]
+async fn tokens_are_only_sent_to_the_configured_service() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/openidc/.well-known/openid-configuration" => serve_discovery(request),
+        "/openidc/Token" => token(request, "oidc/token.json", "oidc/invalid-grant.json"),
+        _ => Response::status(404),
+    })
+    .await;
+    let other = MockServer::start(|_| Response::json("{}")).await;
+
+    let login = start_login(&server).await;
+    let redirect = redirect_for(&login, "CODE");
+    let session = login.login_with_redirect(&redirect).await.unwrap();
+
+    let error = session
+        .send(session.session().get(other.url_for("api/whoami")))
+        .await
+        .unwrap_err();
+    match &error {
+        SessionError::Authentication { error } => assert!(
+            matches!(
+                error.downcast_ref::<OIDCClientError>(),
+                Some(OIDCClientError::UntrustedOrigin { .. })
+            ),
+            "{:?}",
+            error
+        ),
+        _ => panic!("unexpected error: {:?}", error),
+    }
+
+    assert!(other.requests().is_empty());
+}
+
+/// This helper function returns an error response of the token endpoint with the given code.
+fn token_error(error: &str) -> Response {
+    Response::status(400).with_body("application/json", &format!(r#"{{"error": "{}"}}"#, error))
+}
+
+/// This helper function starts a server that answers device authorization requests with the given
+/// fixture, and requests to the token endpoint with the given responses, in order (the last
+/// response is repeated).
+async fn start_device(device: &'static str, polls: Vec<Response>) -> MockServer {
+    let count = AtomicUsize::new(0);
+    MockServer::start(move |request| match request.path.as_str() {
+        "/openidc/.well-known/openid-configuration" => serve_discovery(request),
+        "/openidc/Device" => Response::json(&fixture(device)),
+        "/openidc/Token" => {
+            let index = count.fetch_add(1, Ordering::SeqCst).min(polls.len() - 1);
+            polls[index].clone()
+        },
+        "/api/whoami" => api(request, "ACCESS-TOKEN-1"),
+        _ => Response::status(404),
+    })
+    .await
+}
+
+/// This helper function returns all requests to the token endpoint.
+fn polls(server: &MockServer) -> Vec<Request> {
+    server
+        .requests()
+        .into_iter()
+        .filter(|request| request.path == "/openidc/Token")
+        .collect()
+}
+
+#[tokio::test]
+async fn device_code_polling_waits_for_approval() {
+    let server = start_device(
+        "oidc/device-authorization.json",
+        vec![
+            token_error("authorization_pending"),
+            token_error("authorization_pending"),
+            Response::fixture("oidc/token.json"),
+        ],
+    )
+    .await;
+
+    let mut prompted = None;
+    let login = start_login(&server).await;
+    let session = login
+        .login_with_device_code(|auth| prompted = Some(auth.user_code.clone()))
+        .await
+        .unwrap();
+    assert_eq!(prompted.as_deref(), Some("ABCD-EFGH"));
+
+    let response = session
+        .send(session.session().get(server.url_for("api/whoami")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let polls = polls(&server);
+    assert_eq!(polls.len(), 3);
+    for poll in &polls {
+        assert_eq!(
+            poll.form("grant_type").as_deref(),
+            Some("urn:ietf:params:oauth:grant-type:device_code")
+        );
+        assert_eq!(poll.form("device_code").as_deref(), Some("DEVICE-CODE"));
+        assert_eq!(poll.form("client_id").as_deref(), Some("fedora-rs"));
+    }
+}
+
+// The client reads responses from real sockets, so this test can not use paused time (tokio would
+// advance the clock to the request timeout while waiting for the stand-in server). The fixture
+// sets the polling interval to zero, so the only delay is the one requested with "slow_down".
+#[tokio::test]
+async fn device_code_polling_slows_down() {
+    let server = start_device(
+        "oidc/device-authorization.json",
+        vec![token_error("slow_down"), Response::fixture("oidc/token.json")],
+    )
+    .await;
+
+    let login = start_login(&server).await;
+    let start = Instant::now();
+    login.login_with_device_code(|_| ()).await.unwrap();
+
+    assert!(start.elapsed() >= Duration::from_secs(5), "{:?}", start.elapsed());
+    assert_eq!(polls(&server).len(), 2);
+}
+
+#[tokio::test]
+async fn device_code_polling_stops_when_the_code_expires() {
+    // the provider reports that the device code has expired
+    let server = start_device(
+        "oidc/device-authorization.json",
+        vec![token_error("authorization_pending"), token_error("expired_token")],
+    )
+    .await;
+
+    let login = start_login(&server).await;
+    let error = login.login_with_device_code(|_| ()).await.unwrap_err();
+    match &error {
+        OIDCClientError::Provider { error, .. } => assert_eq!(error, "expired_token"),
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert_eq!(polls(&server).len(), 2);
+
+    // the device code expires before the provider is polled for the first time
+    let server = start_device(
+        "oidc/device-authorization-expired.json",
+        vec![token_error("authorization_pending")],
+    )
+    .await;
+
+    let login = start_login(&server).await;
+    let error = login.login_with_device_code(|_| ()).await.unwrap_err();
+    match &error {
+        OIDCClientError::Provider { error, .. } => assert_eq!(error, "expired_token"),
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert!(polls(&server).is_empty());
+}
+
+#[tokio::test]
+async fn device_code_polling_stops_when_access_is_denied() {
+    let server = start_device(
+        "oidc/device-authorization.json",
+        vec![token_error("authorization_pending"), token_error("access_denied")],
+    )
+    .await;
+
+    let login = start_login(&server).await;
+    let error = login.login_with_device_code(|_| ()).await.unwrap_err();
+    match &error {
+        OIDCClientError::Provider { error: code, .. } => assert_eq!(code, "access_denied"),
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert!(error.is_auth_failure());
+    assert_eq!(polls(&server).len(), 2);
+}
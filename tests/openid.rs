@@ -0,0 +1,391 @@
+//! tests for sessions that are authenticated via OpenID, against a local stand-in server that acts
+//! both as the web service and as the OpenID provider
+
+#![allow(deprecated)]
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::{fixture, MockServer, Request, Response};
+use fedora::{CaBundle, CookieCache, OpenIDClientError, OpenIDSessionBuilder, OpenIDSessionKind, Session};
+use url::Url;
+
+/// This helper function returns a path for an empty cookie cache that is not shared with other
+/// tests.
+fn cache_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("fedora-rs-test-{}-openid-{}.json", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// This helper function answers requests to the web service and to the OpenID provider.
+fn serve(request: &Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        // relative redirect from the login URL to the login form of the OpenID provider
+        ("GET", "/login") => Response::status(302).with_header(
+            "Location",
+            "openid/?openid.mode=checkid_setup&openid.return_to=%2Flogin%2Fcomplete",
+        ),
+        ("GET", "/openid/") => Response::status(200).with_body("text/html", "<form method=\"post\"></form>"),
+        ("POST", "/openid/") if request.form("password").as_deref() == Some("hunter2") => {
+            let return_to = format!("http://{}/login/complete", request.header("host").unwrap());
+            Response::json(&fixture("openid/success.json").replace("{return_to}", &return_to))
+        },
+        ("POST", "/openid/") => Response::json(r#"{"success": false, "message": "Invalid username or password"}"#),
+        ("POST", "/login/complete") => {
+            Response::status(200).with_header("Set-Cookie", "session=SESSION-1; Path=/; Max-Age=3600")
+        },
+        _ => Response::status(404),
+    }
+}
+
+fn builder(server: &MockServer, cache: &Path) -> OpenIDSessionBuilder<'static> {
+    let kind = OpenIDSessionKind::Custom {
+        auth_url: server.url_for("openid/"),
+    };
+    Session::openid_auth(server.url_for("login"), kind).cookie_cache(CookieCache::path(cache))
+}
+
+#[tokio::test]
+async fn login() {
+    let server = MockServer::start(serve).await;
+
+    let session = builder(&server, &cache_path("login"))
+        .insecure_loopback(true)
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    let paths: Vec<(&str, &str)> = requests
+        .iter()
+        .map(|request| (request.method.as_str(), request.path.as_str()))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("GET", "/login"),
+            ("GET", "/openid/"),
+            ("POST", "/openid/"),
+            ("POST", "/login/complete"),
+        ]
+    );
+
+    // the parameters from the relative redirect are passed to the OpenID provider
+    let auth = &requests[2];
+    assert_eq!(auth.form("username").as_deref(), Some("janedoe"));
+    assert_eq!(auth.form("openid.mode").as_deref(), Some("checkid_setup"));
+    assert_eq!(auth.form("openid.return_to").as_deref(), Some("/login/complete"));
+
+    // the signed response is sent back to the original site
+    assert_eq!(requests[3].form("openid.sig").as_deref(), Some("c2lnbmF0dXJl"));
+
+    assert_eq!(session.cookies().len(), 1);
+    assert_eq!(session.cookies()[0].name, "session");
+}
+
+#[tokio::test]
+async fn wrong_passwords_are_rejected() {
+    let server = MockServer::start(serve).await;
+
+    let error = builder(&server, &cache_path("wrong-password"))
+        .insecure_loopback(true)
+        .build()
+        .login("janedoe", "hunter3")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, OpenIDClientError::Provider { .. }), "{:?}", error);
+    assert!(error.is_auth_failure());
+}
+
+#[tokio::test]
+async fn credentials_are_not_sent_over_plain_http() {
+    let server = MockServer::start(serve).await;
+
+    // loopback addresses are not exempt unless explicitly allowed
+    let error = builder(&server, &cache_path("plain-http"))
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, OpenIDClientError::InsecureTransport { .. }),
+        "{:?}",
+        error
+    );
+
+    assert!(server.requests().iter().all(|request| request.method == "GET"));
+}
+
+#[tokio::test]
+async fn redirects_are_limited() {
+    let server = MockServer::start(|request| {
+        let hop: usize = request.path.trim_start_matches("/hop/").parse().unwrap_or(0);
+        Response::status(302).with_header("Location", &format!("/hop/{}", hop + 1))
+    })
+    .await;
+
+    let error = builder(&server, &cache_path("hop-limit"))
+        .max_redirects(3)
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap_err();
+    match &error {
+        OpenIDClientError::TooManyRedirects { limit, .. } => assert_eq!(*limit, 3),
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn redirect_loops_are_detected() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/login" => Response::status(302).with_header("Location", "/elsewhere"),
+        _ => Response::status(302).with_header("Location", "/login"),
+    })
+    .await;
+
+    let error = builder(&server, &cache_path("loop"))
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, OpenIDClientError::RedirectLoop { .. }), "{:?}", error);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn redirects_to_untrusted_hosts_are_not_followed() {
+    // "localhost" is a different host than "127.0.0.1", even though it is the same server
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/login" => {
+            let port = request.header("host").unwrap().rsplit(':').next().unwrap().to_owned();
+            Response::status(302).with_header("Location", &format!("http://localhost:{}/openid/", port))
+        },
+        _ => Response::status(200),
+    })
+    .await;
+
+    let error = builder(&server, &cache_path("untrusted"))
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap_err();
+    match &error {
+        OpenIDClientError::UntrustedHost { url } => assert_eq!(url.host_str(), Some("localhost")),
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert_eq!(server.requests().len(), 1);
+
+    // the host can be trusted explicitly
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/login" => {
+            let port = request.header("host").unwrap().rsplit(':').next().unwrap().to_owned();
+            Response::status(302).with_header("Location", &format!("http://localhost:{}/openid/", port))
+        },
+        _ => Response::status(500),
+    })
+    .await;
+
+    let error = builder(&server, &cache_path("trusted"))
+        .trusted_host("localhost")
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap_err();
+    assert!(!matches!(error, OpenIDClientError::UntrustedHost { .. }), "{:?}", error);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn redirects_from_https_to_http_are_not_followed() {
+    let insecure = MockServer::start(|_| Response::status(200)).await;
+    let location = insecure.url_for("openid/").to_string();
+    let server = MockServer::start_tls(move |_| Response::status(302).with_header("Location", &location)).await;
+
+    let kind = OpenIDSessionKind::Custom {
+        auth_url: server.url_for("openid/"),
+    };
+    let error = Session::openid_auth(server.url_for("login"), kind)
+        .cookie_cache(CookieCache::path(cache_path("downgrade")))
+        .ca_bundle(CaBundle::from_pem(fixture("tls/ca.pem").as_bytes()).unwrap())
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap_err();
+    match &error {
+        OpenIDClientError::InsecureTransport { url } => assert_eq!(url.scheme(), "http"),
+        _ => panic!("unexpected error: {:?}", error),
+    }
+
+    assert_eq!(server.requests().len(), 1);
+    assert!(insecure.requests().is_empty());
+}
+
+/// This helper function logs in once (so the cookie cache contains a fresh session cookie), and
+/// then builds another session from the cached cookies, which are checked with the given
+/// validation URL. It returns the requests that were sent for the second session.
+async fn login_with_cache(server: &MockServer, name: &str, validation_url: Url) -> Vec<Request> {
+    let cache = cache_path(name);
+
+    builder(server, &cache)
+        .insecure_loopback(true)
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap();
+    let before = server.requests().len();
+
+    builder(server, &cache)
+        .insecure_loopback(true)
+        .validation_url(validation_url)
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap();
+
+    server.requests().split_off(before)
+}
+
+/// This helper function starts a server that answers requests to "/whoami" with the given
+/// response, and all other requests like [`serve`].
+async fn start_with_whoami(whoami: Response) -> MockServer {
+    MockServer::start(move |request| match request.path.as_str() {
+        "/whoami" => whoami.clone(),
+        _ => serve(request),
+    })
+    .await
+}
+
+fn paths(requests: &[Request]) -> Vec<&str> {
+    requests.iter().map(|request| request.path.as_str()).collect()
+}
+
+#[tokio::test]
+async fn accepted_session_cookies_are_reused() {
+    let server = start_with_whoami(Response::json(r#"{"username": "janedoe"}"#)).await;
+
+    let requests = login_with_cache(&server, "validation-ok", server.url_for("whoami")).await;
+    assert_eq!(paths(&requests), vec!["/whoami"]);
+    assert_eq!(requests[0].header("cookie"), Some("session=SESSION-1"));
+}
+
+#[tokio::test]
+async fn rejected_session_cookies_are_replaced() {
+    for whoami in [
+        Response::status(401),
+        Response::status(302).with_header("Location", "/login?next=%2Fwhoami"),
+    ] {
+        let server = start_with_whoami(whoami).await;
+
+        let requests = login_with_cache(&server, "validation-rejected", server.url_for("whoami")).await;
+        assert_eq!(
+            paths(&requests),
+            vec!["/whoami", "/login", "/openid/", "/openid/", "/login/complete"]
+        );
+    }
+}
+
+#[tokio::test]
+async fn redirects_to_other_pages_are_not_rejections() {
+    // the path contains "login", but it is not the login URL
+    let server = start_with_whoami(Response::status(302).with_header("Location", "/help/login-problems")).await;
+    let cache = cache_path("validation-redirect");
+
+    builder(&server, &cache)
+        .insecure_loopback(true)
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap();
+    let before = server.requests().len();
+
+    // all redirects to the host of the OpenID provider are rejections, so use a different one
+    let kind = OpenIDSessionKind::Custom {
+        auth_url: Url::parse("https://id.example.com/openid/").unwrap(),
+    };
+    Session::openid_auth(server.url_for("login"), kind)
+        .cookie_cache(CookieCache::path(&cache))
+        .validation_url(server.url_for("whoami"))
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap();
+
+    assert_eq!(paths(&server.requests()[before..]), vec!["/whoami"]);
+}
+
+#[tokio::test]
+async fn failed_validation_requests_fall_back_to_logging_in() {
+    let server = MockServer::start(serve).await;
+
+    // nothing is listening on this port after the listener is dropped
+    let unreachable = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        Url::parse(&format!("http://127.0.0.1:{}/whoami", port)).unwrap()
+    };
+
+    let requests = login_with_cache(&server, "validation-unreachable", unreachable).await;
+    assert_eq!(
+        paths(&requests),
+        vec!["/login", "/openid/", "/openid/", "/login/complete"]
+    );
+}
+
+/// This helper function logs in with the given cookie cache, and returns the logged-in session.
+async fn login_to_cache(server: &MockServer, cache: &Path) -> Session {
+    builder(server, cache)
+        .insecure_loopback(true)
+        .build()
+        .login("janedoe", "hunter2")
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn logout_clears_the_cookie_cache() {
+    let server = MockServer::start(serve).await;
+    let cache = cache_path("logout");
+
+    let session = login_to_cache(&server, &cache).await;
+    assert!(std::fs::read_to_string(&cache).unwrap().contains("SESSION-1"));
+
+    session.logout().await.unwrap();
+    assert!(session.cookies().is_empty());
+    assert!(!std::fs::read_to_string(&cache).unwrap().contains("SESSION-1"));
+
+    // the next session has to log in again
+    let before = server.requests().len();
+    login_to_cache(&server, &cache).await;
+    assert_eq!(
+        paths(&server.requests()[before..]),
+        vec!["/login", "/openid/", "/openid/", "/login/complete"]
+    );
+}
+
+#[tokio::test]
+async fn removed_cookies_are_not_restored_from_the_cache() {
+    let server = MockServer::start(serve).await;
+    let cache = cache_path("remove");
+
+    let session = login_to_cache(&server, &cache).await;
+    assert_eq!(session.remove_cookies("127.0.0.1"), 1);
+
+    // the cookie cache still contains the cookie, but it is not merged back into the cache
+    assert!(std::fs::read_to_string(&cache).unwrap().contains("SESSION-1"));
+    session.persist().unwrap();
+    assert!(!std::fs::read_to_string(&cache).unwrap().contains("SESSION-1"));
+
+    // cookies that are set again after removing them are persisted
+    session
+        .send(session.session().post(server.url_for("login/complete")))
+        .await
+        .unwrap();
+    assert_eq!(session.cookies().len(), 1);
+    session.persist().unwrap();
+    assert!(std::fs::read_to_string(&cache).unwrap().contains("SESSION-1"));
+}
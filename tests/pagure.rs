@@ -0,0 +1,181 @@
+//! tests for the Pagure client, against recorded responses served by a local stand-in server
+
+#![cfg(feature = "pagure")]
+#![allow(deprecated)]
+
+mod common;
+
+use common::{MockServer, Response};
+use fedora::pagure::{IssueQuery, PagureClient, PagureError, ProjectQuery, PullRequestQuery};
+use futures_util::TryStreamExt;
+
+#[tokio::test]
+async fn project() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/api/0/rpms/rust-fedora" => Response::fixture("pagure/project.json"),
+        _ => Response::fixture("pagure/not-found.json").with_status(404),
+    })
+    .await;
+    let pagure = server.client(PagureClient::with_url);
+
+    let project = pagure.project("rpms/rust-fedora").await.unwrap().unwrap();
+    assert_eq!(project.fullname, "rpms/rust-fedora");
+    assert_eq!(project.namespace.as_deref(), Some("rpms"));
+    assert_eq!(project.access_users["owner"], vec!["janedoe"]);
+    assert!(project.extra.contains_key("url_path"));
+
+    assert!(pagure.project("rpms/rust-foobar").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn streams_request_all_pages() {
+    let server = MockServer::start(|request| match request.param("page").as_deref() {
+        Some("1") => Response::fixture("pagure/pull-requests-page-1.json"),
+        Some("2") => Response::fixture("pagure/pull-requests-page-2.json"),
+        _ => Response::status(400),
+    })
+    .await;
+    let pagure = server.client(PagureClient::with_url);
+
+    let query = PullRequestQuery::new().per_page(2);
+    let pull_requests: Vec<_> = pagure
+        .pull_requests("rpms/rust-fedora", &query)
+        .try_collect()
+        .await
+        .unwrap();
+
+    let ids: Vec<u64> = pull_requests.iter().map(|pull_request| pull_request.id).collect();
+    assert_eq!(ids, vec![3, 2, 1]);
+    assert_eq!(pull_requests[2].title, "Fix license tag");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(request.path, "/api/0/rpms/rust-fedora/pull-requests");
+        assert_eq!(request.param("per_page").as_deref(), Some("2"));
+    }
+}
+
+#[tokio::test]
+async fn streams_stop_at_empty_pages() {
+    let server = MockServer::start(|_| Response::fixture("pagure/issues-empty.json")).await;
+    let pagure = server.client(PagureClient::with_url);
+
+    let issues: Vec<_> = pagure
+        .issues("fedora-infrastructure", &IssueQuery::new())
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(issues.is_empty());
+
+    // the pagination claims that there are more pages, but an empty page ends the stream
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/api/0/fedora-infrastructure/issues");
+    assert_eq!(requests[0].param("page").as_deref(), Some("1"));
+}
+
+#[tokio::test]
+async fn streams_without_pagination_have_one_page() {
+    let server = MockServer::start(|_| Response::fixture("pagure/projects-unpaginated.json")).await;
+    let pagure = server.client(PagureClient::with_url);
+
+    let query = ProjectQuery::new().namespace("rpms").pattern("rust-fedora*");
+    let projects: Vec<_> = pagure.projects(&query).try_collect().await.unwrap();
+
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].fullname, "rpms/rust-fedora");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/api/0/projects");
+    assert_eq!(requests[0].param("namespace").as_deref(), Some("rpms"));
+}
+
+#[tokio::test]
+async fn errors_end_streams() {
+    let server = MockServer::start(|request| match request.param("page").as_deref() {
+        Some("1") => Response::fixture("pagure/pull-requests-page-1.json"),
+        _ => Response::status(503).with_body("text/html", "<h1>Service Unavailable</h1>"),
+    })
+    .await;
+    let pagure = server.client(PagureClient::with_url);
+
+    let result: Result<Vec<_>, _> = pagure
+        .pull_requests("rpms/rust-fedora", &PullRequestQuery::new())
+        .try_collect()
+        .await;
+
+    let error = result.unwrap_err();
+    assert!(matches!(error, PagureError::Status { .. }), "{:?}", error);
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn names_can_not_change_the_requested_path() {
+    let server = MockServer::start(|_| Response::fixture("pagure/not-found.json").with_status(404)).await;
+    let pagure = server.client(PagureClient::with_url);
+
+    // a colon before the first slash must not be parsed as a URL scheme
+    assert!(pagure.project("javascript:alert").await.unwrap().is_none());
+    assert!(pagure.project("rpms/foo?bar#baz").await.unwrap().is_none());
+    assert!(pagure.issue("fork/jane doe/rpms/foo", 1).await.unwrap().is_none());
+
+    let paths: Vec<String> = server.requests().into_iter().map(|request| request.path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/api/0/javascript:alert",
+            "/api/0/rpms/foo%3Fbar%23baz",
+            "/api/0/fork/jane%20doe/rpms/foo/issue/1",
+        ]
+    );
+    assert!(server.requests().iter().all(|request| request.query.is_none()));
+
+    for project in ["rpms/../../-/whoami", "rpms/./foo", "/rpms/foo", "rpms//foo", ""] {
+        let error = pagure.contributors(project).await.unwrap_err();
+        assert!(matches!(error, PagureError::InvalidName { .. }), "{:?}", error);
+    }
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn comment_on_pull_request() {
+    let server = MockServer::start(|request| match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/api/0/rpms/rust-fedora/pull-request/3/comment") => Response::fixture("pagure/comment.json"),
+        _ => Response::fixture("pagure/not-found.json").with_status(404),
+    })
+    .await;
+
+    let message = server
+        .client(PagureClient::with_url)
+        .comment_on_pull_request("rpms/rust-fedora", 3, "LGTM & thanks!")
+        .await
+        .unwrap();
+    assert_eq!(message, "Comment added");
+
+    let request = &server.requests()[0];
+    assert_eq!(
+        request.header("content-type"),
+        Some("application/x-www-form-urlencoded")
+    );
+    assert_eq!(request.text(), "comment=LGTM+%26+thanks%21");
+}
+
+#[tokio::test]
+async fn invalid_tokens_are_auth_failures() {
+    let server = MockServer::start(|_| Response::fixture("pagure/invalid-token.json").with_status(401)).await;
+
+    let error = server.client(PagureClient::with_url).whoami().await.unwrap_err();
+    match &error {
+        PagureError::Status { code, message, .. } => {
+            assert_eq!(code.as_deref(), Some("EINVALIDTOK"));
+            assert!(message.starts_with("Invalid or expired token."));
+        },
+        _ => panic!("unexpected error: {:?}", error),
+    }
+    assert!(error.is_auth_failure());
+    assert!(!error.is_retryable());
+
+    assert_eq!(server.requests()[0].path, "/api/0/-/whoami");
+}
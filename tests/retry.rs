@@ -0,0 +1,102 @@
+//! tests for retrying requests that failed due to temporary errors, against a local stand-in server
+
+#![allow(deprecated)]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use common::{MockServer, Response};
+use fedora::{RetryPolicy, Session};
+
+/// This helper function starts a server that answers the first `failures` requests with the given
+/// error response, and all later requests with "200 OK".
+async fn start_flaky(failures: usize, error: Response) -> MockServer {
+    let count = AtomicUsize::new(0);
+    MockServer::start(move |_| {
+        if count.fetch_add(1, Ordering::SeqCst) < failures {
+            error.clone()
+        } else {
+            Response::json(r#"{"status": "ok"}"#)
+        }
+    })
+    .await
+}
+
+fn retrying_session(policy: RetryPolicy) -> Session {
+    Session::anonymous()
+        .retry(policy.initial_backoff(Duration::from_millis(10)))
+        .build()
+}
+
+#[tokio::test]
+async fn unavailable_services_are_retried() {
+    let server = start_flaky(1, Response::status(503)).await;
+    let session = retrying_session(RetryPolicy::new());
+
+    let response = session.send(session.session().get(server.url())).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn attempts_are_limited() {
+    let server = start_flaky(usize::MAX, Response::status(503)).await;
+    let session = retrying_session(RetryPolicy::new().max_attempts(3));
+
+    let response = session.send(session.session().get(server.url())).await.unwrap();
+    assert_eq!(response.status(), 503);
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn retry_after_is_honored() {
+    let server = start_flaky(1, Response::status(429).with_header("Retry-After", "1")).await;
+    let session = retrying_session(RetryPolicy::new());
+
+    let start = Instant::now();
+    let response = session.send(session.session().get(server.url())).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(start.elapsed() >= Duration::from_secs(1), "{:?}", start.elapsed());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn long_retry_after_is_not_waited_for() {
+    let server = start_flaky(1, Response::status(503).with_header("Retry-After", "120")).await;
+    let session = retrying_session(RetryPolicy::new().max_backoff(Duration::from_secs(1)));
+
+    let start = Instant::now();
+    let response = session.send(session.session().get(server.url())).await.unwrap();
+    assert_eq!(response.status(), 503);
+    assert!(start.elapsed() < Duration::from_secs(1), "{:?}", start.elapsed());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn non_idempotent_requests_are_only_retried_if_allowed() {
+    let server = start_flaky(1, Response::status(503)).await;
+    let session = retrying_session(RetryPolicy::new());
+
+    let response = session.send(session.session().post(server.url())).await.unwrap();
+    assert_eq!(response.status(), 503);
+    assert_eq!(server.requests().len(), 1);
+
+    let server = start_flaky(1, Response::status(503)).await;
+    let session = retrying_session(RetryPolicy::new().all_methods(true));
+
+    let response = session.send(session.session().post(server.url())).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let server = start_flaky(1, Response::status(404)).await;
+    let session = retrying_session(RetryPolicy::new());
+
+    let response = session.send(session.session().get(server.url())).await.unwrap();
+    assert_eq!(response.status(), 404);
+    assert_eq!(server.requests().len(), 1);
+}
@@ -0,0 +1,64 @@
+//! tests for sessions that are authenticated with static API tokens, against local stand-in servers
+
+#![allow(deprecated)]
+
+mod common;
+
+use common::{MockServer, Response};
+use fedora::{ApiToken, ApiTokenError, Session};
+
+#[tokio::test]
+async fn tokens_are_only_sent_to_the_configured_host() {
+    let service = MockServer::start(|_| Response::json("{}")).await;
+    let other = MockServer::start(|_| Response::json("{}")).await;
+
+    let session = Session::with_token(ApiToken::pagure("SECRET"), &service.url_for("api/0/"))
+        .build()
+        .unwrap();
+
+    for server in [&service, &other] {
+        let response = session
+            .send(session.session().get(server.url_for("api/0/-/whoami")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    // requests sent with the wrapped client directly are not authenticated
+    session.session().get(service.url()).send().await.unwrap();
+
+    let requests = service.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].header("authorization"), Some("token SECRET"));
+    assert_eq!(requests[1].header("authorization"), None);
+
+    assert_eq!(other.requests()[0].header("authorization"), None);
+}
+
+#[tokio::test]
+async fn copr_tokens_use_basic_authentication() {
+    let service = MockServer::start(|_| Response::json("{}")).await;
+
+    let session = Session::with_token(ApiToken::copr("LOGIN", "SECRET"), &service.url())
+        .build()
+        .unwrap();
+    session
+        .send(session.session().post(service.url_for("api_3/project/add/janedoe")))
+        .await
+        .unwrap();
+
+    // base64("LOGIN:SECRET")
+    assert_eq!(
+        service.requests()[0].header("authorization"),
+        Some("Basic TE9HSU46U0VDUkVU")
+    );
+}
+
+#[test]
+fn invalid_tokens_are_rejected() {
+    let url = url::Url::parse("https://pagure.io/").unwrap();
+    let error = Session::with_token(ApiToken::bearer("SECRET\nX-Injected: true"), &url)
+        .build()
+        .unwrap_err();
+    assert!(matches!(error, ApiTokenError::InvalidToken), "{:?}", error);
+}